@@ -0,0 +1,7 @@
+drop index idx_chat_tags_tag_id;
+drop table chat_tags;
+drop table tags;
+
+alter table chat_messages drop column starred;
+alter table chats drop column starred;
+alter table chats drop column pinned;
@@ -0,0 +1,21 @@
+alter table chats add column pinned integer not null default 0 check (pinned in (0, 1));
+alter table chats add column starred integer not null default 0 check (starred in (0, 1));
+alter table chat_messages add column starred integer not null default 0 check (starred in (0, 1));
+
+create table tags (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id text primary key not null,
+    name text not null,
+    constraint uq_tags_name unique (name)
+);
+
+create table chat_tags (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    chat_id text not null,
+    tag_id text not null,
+    primary key (chat_id, tag_id),
+    constraint fk_chat_tags_chats_chat_id foreign key (chat_id) references chats(id) on delete cascade,
+    constraint fk_chat_tags_tags_tag_id foreign key (tag_id) references tags(id) on delete cascade
+);
+
+create index idx_chat_tags_tag_id on chat_tags(tag_id);
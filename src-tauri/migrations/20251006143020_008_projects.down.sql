@@ -0,0 +1,6 @@
+drop trigger tr_projects_set_updated_at;
+drop index idx_chats_project_id;
+alter table chats drop column project_id;
+drop index idx_project_documents_project_id;
+drop table project_documents;
+drop table projects;
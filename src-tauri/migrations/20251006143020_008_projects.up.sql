@@ -0,0 +1,34 @@
+create table projects (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    updated_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id text primary key not null,
+    name text not null,
+    system_prompt text not null default '',
+    default_agent_id text null,
+    constraint fk_projects_agents_default_agent_id foreign key (default_agent_id) references agents(id) on delete set null
+);
+
+create table project_documents (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id text primary key not null,
+    project_id text not null,
+    name text not null,
+    content text not null,
+    constraint fk_project_documents_projects_project_id foreign key (project_id) references projects(id) on delete cascade
+);
+
+create index idx_project_documents_project_id on project_documents(project_id);
+
+alter table chats add column project_id text null references projects(id) on delete set null;
+
+create index idx_chats_project_id on chats(project_id);
+
+create trigger tr_projects_set_updated_at
+after update on projects
+for each row
+when new.updated_at = old.updated_at
+begin
+    update projects
+    set updated_at = (cast(unixepoch('now', 'subsecond') * 1000 as integer))
+    where rowid = new.rowid;
+end;
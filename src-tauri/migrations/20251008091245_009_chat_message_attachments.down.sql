@@ -0,0 +1,7 @@
+drop index idx_chat_message_attachments_message_id;
+drop table chat_message_attachments;
+
+delete from agents where id = X'afe068203df84310beb1c7d63b26f974';
+delete from agents where id = X'51b8862dde0b4f9e86359767e72b1418';
+
+alter table agents drop column vision;
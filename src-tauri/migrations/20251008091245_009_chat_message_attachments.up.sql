@@ -0,0 +1,20 @@
+alter table agents add column vision integer not null default 0 check (vision in (0, 1));
+
+update agents set vision = 1 where provider = 'google';
+
+insert into agents (id, provider, model, vision) values (X'afe068203df84310beb1c7d63b26f974', 'groq', 'meta-llama/llama-4-scout-17b-16e-instruct', 1);
+insert into agents (id, provider, model, vision) values (X'51b8862dde0b4f9e86359767e72b1418', 'groq', 'meta-llama/llama-4-maverick-17b-128e-instruct', 1);
+
+create table chat_message_attachments (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id text primary key not null,
+    message_id text not null,
+    name text null,
+    mime_type text not null,
+    data blob null,
+    path text null,
+    check (data is not null or path is not null),
+    constraint fk_chat_message_attachments_chat_messages_message_id foreign key (message_id) references chat_messages(id) on delete cascade
+);
+
+create index idx_chat_message_attachments_message_id on chat_message_attachments(message_id);
@@ -0,0 +1,3 @@
+delete from chat_message_attachments where kind != 'image';
+alter table chat_message_attachments drop column text;
+alter table chat_message_attachments drop column kind;
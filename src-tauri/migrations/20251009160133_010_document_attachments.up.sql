@@ -0,0 +1,2 @@
+alter table chat_message_attachments add column kind text not null default 'image' check (kind in ('image', 'document'));
+alter table chat_message_attachments add column text text null;
@@ -0,0 +1,6 @@
+alter table agents add column capability text not null default 'chat' check (capability in ('chat', 'transcription'));
+
+update agents set capability = 'transcription' where provider = 'groq' and model in ('whisper-large-v3', 'whisper-large-v3-turbo');
+
+delete from current_agent where agent_id in (select id from agents where capability = 'transcription');
+update projects set default_agent_id = null where default_agent_id in (select id from agents where capability = 'transcription');
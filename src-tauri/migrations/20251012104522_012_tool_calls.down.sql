@@ -0,0 +1,5 @@
+delete from chat_messages where kind = 'tool_result';
+alter table chat_messages drop column tool_name;
+alter table chat_messages drop column tool_call_id;
+alter table chat_messages drop column tool_calls;
+alter table chat_messages drop column kind;
@@ -0,0 +1,4 @@
+alter table chat_messages add column kind text not null default 'text' check (kind in ('text', 'tool_call', 'tool_result'));
+alter table chat_messages add column tool_calls text null;
+alter table chat_messages add column tool_call_id text null;
+alter table chat_messages add column tool_name text null;
@@ -0,0 +1,2 @@
+drop trigger tr_mcp_servers_set_updated_at;
+drop table mcp_servers;
@@ -0,0 +1,21 @@
+create table mcp_servers (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    updated_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id text primary key not null,
+    name text not null,
+    command text not null,
+    args text not null default '[]',
+    env text not null default '{}',
+    enabled integer not null default 1 check (enabled in (0, 1)),
+    constraint uq_mcp_servers_name unique (name)
+);
+
+create trigger tr_mcp_servers_set_updated_at
+after update on mcp_servers
+for each row
+when new.updated_at = old.updated_at
+begin
+    update mcp_servers
+    set updated_at = (cast(unixepoch('now', 'subsecond') * 1000 as integer))
+    where rowid = new.rowid;
+end;
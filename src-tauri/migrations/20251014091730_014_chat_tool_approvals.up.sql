@@ -0,0 +1,7 @@
+create table chat_tool_approvals (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    chat_id text not null,
+    tool_name text not null,
+    primary key (chat_id, tool_name),
+    constraint fk_chat_tool_approvals_chats_chat_id foreign key (chat_id) references chats(id) on delete cascade
+);
@@ -0,0 +1,2 @@
+drop table agent_disabled_tools;
+drop table tool_directories;
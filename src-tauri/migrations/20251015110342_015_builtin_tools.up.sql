@@ -0,0 +1,14 @@
+create table tool_directories (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id text primary key not null,
+    path text not null,
+    constraint uq_tool_directories_path unique (path)
+);
+
+create table agent_disabled_tools (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    agent_id text not null,
+    tool_name text not null,
+    primary key (agent_id, tool_name),
+    constraint fk_agent_disabled_tools_agents_agent_id foreign key (agent_id) references agents(id) on delete cascade
+);
@@ -0,0 +1 @@
+alter table chat_messages drop column schema_errors;
@@ -0,0 +1 @@
+alter table chat_messages add column schema_errors text null;
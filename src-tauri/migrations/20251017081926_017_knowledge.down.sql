@@ -0,0 +1,4 @@
+alter table chat_messages drop column citations;
+drop table knowledge_chunks;
+drop table knowledge_files;
+drop table knowledge_folders;
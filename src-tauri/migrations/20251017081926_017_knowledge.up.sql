@@ -0,0 +1,57 @@
+create table knowledge_folders (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    updated_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id text primary key not null,
+    path text not null,
+    agent_id text not null,
+    embedding_model text not null,
+    indexed_at integer null,
+    constraint uq_knowledge_folders_path unique (path),
+    constraint fk_knowledge_folders_agents_agent_id foreign key (agent_id) references agents(id) on delete cascade
+);
+
+create table knowledge_files (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    updated_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id text primary key not null,
+    folder_id text not null,
+    path text not null,
+    size integer not null,
+    modified_at integer not null,
+    constraint uq_knowledge_files_folder_id_path unique (folder_id, path),
+    constraint fk_knowledge_files_knowledge_folders_folder_id foreign key (folder_id) references knowledge_folders(id) on delete cascade
+);
+
+create table knowledge_chunks (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id text primary key not null,
+    file_id text not null,
+    ordinal integer not null,
+    content text not null,
+    embedding blob not null,
+    constraint fk_knowledge_chunks_knowledge_files_file_id foreign key (file_id) references knowledge_files(id) on delete cascade
+);
+
+create index idx_knowledge_chunks_file_id on knowledge_chunks(file_id);
+
+alter table chat_messages add column citations text null;
+
+create trigger tr_knowledge_folders_set_updated_at
+after update on knowledge_folders
+for each row
+when new.updated_at = old.updated_at
+begin
+    update knowledge_folders
+    set updated_at = (cast(unixepoch('now', 'subsecond') * 1000 as integer))
+    where rowid = new.rowid;
+end;
+
+create trigger tr_knowledge_files_set_updated_at
+after update on knowledge_files
+for each row
+when new.updated_at = old.updated_at
+begin
+    update knowledge_files
+    set updated_at = (cast(unixepoch('now', 'subsecond') * 1000 as integer))
+    where rowid = new.rowid;
+end;
@@ -0,0 +1,3 @@
+drop trigger tr_chat_messages_bump_content_revision;
+drop table chat_message_embeddings;
+alter table chat_messages drop column content_revision;
@@ -0,0 +1,20 @@
+alter table chat_messages add column content_revision integer not null default 0;
+
+create table chat_message_embeddings (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    message_id text primary key not null,
+    embedding_model text not null,
+    content_revision integer not null,
+    embedding blob not null,
+    constraint fk_chat_message_embeddings_chat_messages_message_id foreign key (message_id) references chat_messages(id) on delete cascade
+);
+
+create trigger tr_chat_messages_bump_content_revision
+after update of content on chat_messages
+for each row
+when new.content is not old.content
+begin
+    update chat_messages
+    set content_revision = old.content_revision + 1
+    where rowid = new.rowid;
+end;
@@ -0,0 +1,15 @@
+drop table chat_message_speeches;
+
+delete from chat_message_attachments where kind = 'audio';
+alter table chat_message_attachments add column kind_old text not null default 'image' check (kind_old in ('image', 'document'));
+update chat_message_attachments set kind_old = kind;
+alter table chat_message_attachments drop column kind;
+alter table chat_message_attachments rename column kind_old to kind;
+
+delete from current_agent where agent_id in (select id from agents where capability = 'speech');
+update projects set default_agent_id = null where default_agent_id in (select id from agents where capability = 'speech');
+delete from agents where capability = 'speech';
+alter table agents add column capability_old text not null default 'chat' check (capability_old in ('chat', 'transcription'));
+update agents set capability_old = capability;
+alter table agents drop column capability;
+alter table agents rename column capability_old to capability;
@@ -0,0 +1,25 @@
+alter table agents add column capability_new text not null default 'chat' check (capability_new in ('chat', 'transcription', 'speech'));
+update agents set capability_new = capability;
+alter table agents drop column capability;
+alter table agents rename column capability_new to capability;
+
+insert into agents (id, provider, model, capability) values (X'9ffadb27f8014b84bb2f286ae9ea4a46', 'groq', 'playai-tts', 'speech');
+insert into agents (id, provider, model, capability) values (X'83614bd7526241edb3dde37a24ad2b40', 'groq', 'playai-tts-arabic', 'speech');
+
+alter table chat_message_attachments add column kind_new text not null default 'image' check (kind_new in ('image', 'document', 'audio'));
+update chat_message_attachments set kind_new = kind;
+alter table chat_message_attachments drop column kind;
+alter table chat_message_attachments rename column kind_new to kind;
+
+create table chat_message_speeches (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    attachment_id text primary key not null,
+    message_id text not null,
+    agent_id text not null,
+    voice text not null,
+    content_revision integer not null,
+    constraint uq_chat_message_speeches_message_id_agent_id_voice unique (message_id, agent_id, voice),
+    constraint fk_chat_message_speeches_chat_message_attachments_attachment_id foreign key (attachment_id) references chat_message_attachments(id) on delete cascade,
+    constraint fk_chat_message_speeches_chat_messages_message_id foreign key (message_id) references chat_messages(id) on delete cascade,
+    constraint fk_chat_message_speeches_agents_agent_id foreign key (agent_id) references agents(id) on delete cascade
+);
@@ -0,0 +1,2 @@
+drop trigger tr_chat_encryption_set_updated_at;
+drop table chat_encryption;
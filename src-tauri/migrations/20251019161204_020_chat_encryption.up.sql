@@ -0,0 +1,18 @@
+create table chat_encryption (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    updated_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id integer not null default 1 primary key check(id = 1),
+    enabled integer not null default 0 check (enabled in (0, 1))
+);
+
+insert into chat_encryption (id, enabled) values (1, 0);
+
+create trigger tr_chat_encryption_set_updated_at
+after update on chat_encryption
+for each row
+when new.updated_at = old.updated_at
+begin
+    update chat_encryption
+    set updated_at = (cast(unixepoch('now', 'subsecond') * 1000 as integer))
+    where rowid = new.rowid;
+end;
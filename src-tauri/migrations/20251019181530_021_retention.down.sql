@@ -0,0 +1,3 @@
+drop trigger tr_retention_policy_set_updated_at;
+drop table retention_policy;
+alter table chats drop column archived_at;
@@ -0,0 +1,25 @@
+alter table chats add column archived_at integer null;
+
+create table retention_policy (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    updated_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id integer not null default 1 primary key check(id = 1),
+    enabled integer not null default 0 check (enabled in (0, 1)),
+    max_age_days integer null check (max_age_days is null or max_age_days > 0),
+    action text not null default 'archive' check (action in ('archive', 'delete')),
+    keep_pinned integer not null default 1 check (keep_pinned in (0, 1)),
+    max_database_bytes integer null check (max_database_bytes is null or max_database_bytes > 0),
+    last_run_at integer null
+);
+
+insert into retention_policy (id) values (1);
+
+create trigger tr_retention_policy_set_updated_at
+after update on retention_policy
+for each row
+when new.updated_at = old.updated_at
+begin
+    update retention_policy
+    set updated_at = (cast(unixepoch('now', 'subsecond') * 1000 as integer))
+    where rowid = new.rowid;
+end;
@@ -0,0 +1,4 @@
+drop table sync_cursors;
+drop table sync_entities;
+drop trigger tr_sync_state_set_updated_at;
+drop table sync_state;
@@ -0,0 +1,34 @@
+create table sync_state (
+    created_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    updated_at integer not null default (cast(unixepoch('now', 'subsecond') * 1000 as integer)),
+    id integer not null default 1 primary key check(id = 1),
+    device_id text not null,
+    folder text null,
+    last_synced_at integer null
+);
+
+insert into sync_state (id, device_id) values (1, randomblob(16));
+
+create trigger tr_sync_state_set_updated_at
+after update on sync_state
+for each row
+when new.updated_at = old.updated_at
+begin
+    update sync_state
+    set updated_at = (cast(unixepoch('now', 'subsecond') * 1000 as integer))
+    where rowid = new.rowid;
+end;
+
+create table sync_entities (
+    entity text not null check (entity in ('chat', 'chat_message', 'agent_config')),
+    entity_id text not null,
+    fingerprint text null,
+    changed_at integer not null,
+    device_id text not null,
+    primary key (entity, entity_id)
+);
+
+create table sync_cursors (
+    device_id text not null primary key,
+    line integer not null
+);
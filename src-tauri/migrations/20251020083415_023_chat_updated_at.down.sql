@@ -0,0 +1,4 @@
+drop trigger tr_chat_messages_set_updated_at;
+alter table chat_messages drop column updated_at;
+drop trigger tr_chats_set_updated_at;
+alter table chats drop column updated_at;
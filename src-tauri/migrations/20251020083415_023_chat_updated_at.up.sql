@@ -0,0 +1,27 @@
+alter table chats add column updated_at integer not null default 0;
+
+update chats set updated_at = created_at;
+
+create trigger tr_chats_set_updated_at
+after update on chats
+for each row
+when new.updated_at = old.updated_at
+begin
+    update chats
+    set updated_at = (cast(unixepoch('now', 'subsecond') * 1000 as integer))
+    where rowid = new.rowid;
+end;
+
+alter table chat_messages add column updated_at integer not null default 0;
+
+update chat_messages set updated_at = created_at;
+
+create trigger tr_chat_messages_set_updated_at
+after update on chat_messages
+for each row
+when new.updated_at = old.updated_at
+begin
+    update chat_messages
+    set updated_at = (cast(unixepoch('now', 'subsecond') * 1000 as integer))
+    where rowid = new.rowid;
+end;
@@ -3,11 +3,16 @@ pub mod google;
 pub mod groq;
 pub mod repo;
 
-use std::{pin::Pin, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    pin::Pin,
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use futures_util::Stream;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 
 use crate::{
@@ -16,9 +21,21 @@ use crate::{
         groq::{GroqAgent, GroqTextGenParams},
         repo::AgentRepo,
     },
-    chat::repo::ChatRepo,
+    chat::{
+        document::{render_document, DOCUMENT_CONTEXT_CHARS},
+        repo::ChatRepo,
+    },
     cipher::Cipher,
-    common::{entity::agent::AgentRow, error::AppError, http::HttpClientManager},
+    common::{
+        entity::{
+            agent::AgentRow,
+            chat::{ChatMessageAttachmentKind, ChatToolCall},
+        },
+        error::AppError,
+        http::HttpClientManager,
+    },
+    project::{build_project_instructions, repo::ProjectRepo},
+    tool::{ToolDefinition, ToolRegistry},
 };
 
 #[derive(Clone, sqlx::Type, Serialize)]
@@ -29,6 +46,16 @@ pub enum AgentProvider {
     Groq,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AgentCapability {
+    Chat,
+    Transcription,
+    Speech,
+}
+
+#[derive(Clone)]
 pub enum Agent {
     Gemini(GoogleAgent),
     Groq(GroqAgent),
@@ -39,10 +66,6 @@ pub enum AgentTextGenParams {
     Groq(GroqTextGenParams),
 }
 
-pub trait AgentTextGenParamsApi {
-    fn push_message_str(&mut self, message: &str);
-}
-
 #[async_trait]
 pub trait AgentApi {
     type TextGenParams;
@@ -57,18 +80,140 @@ pub trait AgentApi {
         &self,
         context: AgentContext,
         chat_id: Uuid,
+        options: AgentTextGenOptions,
     ) -> Result<Option<Self::TextGenParams>, AppError>;
 }
 
+#[derive(Clone, Default, Debug)]
+pub struct AgentTextGenOptions {
+    /// Asks for a JSON response conforming to this schema. Tools are not offered in this
+    /// mode, since neither provider combines them reliably with a response format.
+    pub response_schema: Option<Value>,
+    /// Retrieved excerpts from the user's knowledge folders, appended to the system
+    /// instructions.
+    pub knowledge: Option<String>,
+}
+
+impl AgentTextGenOptions {
+    pub fn with_knowledge(&self, instructions: Option<String>) -> Option<String> {
+        match (instructions, self.knowledge.clone()) {
+            (Some(instructions), Some(knowledge)) => {
+                Some(format!("{}\n\n{}", instructions, knowledge))
+            }
+            (instructions, knowledge) => instructions.or(knowledge),
+        }
+    }
+}
+
 #[derive(Serialize, Clone, Debug)]
 pub struct AgentTextGenResult {
     pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<AgentToolCallDelta>,
+    /// Images generated by the model, e.g. Gemini `inlineData` parts.
+    #[serde(skip)]
+    pub images: Vec<AgentTextGenAttachment>,
+}
+
+/// A fragment of a tool call as it arrives in the response stream. Fragments sharing an
+/// `index` belong to the same call and their `arguments` are concatenated.
+#[derive(Serialize, Clone, Debug)]
+pub struct AgentToolCallDelta {
+    pub index: usize,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub arguments: String,
+}
+
+#[derive(Default)]
+pub struct AgentToolCalls {
+    calls: BTreeMap<usize, AgentToolCallBuilder>,
+}
+
+#[derive(Default)]
+struct AgentToolCallBuilder {
+    id: Option<String>,
+    name: String,
+    arguments: String,
+}
+
+impl AgentToolCalls {
+    pub fn push(&mut self, delta: AgentToolCallDelta) {
+        let call = self.calls.entry(delta.index).or_default();
+        if delta.id.is_some() {
+            call.id = delta.id;
+        }
+        if let Some(name) = delta.name {
+            call.name.push_str(&name);
+        }
+        call.arguments.push_str(&delta.arguments);
+    }
+
+    pub fn finish(self) -> Vec<ChatToolCall> {
+        self.calls
+            .into_values()
+            .map(|a| ChatToolCall {
+                id: a
+                    .id
+                    .unwrap_or_else(|| format!("call_{}", Uuid::new_v4().simple())),
+                name: a.name,
+                arguments: match a.arguments.trim() {
+                    "" => Value::Object(Default::default()),
+                    arguments => serde_json::from_str(arguments)
+                        .unwrap_or_else(|_| Value::String(arguments.to_string())),
+                },
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AgentTextGenAttachment {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+pub struct AgentTranscriptionParams {
+    pub file_name: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+    pub language: Option<String>,
+    pub prompt: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AgentTranscriptionResult {
+    pub text: String,
+}
+
+pub struct AgentSpeechParams {
+    pub input: String,
+    pub voice: String,
+}
+
+pub struct AgentSpeechResult {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AgentEmbeddingTask {
+    Document,
+    Query,
+}
+
+pub struct AgentEmbeddingParams {
+    pub model: String,
+    pub texts: Vec<String>,
+    pub task: AgentEmbeddingTask,
 }
 
 pub struct AgentContext {
     pub http_client_manager: Arc<HttpClientManager>,
     pub agent_repo: Arc<dyn AgentRepo>,
     pub chat_repo: Arc<dyn ChatRepo>,
+    pub project_repo: Arc<dyn ProjectRepo>,
+    pub tool_registry: Arc<ToolRegistry>,
     pub cipher: Arc<dyn Cipher>,
 }
 
@@ -77,15 +222,112 @@ impl AgentContext {
         http_client_manager: Arc<HttpClientManager>,
         agent_repo: Arc<dyn AgentRepo>,
         chat_repo: Arc<dyn ChatRepo>,
+        project_repo: Arc<dyn ProjectRepo>,
+        tool_registry: Arc<ToolRegistry>,
         cipher: Arc<dyn Cipher>,
     ) -> Self {
         Self {
             http_client_manager,
             agent_repo,
             chat_repo,
+            project_repo,
+            tool_registry,
             cipher,
         }
     }
+
+    pub async fn get_chat_instructions(&self, chat_id: Uuid) -> Result<Option<String>, AppError> {
+        let project_id = match self
+            .chat_repo
+            .get_chat(chat_id)
+            .await?
+            .and_then(|a| a.project_id)
+        {
+            Some(project_id) => project_id,
+            None => return Ok(None),
+        };
+        let project = match self.project_repo.get_project(project_id).await? {
+            Some(project) => project,
+            None => return Ok(None),
+        };
+        let documents = self.project_repo.get_project_documents(project.id).await?;
+        Ok(build_project_instructions(&project, &documents))
+    }
+
+    /// The registered tools minus those the user switched off for this agent.
+    pub async fn get_agent_tools(&self, agent_id: Uuid) -> Result<Vec<ToolDefinition>, AppError> {
+        let disabled = self.agent_repo.get_agent_disabled_tools(agent_id).await?;
+        Ok(self
+            .tool_registry
+            .definitions()
+            .await
+            .into_iter()
+            .filter(|a| !disabled.contains(&a.name))
+            .collect())
+    }
+
+    pub async fn get_chat_attachments(
+        &self,
+        chat_id: Uuid,
+    ) -> Result<HashMap<Uuid, Vec<AgentTextGenAttachment>>, AppError> {
+        let mut attachments = HashMap::<Uuid, Vec<AgentTextGenAttachment>>::new();
+        for row in self.chat_repo.get_chat_attachments(chat_id).await? {
+            if row.kind != ChatMessageAttachmentKind::Image {
+                continue;
+            }
+            let data = match (row.data, row.path) {
+                (Some(data), _) => data,
+                (None, Some(path)) => tokio::fs::read(&path).await.map_err(AppError::from)?,
+                (None, None) => continue,
+            };
+            attachments
+                .entry(row.message_id)
+                .or_default()
+                .push(AgentTextGenAttachment {
+                    mime_type: row.mime_type,
+                    data,
+                });
+        }
+        Ok(attachments)
+    }
+
+    /// Renders the document attachments of each message, spending the character budget
+    /// on the newest messages first so older documents are the ones that get cut.
+    pub async fn get_chat_documents(
+        &self,
+        chat_id: Uuid,
+    ) -> Result<HashMap<Uuid, String>, AppError> {
+        let rows = self.chat_repo.get_chat_attachments(chat_id).await?;
+        let mut budget = DOCUMENT_CONTEXT_CHARS;
+        let mut documents = HashMap::<Uuid, Vec<String>>::new();
+        for row in rows.into_iter().rev() {
+            let text = match (row.kind, row.text) {
+                (ChatMessageAttachmentKind::Document, Some(text)) => text,
+                _ => continue,
+            };
+            let name = row.name.unwrap_or_else(|| row.id.to_string());
+            let (rendered, included) = render_document(&name, &text, budget);
+            if included < text.chars().count() {
+                log::warn!("document {} truncated to {} characters", name, included);
+            }
+            budget -= included;
+            documents.entry(row.message_id).or_default().push(rendered);
+        }
+        Ok(documents
+            .into_iter()
+            .map(|(message_id, mut rendered)| {
+                rendered.reverse();
+                (message_id, rendered.join("\n\n"))
+            })
+            .collect())
+    }
+}
+
+pub fn with_documents(content: String, documents: Option<String>) -> String {
+    match documents {
+        Some(documents) => format!("{}\n\n{}", documents, content),
+        None => content,
+    }
 }
 
 impl Clone for AgentContext {
@@ -94,6 +336,8 @@ impl Clone for AgentContext {
             http_client_manager: Arc::clone(&self.http_client_manager),
             agent_repo: Arc::clone(&self.agent_repo),
             chat_repo: Arc::clone(&self.chat_repo),
+            project_repo: Arc::clone(&self.project_repo),
+            tool_registry: Arc::clone(&self.tool_registry),
             cipher: Arc::clone(&self.cipher),
         }
     }
@@ -105,15 +349,66 @@ impl From<AgentRow> for Agent {
             AgentProvider::Google => Self::Gemini(GoogleAgent {
                 id: row.id,
                 model: row.model,
+                vision: row.vision,
             }),
             AgentProvider::Groq => Self::Groq(GroqAgent {
                 id: row.id,
                 model: row.model,
+                vision: row.vision,
             }),
         }
     }
 }
 
+impl Agent {
+    pub fn id(&self) -> Uuid {
+        match self {
+            Agent::Gemini(agent) => agent.id,
+            Agent::Groq(agent) => agent.id,
+        }
+    }
+
+    pub async fn transcribe_audio(
+        self,
+        context: AgentContext,
+        params: AgentTranscriptionParams,
+    ) -> Result<AgentTranscriptionResult, AppError> {
+        match self {
+            Agent::Groq(agent) => agent.transcribe_audio(context, params).await,
+            Agent::Gemini(agent) => Err(AppError::AgentCapabilityUnsupported(agent.model)),
+        }
+    }
+
+    pub fn default_speech_voice(&self) -> Option<&'static str> {
+        match self {
+            Agent::Groq(agent) => agent.default_speech_voice(),
+            Agent::Gemini(_) => None,
+        }
+    }
+
+    pub async fn synthesize_speech(
+        self,
+        context: AgentContext,
+        params: AgentSpeechParams,
+    ) -> Result<AgentSpeechResult, AppError> {
+        match self {
+            Agent::Groq(agent) => agent.synthesize_speech(context, params).await,
+            Agent::Gemini(agent) => Err(AppError::AgentCapabilityUnsupported(agent.model)),
+        }
+    }
+
+    pub async fn embed_texts(
+        self,
+        context: AgentContext,
+        params: AgentEmbeddingParams,
+    ) -> Result<Vec<Vec<f32>>, AppError> {
+        match self {
+            Agent::Gemini(agent) => agent.embed_texts(context, params).await,
+            Agent::Groq(agent) => Err(AppError::AgentCapabilityUnsupported(agent.model)),
+        }
+    }
+}
+
 #[async_trait]
 impl AgentApi for Agent {
     type TextGenParams = AgentTextGenParams;
@@ -139,29 +434,17 @@ impl AgentApi for Agent {
         &self,
         context: AgentContext,
         chat_id: Uuid,
+        options: AgentTextGenOptions,
     ) -> Result<Option<Self::TextGenParams>, AppError> {
         match self {
             Agent::Gemini(agent) => agent
-                .create_text_gen_params(context, chat_id)
+                .create_text_gen_params(context, chat_id, options)
                 .await
                 .map(|a| a.map(|a| AgentTextGenParams::Google(a))),
             Agent::Groq(agent) => agent
-                .create_text_gen_params(context, chat_id)
+                .create_text_gen_params(context, chat_id, options)
                 .await
                 .map(|a| a.map(|a| AgentTextGenParams::Groq(a))),
         }
     }
 }
-
-impl AgentTextGenParamsApi for AgentTextGenParams {
-    fn push_message_str(&mut self, message: &str) {
-        match self {
-            AgentTextGenParams::Google(params) => {
-                params.push_message_str(message);
-            }
-            AgentTextGenParams::Groq(params) => {
-                params.push_message_str(message);
-            }
-        }
-    }
-}
@@ -1,15 +1,22 @@
-use serde::Deserialize;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tauri::State;
 use uuid::Uuid;
 
 use crate::{
-    agent::repo::{AgentRepo, UpdateCurrentAgent, UpsertAgentConfig},
+    agent::{
+        repo::{AgentRepo, UpdateCurrentAgent, UpsertAgentConfig},
+        Agent, AgentCapability, AgentContext, AgentTranscriptionParams, AgentTranscriptionResult,
+    },
     cipher::Cipher,
     common::{
         entity::agent::{AgentConfigRow, AgentRow},
         error::AppError,
     },
+    tool::ToolRegistry,
 };
 
 #[derive(Deserialize)]
@@ -17,11 +24,21 @@ pub struct UpsertAgentConfigCmd {
     pub api_key: Option<String>,
 }
 
+#[derive(Serialize)]
+pub struct AgentTool {
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+}
+
 #[tauri::command]
 pub async fn get_agents(
+    capability: Option<AgentCapability>,
     agent_repo: State<'_, Arc<dyn AgentRepo>>,
 ) -> Result<Vec<AgentRow>, AppError> {
-    agent_repo.get_agents().await
+    agent_repo
+        .get_agents(Some(capability.unwrap_or(AgentCapability::Chat)))
+        .await
 }
 
 #[tauri::command]
@@ -36,6 +53,13 @@ pub async fn update_current_agent(
     agent_id: Uuid,
     agent_repo: State<'_, Arc<dyn AgentRepo>>,
 ) -> Result<(), AppError> {
+    let agent = agent_repo
+        .get_agent(agent_id)
+        .await?
+        .ok_or_else(|| AppError::AgentRequired)?;
+    if agent.capability != AgentCapability::Chat {
+        return Err(AppError::AgentCapabilityUnsupported(agent.model));
+    }
     agent_repo
         .update_current_agent(UpdateCurrentAgent { agent_id })
         .await?;
@@ -84,3 +108,85 @@ pub async fn decrypt_agent_ciphertext(
 ) -> Result<String, AppError> {
     cipher.decrypt_base64_str(&ciphertext)
 }
+
+#[tauri::command]
+pub async fn transcribe_audio(
+    agent_id: Uuid,
+    path: PathBuf,
+    language: Option<String>,
+    prompt: Option<String>,
+    agent_context: State<'_, AgentContext>,
+) -> Result<AgentTranscriptionResult, AppError> {
+    let agent = agent_context
+        .agent_repo
+        .get_agent(agent_id)
+        .await?
+        .ok_or_else(|| AppError::AgentRequired)?;
+    if agent.capability != AgentCapability::Transcription {
+        return Err(AppError::AgentCapabilityUnsupported(agent.model));
+    }
+    let file_name = path
+        .file_name()
+        .map(|a| a.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mime_type = guess_audio_mime_type(&file_name)
+        .ok_or_else(|| AppError::UnsupportedAttachment(file_name.clone()))?;
+    let data = tokio::fs::read(&path).await.map_err(AppError::from)?;
+    Agent::from(agent)
+        .transcribe_audio(
+            agent_context.inner().clone(),
+            AgentTranscriptionParams {
+                file_name,
+                mime_type: mime_type.to_string(),
+                data,
+                language: language.filter(|a| !a.trim().is_empty()),
+                prompt: prompt.filter(|a| !a.trim().is_empty()),
+            },
+        )
+        .await
+}
+
+fn guess_audio_mime_type(name: &str) -> Option<&'static str> {
+    let extension = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "flac" => "audio/flac",
+        "mp3" | "mpga" | "mpeg" => "audio/mpeg",
+        "mp4" => "audio/mp4",
+        "m4a" => "audio/m4a",
+        "ogg" | "opus" => "audio/ogg",
+        "wav" => "audio/wav",
+        "webm" => "audio/webm",
+        _ => return None,
+    })
+}
+
+#[tauri::command]
+pub async fn get_agent_tools(
+    agent_id: Uuid,
+    agent_repo: State<'_, Arc<dyn AgentRepo>>,
+    tool_registry: State<'_, Arc<ToolRegistry>>,
+) -> Result<Vec<AgentTool>, AppError> {
+    let disabled = agent_repo.get_agent_disabled_tools(agent_id).await?;
+    Ok(tool_registry
+        .definitions()
+        .await
+        .into_iter()
+        .map(|a| AgentTool {
+            enabled: !disabled.contains(&a.name),
+            name: a.name,
+            description: a.description,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn update_agent_tool(
+    agent_id: Uuid,
+    tool_name: String,
+    enabled: bool,
+    agent_repo: State<'_, Arc<dyn AgentRepo>>,
+) -> Result<(), AppError> {
+    agent_repo
+        .update_agent_tool(agent_id, &tool_name, enabled)
+        .await
+}
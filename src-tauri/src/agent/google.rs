@@ -1,35 +1,85 @@
+use std::collections::HashMap;
 use std::pin::Pin;
 
 use async_trait::async_trait;
+use base64::{prelude::BASE64_STANDARD, Engine};
 use futures_util::{self, stream, Stream, TryStreamExt};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use uuid::Uuid;
 
-use crate::agent::{AgentApi, AgentContext, AgentTextGenParamsApi, AgentTextGenResult};
+use crate::agent::{
+    with_documents, AgentApi, AgentContext, AgentEmbeddingParams, AgentEmbeddingTask,
+    AgentTextGenAttachment, AgentTextGenOptions, AgentTextGenResult, AgentToolCallDelta,
+};
+use crate::common::entity::chat::{ChatMessageStatus, ChatToolCall};
 use crate::common::error::AppError;
+use crate::tool::ToolDefinition;
 
 const HEADER_CONTENT_TYPE: &str = "Content-Type";
 const HEADER_X_GOOG_API_KEY: &str = "X-goog-api-key";
+const EMBEDDING_BATCH_SIZE: usize = 100;
+const EMBEDDING_DIMENSIONS: usize = 768;
 
 #[derive(Clone)]
 pub struct GoogleAgent {
     pub id: Uuid,
     pub model: String,
+    pub vision: bool,
 }
 
 pub struct GoogleTextGenParams {
     pub api_key: String,
+    pub system_instruction: Option<String>,
     pub messages: Vec<GoogleTextGenParamsMessage>,
+    pub tools: Vec<ToolDefinition>,
+    pub response_schema: Option<Value>,
 }
 
 pub struct GoogleTextGenParamsMessage {
     pub role: String,
     pub content: String,
+    pub attachments: Vec<AgentTextGenAttachment>,
+    pub tool_calls: Vec<ChatToolCall>,
+    pub tool_name: Option<String>,
 }
 
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GoogleTextGenRequestBody {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<GoogleTextGenRequestBodySystemInstruction>,
     pub contents: Vec<GoogleTextGenRequestBodyContent>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<GoogleTextGenRequestBodyTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_config: Option<GoogleTextGenRequestBodyGenerationConfig>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleTextGenRequestBodyGenerationConfig {
+    pub response_mime_type: String,
+    pub response_schema: Value,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleTextGenRequestBodyTool {
+    pub function_declarations: Vec<GoogleTextGenRequestBodyFunctionDeclaration>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleTextGenRequestBodyFunctionDeclaration {
+    pub name: String,
+    pub description: String,
+    pub parameters_json_schema: Value,
+}
+
+#[derive(Serialize)]
+pub struct GoogleTextGenRequestBodySystemInstruction {
+    pub parts: Vec<GoogleTextGenRequestBodyContentPart>,
 }
 
 #[derive(Serialize)]
@@ -39,8 +89,43 @@ pub struct GoogleTextGenRequestBodyContent {
 }
 
 #[derive(Serialize)]
-pub struct GoogleTextGenRequestBodyContentPart {
-    pub text: String,
+#[serde(untagged)]
+pub enum GoogleTextGenRequestBodyContentPart {
+    Text {
+        text: String,
+    },
+    InlineData {
+        #[serde(rename = "inlineData")]
+        inline_data: GoogleTextGenRequestBodyInlineData,
+    },
+    FunctionCall {
+        #[serde(rename = "functionCall")]
+        function_call: GoogleTextGenFunctionCall,
+    },
+    FunctionResponse {
+        #[serde(rename = "functionResponse")]
+        function_response: GoogleTextGenRequestBodyFunctionResponse,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GoogleTextGenFunctionCall {
+    pub name: String,
+    #[serde(default)]
+    pub args: Value,
+}
+
+#[derive(Serialize)]
+pub struct GoogleTextGenRequestBodyFunctionResponse {
+    pub name: String,
+    pub response: Value,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleTextGenRequestBodyInlineData {
+    pub mime_type: String,
+    pub data: String,
 }
 
 #[derive(Deserialize)]
@@ -59,8 +144,105 @@ pub struct GoogleTextGenResponseBodyCandidateContent {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GoogleTextGenResponseBodyCandidateContentPart {
-    pub text: String,
+    pub text: Option<String>,
+    pub function_call: Option<GoogleTextGenFunctionCall>,
+    pub inline_data: Option<GoogleTextGenResponseBodyInlineData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleTextGenResponseBodyInlineData {
+    pub mime_type: String,
+    pub data: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleEmbedRequestBody {
+    pub requests: Vec<GoogleEmbedRequestBodyRequest>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleEmbedRequestBodyRequest {
+    pub model: String,
+    pub content: GoogleTextGenRequestBodyContent,
+    pub task_type: String,
+    pub output_dimensionality: usize,
+}
+
+#[derive(Deserialize)]
+pub struct GoogleEmbedResponseBody {
+    pub embeddings: Vec<GoogleEmbedResponseBodyEmbedding>,
+}
+
+#[derive(Deserialize)]
+pub struct GoogleEmbedResponseBodyEmbedding {
+    pub values: Vec<f32>,
+}
+
+impl GoogleAgent {
+    pub async fn embed_texts(
+        self,
+        context: AgentContext,
+        params: AgentEmbeddingParams,
+    ) -> Result<Vec<Vec<f32>>, AppError> {
+        let config = context
+            .agent_repo
+            .get_agent_config(self.id)
+            .await?
+            .ok_or_else(|| AppError::AgentConfigRequired)?;
+        let api_key = context
+            .cipher
+            .decrypt_base64_str(&config.api_key.unwrap_or_default())?;
+        let task_type = match params.task {
+            AgentEmbeddingTask::Document => "RETRIEVAL_DOCUMENT",
+            AgentEmbeddingTask::Query => "RETRIEVAL_QUERY",
+        };
+        let client = context.http_client_manager.get_client();
+        let mut embeddings = Vec::with_capacity(params.texts.len());
+        for batch in params.texts.chunks(EMBEDDING_BATCH_SIZE) {
+            let body = GoogleEmbedRequestBody {
+                requests: batch
+                    .iter()
+                    .map(|a| GoogleEmbedRequestBodyRequest {
+                        model: format!("models/{}", params.model),
+                        content: GoogleTextGenRequestBodyContent {
+                            role: "user".to_string(),
+                            parts: vec![GoogleTextGenRequestBodyContentPart::Text {
+                                text: a.clone(),
+                            }],
+                        },
+                        task_type: task_type.to_string(),
+                        output_dimensionality: EMBEDDING_DIMENSIONS,
+                    })
+                    .collect(),
+            };
+            let response = client
+                .request(
+                    reqwest::Method::POST,
+                    format!(
+                        "https://generativelanguage.googleapis.com/v1beta/models/{}:batchEmbedContents",
+                        params.model
+                    ),
+                )
+                .header(HEADER_CONTENT_TYPE, "application/json")
+                .header(HEADER_X_GOOG_API_KEY, &api_key)
+                .json(&body)
+                .send()
+                .await
+                .map_err(AppError::from)?
+                .error_for_status()
+                .map_err(AppError::from)?
+                .json::<GoogleEmbedResponseBody>()
+                .await
+                .map_err(AppError::from)?;
+            embeddings.extend(response.embeddings.into_iter().map(|a| a.values));
+        }
+        Ok(embeddings)
+    }
 }
 
 #[async_trait]
@@ -74,15 +256,35 @@ impl AgentApi for GoogleAgent {
     ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentTextGenResult, AppError>> + Send>>, AppError> {
         let client = context.http_client_manager.get_client();
         let body = GoogleTextGenRequestBody {
-            contents: params
-                .messages
-                .into_iter()
-                .map(|a| GoogleTextGenRequestBodyContent {
-                    role: a.role,
-                    parts: vec![GoogleTextGenRequestBodyContentPart { text: a.content }],
-                })
-                .collect(),
+            system_instruction: params.system_instruction.map(|text| {
+                GoogleTextGenRequestBodySystemInstruction {
+                    parts: vec![GoogleTextGenRequestBodyContentPart::Text { text }],
+                }
+            }),
+            contents: create_contents(params.messages),
+            tools: match params.tools.is_empty() {
+                true => Vec::new(),
+                false => vec![GoogleTextGenRequestBodyTool {
+                    function_declarations: params
+                        .tools
+                        .into_iter()
+                        .map(|a| GoogleTextGenRequestBodyFunctionDeclaration {
+                            name: a.name,
+                            description: a.description,
+                            parameters_json_schema: a.parameters,
+                        })
+                        .collect(),
+                }],
+            },
+            generation_config: params.response_schema.map(|a| {
+                GoogleTextGenRequestBodyGenerationConfig {
+                    response_mime_type: "application/json".to_string(),
+                    response_schema: a,
+                }
+            }),
         };
+        let mut next_tool_call = 0;
+        let mut pending = Vec::<u8>::new();
         let stream = client
             .request(
                 reqwest::Method::POST,
@@ -96,8 +298,15 @@ impl AgentApi for GoogleAgent {
             .map_err(AppError::from)
             ?.bytes_stream()
             .map_err(AppError::from)
-            .map_ok(|bytes| {
-                let results: Vec<Result<AgentTextGenResult, AppError>> = match std::str::from_utf8(&bytes) {
+            .map_ok(move |bytes| {
+                // Events carrying images span many network chunks, so only complete lines
+                // are parsed and the rest waits for the next chunk.
+                pending.extend_from_slice(&bytes);
+                let lines = match pending.iter().rposition(|a| *a == b'\n') {
+                    Some(i) => pending.drain(..=i).collect::<Vec<_>>(),
+                    None => Vec::new(),
+                };
+                let results: Vec<Result<AgentTextGenResult, AppError>> = match std::str::from_utf8(&lines) {
                     Ok(text) => {
                         text.lines()
                             .filter_map(|line| line.strip_prefix("data:").map(str::trim))
@@ -108,7 +317,37 @@ impl AgentApi for GoogleAgent {
                                         body.candidates
                                             .into_iter()
                                             .flat_map(|c| c.content.parts)
-                                            .map(|p| Ok(AgentTextGenResult { text: p.text }))
+                                            .map(|p| {
+                                                let tool_calls = p
+                                                    .function_call
+                                                    .map(|f| {
+                                                        next_tool_call += 1;
+                                                        AgentToolCallDelta {
+                                                            index: next_tool_call - 1,
+                                                            id: None,
+                                                            name: Some(f.name),
+                                                            arguments: f.args.to_string(),
+                                                        }
+                                                    })
+                                                    .into_iter()
+                                                    .collect();
+                                                let images = p
+                                                    .inline_data
+                                                    .map(|d| {
+                                                        Ok::<_, AppError>(AgentTextGenAttachment {
+                                                            data: BASE64_STANDARD.decode(&d.data)?,
+                                                            mime_type: d.mime_type,
+                                                        })
+                                                    })
+                                                    .transpose()?
+                                                    .into_iter()
+                                                    .collect();
+                                                Ok(AgentTextGenResult {
+                                                    text: p.text.unwrap_or_default(),
+                                                    tool_calls,
+                                                    images,
+                                                })
+                                            })
                                             .collect()
                                     })
                                     .unwrap_or_else(|e| vec![Err(AppError::from(e))])
@@ -128,27 +367,46 @@ impl AgentApi for GoogleAgent {
         &self,
         context: AgentContext,
         chat_id: Uuid,
+        options: AgentTextGenOptions,
     ) -> Result<Option<Self::TextGenParams>, AppError> {
         let config = context.agent_repo.get_agent_config(self.id).await?;
 
         Ok(match config {
             Some(config) => {
                 let chat_messages = context.chat_repo.get_chat_messages(chat_id).await?;
+                let mut attachments = if self.vision {
+                    context.get_chat_attachments(chat_id).await?
+                } else {
+                    HashMap::new()
+                };
+                let mut documents = context.get_chat_documents(chat_id).await?;
+                let tools = match options.response_schema {
+                    Some(_) => Vec::new(),
+                    None => context.get_agent_tools(self.id).await?,
+                };
                 Some(Self::TextGenParams {
                     api_key: context
                         .cipher
                         .decrypt_base64_str(&config.api_key.unwrap_or_default())?,
+                    system_instruction: options
+                        .with_knowledge(context.get_chat_instructions(chat_id).await?),
                     messages: chat_messages
                         .into_iter()
+                        .filter(|a| !matches!(a.status, ChatMessageStatus::Pending))
                         .map(|a| GoogleTextGenParamsMessage {
                             role: match a.role.as_str() {
                                 "model" => "model",
                                 _ => "user",
                             }
                             .into(),
-                            content: a.content,
+                            attachments: attachments.remove(&a.id).unwrap_or_default(),
+                            content: with_documents(a.content, documents.remove(&a.id)),
+                            tool_calls: a.tool_calls.map(|b| b.0).unwrap_or_default(),
+                            tool_name: a.tool_name,
                         })
                         .collect(),
+                    tools,
+                    response_schema: options.response_schema,
                 })
             }
             None => None,
@@ -156,11 +414,67 @@ impl AgentApi for GoogleAgent {
     }
 }
 
-impl AgentTextGenParamsApi for GoogleTextGenParams {
-    fn push_message_str(&mut self, message: &str) {
-        self.messages.push(GoogleTextGenParamsMessage {
-            role: "user".to_string(),
-            content: message.to_string(),
+/// Gemini expects the responses to parallel function calls in a single turn, so
+/// consecutive tool results are merged into one content.
+fn create_contents(
+    messages: Vec<GoogleTextGenParamsMessage>,
+) -> Vec<GoogleTextGenRequestBodyContent> {
+    let mut contents = Vec::<GoogleTextGenRequestBodyContent>::new();
+    let mut merge_tool_result = false;
+    for message in messages {
+        if let Some(name) = message.tool_name {
+            let part = GoogleTextGenRequestBodyContentPart::FunctionResponse {
+                function_response: GoogleTextGenRequestBodyFunctionResponse {
+                    name,
+                    response: create_function_response(&message.content),
+                },
+            };
+            match contents.last_mut() {
+                Some(last) if merge_tool_result => last.parts.push(part),
+                _ => contents.push(GoogleTextGenRequestBodyContent {
+                    role: "user".to_string(),
+                    parts: vec![part],
+                }),
+            }
+            merge_tool_result = true;
+            continue;
+        }
+        merge_tool_result = false;
+        let mut parts = message
+            .attachments
+            .into_iter()
+            .map(|a| GoogleTextGenRequestBodyContentPart::InlineData {
+                inline_data: GoogleTextGenRequestBodyInlineData {
+                    mime_type: a.mime_type,
+                    data: BASE64_STANDARD.encode(a.data),
+                },
+            })
+            .collect::<Vec<_>>();
+        if !message.content.is_empty() || message.tool_calls.is_empty() {
+            parts.push(GoogleTextGenRequestBodyContentPart::Text {
+                text: message.content,
+            });
+        }
+        parts.extend(message.tool_calls.into_iter().map(|a| {
+            GoogleTextGenRequestBodyContentPart::FunctionCall {
+                function_call: GoogleTextGenFunctionCall {
+                    name: a.name,
+                    args: a.arguments,
+                },
+            }
+        }));
+        contents.push(GoogleTextGenRequestBodyContent {
+            role: message.role,
+            parts,
         });
     }
+    contents
+}
+
+fn create_function_response(content: &str) -> Value {
+    match serde_json::from_str::<Value>(content) {
+        Ok(Value::Object(map)) => Value::Object(map),
+        Ok(value) => json!({ "result": value }),
+        Err(_) => json!({ "result": content }),
+    }
 }
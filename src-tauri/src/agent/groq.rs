@@ -1,34 +1,53 @@
-use std::pin::Pin;
+use std::{collections::HashMap, pin::Pin};
 
 use async_trait::async_trait;
+use base64::{prelude::BASE64_STANDARD, Engine};
 use futures::{stream, TryStreamExt};
 use futures_util::Stream;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio_util::{codec::FramedRead, io::StreamReader};
 use uuid::Uuid;
 
 use crate::{
-    agent::{AgentApi, AgentContext, AgentTextGenParamsApi, AgentTextGenResult},
+    agent::{
+        with_documents, AgentApi, AgentContext, AgentSpeechParams, AgentSpeechResult,
+        AgentTextGenAttachment, AgentTextGenOptions, AgentTextGenResult, AgentToolCallDelta,
+        AgentTranscriptionParams, AgentTranscriptionResult,
+    },
     codec::sse::SseDecoder,
-    common::error::AppError,
+    common::{
+        entity::chat::{ChatMessageStatus, ChatToolCall},
+        error::AppError,
+    },
+    tool::ToolDefinition,
 };
 
 const HEADER_CONTENT_TYPE: &str = "Content-Type";
 const HEADER_API_KEY: &str = "Authorization";
+const SPEECH_INPUT_CHARS: usize = 10_000;
 
+#[derive(Clone)]
 pub struct GroqAgent {
     pub id: Uuid,
     pub model: String,
+    pub vision: bool,
 }
 
 pub struct GroqTextGenParams {
     pub api_key: String,
+    pub system_prompt: Option<String>,
     pub messages: Vec<GroqTextGenParamsMessage>,
+    pub tools: Vec<ToolDefinition>,
+    pub json_mode: bool,
 }
 
 pub struct GroqTextGenParamsMessage {
     pub role: String,
     pub content: String,
+    pub attachments: Vec<AgentTextGenAttachment>,
+    pub tool_calls: Vec<ChatToolCall>,
+    pub tool_call_id: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -37,12 +56,70 @@ pub struct GroqTextGenRequestBody {
     pub model: String,
     pub stream: bool,
     pub include_reasoning: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<GroqTextGenRequestBodyTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<GroqTextGenRequestBodyResponseFormat>,
+}
+
+#[derive(Serialize)]
+pub struct GroqTextGenRequestBodyResponseFormat {
+    #[serde(rename = "type")]
+    pub kind: String,
 }
 
 #[derive(Serialize)]
 pub struct GroqTextGenRequestBodyMessage {
     pub role: String,
-    pub content: String,
+    pub content: GroqTextGenRequestBodyMessageContent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<GroqTextGenRequestBodyToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct GroqTextGenRequestBodyTool {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub function: ToolDefinition,
+}
+
+#[derive(Serialize)]
+pub struct GroqTextGenRequestBodyToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub function: GroqTextGenRequestBodyToolCallFunction,
+}
+
+#[derive(Serialize)]
+pub struct GroqTextGenRequestBodyToolCallFunction {
+    pub name: String,
+    pub arguments: String,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum GroqTextGenRequestBodyMessageContent {
+    Text(String),
+    Parts(Vec<GroqTextGenRequestBodyMessageContentPart>),
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GroqTextGenRequestBodyMessageContentPart {
+    Text {
+        text: String,
+    },
+    ImageUrl {
+        image_url: GroqTextGenRequestBodyImageUrl,
+    },
+}
+
+#[derive(Serialize)]
+pub struct GroqTextGenRequestBodyImageUrl {
+    pub url: String,
 }
 
 #[derive(Deserialize)]
@@ -65,6 +142,135 @@ pub struct GroqTextGenResponseBodyChoice {
 #[derive(Deserialize)]
 pub struct GroqTextGenResponseBodyChoiceDelta {
     pub content: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<GroqTextGenResponseBodyToolCallDelta>,
+}
+
+#[derive(Deserialize)]
+pub struct GroqTextGenResponseBodyToolCallDelta {
+    pub index: usize,
+    pub id: Option<String>,
+    pub function: Option<GroqTextGenResponseBodyToolCallDeltaFunction>,
+}
+
+#[derive(Deserialize)]
+pub struct GroqTextGenResponseBodyToolCallDeltaFunction {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GroqTranscriptionResponseBody {
+    pub text: String,
+}
+
+#[derive(Serialize)]
+pub struct GroqSpeechRequestBody {
+    pub model: String,
+    pub input: String,
+    pub voice: String,
+    pub response_format: String,
+}
+
+impl GroqAgent {
+    pub async fn transcribe_audio(
+        self,
+        context: AgentContext,
+        params: AgentTranscriptionParams,
+    ) -> Result<AgentTranscriptionResult, AppError> {
+        let config = context
+            .agent_repo
+            .get_agent_config(self.id)
+            .await?
+            .ok_or_else(|| AppError::AgentConfigRequired)?;
+        let api_key = context
+            .cipher
+            .decrypt_base64_str(&config.api_key.unwrap_or_default())?;
+        let file = reqwest::multipart::Part::bytes(params.data)
+            .file_name(params.file_name)
+            .mime_str(&params.mime_type)
+            .map_err(AppError::from)?;
+        let mut form = reqwest::multipart::Form::new()
+            .part("file", file)
+            .text("model", self.model)
+            .text("response_format", "json");
+        if let Some(language) = params.language {
+            form = form.text("language", language);
+        }
+        if let Some(prompt) = params.prompt {
+            form = form.text("prompt", prompt);
+        }
+        let body = context
+            .http_client_manager
+            .get_client()
+            .request(
+                reqwest::Method::POST,
+                "https://api.groq.com/openai/v1/audio/transcriptions",
+            )
+            .header(HEADER_API_KEY, format!("Bearer {}", api_key))
+            .multipart(form)
+            .send()
+            .await
+            .map_err(AppError::from)?
+            .error_for_status()
+            .map_err(AppError::from)?
+            .json::<GroqTranscriptionResponseBody>()
+            .await
+            .map_err(AppError::from)?;
+        Ok(AgentTranscriptionResult {
+            text: body.text.trim().to_string(),
+        })
+    }
+
+    pub fn default_speech_voice(&self) -> Option<&'static str> {
+        match self.model.as_str() {
+            "playai-tts" => Some("Fritz-PlayAI"),
+            "playai-tts-arabic" => Some("Ahmad-PlayAI"),
+            _ => None,
+        }
+    }
+
+    pub async fn synthesize_speech(
+        self,
+        context: AgentContext,
+        params: AgentSpeechParams,
+    ) -> Result<AgentSpeechResult, AppError> {
+        let config = context
+            .agent_repo
+            .get_agent_config(self.id)
+            .await?
+            .ok_or_else(|| AppError::AgentConfigRequired)?;
+        let api_key = context
+            .cipher
+            .decrypt_base64_str(&config.api_key.unwrap_or_default())?;
+        let data = context
+            .http_client_manager
+            .get_client()
+            .request(
+                reqwest::Method::POST,
+                "https://api.groq.com/openai/v1/audio/speech",
+            )
+            .header(HEADER_CONTENT_TYPE, "application/json")
+            .header(HEADER_API_KEY, format!("Bearer {}", api_key))
+            .json(&GroqSpeechRequestBody {
+                model: self.model,
+                input: params.input.chars().take(SPEECH_INPUT_CHARS).collect(),
+                voice: params.voice,
+                response_format: "wav".to_string(),
+            })
+            .send()
+            .await
+            .map_err(AppError::from)?
+            .error_for_status()
+            .map_err(AppError::from)?
+            .bytes()
+            .await
+            .map_err(AppError::from)?;
+        Ok(AgentSpeechResult {
+            mime_type: "audio/wav".to_string(),
+            data: data.to_vec(),
+        })
+    }
 }
 
 #[async_trait]
@@ -80,18 +286,43 @@ impl AgentApi for GroqAgent {
         let client = context.http_client_manager.get_client();
         let body = GroqTextGenRequestBody {
             messages: params
-                .messages
+                .system_prompt
                 .iter()
                 .map(|a| GroqTextGenRequestBodyMessage {
-                    role: a.role.clone(),
-                    content: a.content.clone(),
+                    role: "system".to_string(),
+                    content: GroqTextGenRequestBodyMessageContent::Text(a.clone()),
+                    tool_calls: None,
+                    tool_call_id: None,
                 })
+                .chain(
+                    params
+                        .messages
+                        .iter()
+                        .map(|a| GroqTextGenRequestBodyMessage {
+                            role: a.role.clone(),
+                            content: create_message_content(a),
+                            tool_calls: create_message_tool_calls(a),
+                            tool_call_id: a.tool_call_id.clone(),
+                        }),
+                )
                 .collect(),
             model: self.model,
             stream: true,
             include_reasoning: false,
+            tools: params
+                .tools
+                .into_iter()
+                .map(|a| GroqTextGenRequestBodyTool {
+                    kind: "function".to_string(),
+                    function: a,
+                })
+                .collect(),
+            response_format: params
+                .json_mode
+                .then(|| GroqTextGenRequestBodyResponseFormat {
+                    kind: "json_object".to_string(),
+                }),
         };
-        println!("REQUEST BODY: {:?}", serde_json::to_string(&body));
         let stream = client
             .request(
                 reqwest::Method::POST,
@@ -109,7 +340,6 @@ impl AgentApi for GroqAgent {
         );
         let framed_stream = FramedRead::new(reader, SseDecoder::new())
             .map_ok(|line| {
-                println!("GROQ LINE: {}", line);
                 let data = match line.strip_prefix("data: ") {
                     Some(d) => d,
                     None => return stream::iter(vec![]),
@@ -127,9 +357,33 @@ impl AgentApi for GroqAgent {
                         .choices
                         .into_iter()
                         .filter_map(|a| {
-                            a.delta
-                                .content
-                                .map(|content| Ok(AgentTextGenResult { text: content }))
+                            let tool_calls = a
+                                .delta
+                                .tool_calls
+                                .into_iter()
+                                .map(|b| {
+                                    let function = b.function.unwrap_or(
+                                        GroqTextGenResponseBodyToolCallDeltaFunction {
+                                            name: None,
+                                            arguments: None,
+                                        },
+                                    );
+                                    AgentToolCallDelta {
+                                        index: b.index,
+                                        id: b.id,
+                                        name: function.name,
+                                        arguments: function.arguments.unwrap_or_default(),
+                                    }
+                                })
+                                .collect::<Vec<_>>();
+                            match (a.delta.content, tool_calls.is_empty()) {
+                                (None, true) => None,
+                                (content, _) => Some(Ok(AgentTextGenResult {
+                                    text: content.unwrap_or_default(),
+                                    tool_calls,
+                                    images: Vec::new(),
+                                })),
+                            }
                         })
                         .collect(),
                     Err(e) => vec![Err(e)],
@@ -144,27 +398,54 @@ impl AgentApi for GroqAgent {
         &self,
         context: AgentContext,
         chat_id: Uuid,
+        options: AgentTextGenOptions,
     ) -> Result<Option<Self::TextGenParams>, AppError> {
         let config = context.agent_repo.get_agent_config(self.id).await?;
 
         Ok(match config {
             Some(config) => {
                 let chat_messages = context.chat_repo.get_chat_messages(chat_id).await?;
+                let mut attachments = if self.vision {
+                    context.get_chat_attachments(chat_id).await?
+                } else {
+                    HashMap::new()
+                };
+                let mut documents = context.get_chat_documents(chat_id).await?;
+                let tools = match options.response_schema {
+                    Some(_) => Vec::new(),
+                    None => context.get_agent_tools(self.id).await?,
+                };
                 Some(Self::TextGenParams {
                     api_key: context
                         .cipher
                         .decrypt_base64_str(&config.api_key.unwrap_or_default())?,
+                    system_prompt: with_response_schema(
+                        options.with_knowledge(context.get_chat_instructions(chat_id).await?),
+                        options.response_schema.as_ref(),
+                    ),
                     messages: chat_messages
                         .into_iter()
+                        .filter(|a| !matches!(a.status, ChatMessageStatus::Pending))
                         .map(|a| GroqTextGenParamsMessage {
                             role: match a.role.as_str() {
                                 "user" => "user".to_string(),
                                 "model" => "assistant".to_string(),
+                                "tool" => "tool".to_string(),
                                 _ => panic!("unknown role"),
                             },
-                            content: a.content,
+                            // Image parts are only accepted on user messages, so images a
+                            // Gemini model generated earlier in the chat are left out.
+                            attachments: attachments
+                                .remove(&a.id)
+                                .filter(|_| a.role == "user")
+                                .unwrap_or_default(),
+                            content: with_documents(a.content, documents.remove(&a.id)),
+                            tool_calls: a.tool_calls.map(|b| b.0).unwrap_or_default(),
+                            tool_call_id: a.tool_call_id,
                         })
                         .collect(),
+                    tools,
+                    json_mode: options.response_schema.is_some(),
                 })
             }
             None => None,
@@ -172,11 +453,67 @@ impl AgentApi for GroqAgent {
     }
 }
 
-impl AgentTextGenParamsApi for GroqTextGenParams {
-    fn push_message_str(&mut self, message: &str) {
-        self.messages.push(GroqTextGenParamsMessage {
-            role: "user".to_string(),
-            content: message.to_string(),
-        });
+/// JSON mode only guarantees well-formed JSON, so the schema itself is given to the
+/// model in the system prompt.
+fn with_response_schema(system_prompt: Option<String>, schema: Option<&Value>) -> Option<String> {
+    let schema = match schema {
+        Some(schema) => schema,
+        None => return system_prompt,
+    };
+    let instruction = format!(
+        "Respond only with a JSON value that conforms to this JSON Schema:\n{}",
+        schema
+    );
+    Some(match system_prompt {
+        Some(system_prompt) => format!("{}\n\n{}", system_prompt, instruction),
+        None => instruction,
+    })
+}
+
+fn create_message_tool_calls(
+    message: &GroqTextGenParamsMessage,
+) -> Option<Vec<GroqTextGenRequestBodyToolCall>> {
+    if message.tool_calls.is_empty() {
+        return None;
     }
+    Some(
+        message
+            .tool_calls
+            .iter()
+            .map(|a| GroqTextGenRequestBodyToolCall {
+                id: a.id.clone(),
+                kind: "function".to_string(),
+                function: GroqTextGenRequestBodyToolCallFunction {
+                    name: a.name.clone(),
+                    arguments: a.arguments.to_string(),
+                },
+            })
+            .collect(),
+    )
+}
+
+fn create_message_content(
+    message: &GroqTextGenParamsMessage,
+) -> GroqTextGenRequestBodyMessageContent {
+    if message.attachments.is_empty() {
+        return GroqTextGenRequestBodyMessageContent::Text(message.content.clone());
+    }
+    GroqTextGenRequestBodyMessageContent::Parts(
+        [GroqTextGenRequestBodyMessageContentPart::Text {
+            text: message.content.clone(),
+        }]
+        .into_iter()
+        .chain(message.attachments.iter().map(|a| {
+            GroqTextGenRequestBodyMessageContentPart::ImageUrl {
+                image_url: GroqTextGenRequestBodyImageUrl {
+                    url: format!(
+                        "data:{};base64,{}",
+                        a.mime_type,
+                        BASE64_STANDARD.encode(&a.data)
+                    ),
+                },
+            }
+        }))
+        .collect(),
+    )
 }
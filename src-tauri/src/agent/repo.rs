@@ -2,19 +2,23 @@ use async_trait::async_trait;
 use uuid::Uuid;
 
 use crate::{
-    agent::AgentProvider,
+    agent::{AgentCapability, AgentProvider},
     common::{
         entity::agent::{AgentConfigRow, AgentProviderRow, AgentRow},
         error::AppError,
     },
 };
 
+#[cfg(test)]
+pub mod memory;
 pub mod sqlite;
 
 pub struct CreateAgent {
     pub id: Uuid,
     pub provider: AgentProvider,
     pub model: String,
+    pub vision: bool,
+    pub capability: AgentCapability,
 }
 
 pub struct CreateAgentProvider {
@@ -52,7 +56,10 @@ pub struct UpsertAgentConfig {
 
 #[async_trait]
 pub trait AgentRepo: Send + Sync {
-    async fn get_agents(&self) -> Result<Vec<AgentRow>, AppError>;
+    async fn get_agents(
+        &self,
+        capability: Option<AgentCapability>,
+    ) -> Result<Vec<AgentRow>, AppError>;
     async fn create_agent(&self, create: CreateAgent) -> Result<AgentRow, AppError>;
     async fn get_agent(&self, agent_id: Uuid) -> Result<Option<AgentRow>, AppError>;
     async fn update_agent(&self, id: String, update: UpdateAgent) -> Result<(), AppError>;
@@ -82,4 +89,11 @@ pub trait AgentRepo: Send + Sync {
         agent_id: Uuid,
         update: UpsertAgentConfig,
     ) -> Result<u64, AppError>;
+    async fn get_agent_disabled_tools(&self, agent_id: Uuid) -> Result<Vec<String>, AppError>;
+    async fn update_agent_tool(
+        &self,
+        agent_id: Uuid,
+        tool_name: &str,
+        enabled: bool,
+    ) -> Result<(), AppError>;
 }
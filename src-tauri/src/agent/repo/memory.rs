@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    agent::{
+        repo::{
+            AgentRepo, CreateAgent, CreateAgentConfig, CreateAgentProvider, UpdateAgent,
+            UpdateAgentConfig, UpdateAgentProvider, UpdateCurrentAgent, UpsertAgentConfig,
+        },
+        AgentCapability,
+    },
+    common::{
+        entity::agent::{AgentConfigRow, AgentProviderRow, AgentRow},
+        error::AppError,
+    },
+    database::memory::{now_millis, MemoryTables},
+};
+
+pub struct MemoryAgentRepo {
+    tables: Arc<Mutex<MemoryTables>>,
+}
+
+impl MemoryAgentRepo {
+    pub fn new(tables: Arc<Mutex<MemoryTables>>) -> Self {
+        Self { tables }
+    }
+}
+
+#[async_trait]
+impl AgentRepo for MemoryAgentRepo {
+    async fn get_agents(
+        &self,
+        capability: Option<AgentCapability>,
+    ) -> Result<Vec<AgentRow>, AppError> {
+        let tables = self.tables.lock().await;
+        Ok(tables
+            .agents
+            .iter()
+            .filter(|a| capability.is_none_or(|b| a.capability == b))
+            .cloned()
+            .collect())
+    }
+
+    async fn create_agent(&self, create: CreateAgent) -> Result<AgentRow, AppError> {
+        let mut tables = self.tables.lock().await;
+        let now = now_millis();
+        let row = AgentRow {
+            created_at: now,
+            updated_at: now,
+            id: create.id,
+            provider: create.provider,
+            model: create.model,
+            vision: create.vision,
+            capability: create.capability,
+        };
+        tables.agents.push(row.clone());
+        Ok(row)
+    }
+
+    async fn get_agent(&self, agent_id: Uuid) -> Result<Option<AgentRow>, AppError> {
+        let tables = self.tables.lock().await;
+        Ok(tables.agents.iter().find(|a| a.id == agent_id).cloned())
+    }
+
+    async fn update_agent(&self, id: String, update: UpdateAgent) -> Result<(), AppError> {
+        let mut tables = self.tables.lock().await;
+        let id = Uuid::parse_str(&id).ok();
+        if let Some(agent) = tables.agents.iter_mut().find(|a| Some(a.id) == id) {
+            if let Some(provider) = update.provider {
+                agent.provider = provider;
+            }
+            if let Some(model) = update.model {
+                agent.model = model;
+            }
+            agent.updated_at = now_millis();
+        }
+        Ok(())
+    }
+
+    async fn get_current_agent(&self) -> Result<Option<AgentRow>, AppError> {
+        let tables = self.tables.lock().await;
+        Ok(tables
+            .current_agent_id
+            .and_then(|id| tables.agents.iter().find(|a| a.id == id))
+            .cloned())
+    }
+
+    async fn update_current_agent(&self, update: UpdateCurrentAgent) -> Result<(), AppError> {
+        self.tables.lock().await.current_agent_id = Some(update.agent_id);
+        Ok(())
+    }
+
+    async fn create_provider(
+        &self,
+        create: CreateAgentProvider,
+    ) -> Result<AgentProviderRow, AppError> {
+        let mut tables = self.tables.lock().await;
+        let now = now_millis();
+        let row = AgentProviderRow {
+            created_at: now,
+            updated_at: now,
+            id: create.id,
+            provider: create.provider,
+            api_key: create.api_key,
+        };
+        tables.agent_providers.push(row.clone());
+        Ok(row)
+    }
+
+    async fn update_provider(
+        &self,
+        id: String,
+        update: UpdateAgentProvider,
+    ) -> Result<(), AppError> {
+        let mut tables = self.tables.lock().await;
+        let id = Uuid::parse_str(&id).ok();
+        if let Some(provider) = tables.agent_providers.iter_mut().find(|a| Some(a.id) == id) {
+            if let Some(api_key) = update.api_key {
+                provider.api_key = Some(api_key);
+            }
+            provider.updated_at = now_millis();
+        }
+        Ok(())
+    }
+
+    async fn create_agent_config(
+        &self,
+        create: CreateAgentConfig,
+    ) -> Result<AgentConfigRow, AppError> {
+        let mut tables = self.tables.lock().await;
+        let now = now_millis();
+        let row = AgentConfigRow {
+            created_at: now,
+            updated_at: now,
+            agent_id: create.agent_id,
+            api_key: create.api_key,
+        };
+        tables.agent_configs.push(row.clone());
+        Ok(row)
+    }
+
+    async fn get_agent_config(&self, agent_id: Uuid) -> Result<Option<AgentConfigRow>, AppError> {
+        let tables = self.tables.lock().await;
+        Ok(tables
+            .agent_configs
+            .iter()
+            .find(|a| a.agent_id == agent_id)
+            .cloned())
+    }
+
+    async fn update_agent_config(
+        &self,
+        agent_id: Uuid,
+        update: UpdateAgentConfig,
+    ) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        let Some(config) = tables
+            .agent_configs
+            .iter_mut()
+            .find(|a| a.agent_id == agent_id)
+        else {
+            return Ok(0);
+        };
+        if let Some(api_key) = update.api_key {
+            config.api_key = Some(api_key);
+        }
+        config.updated_at = now_millis();
+        Ok(1)
+    }
+
+    /// Like the sqlite upsert, an empty key clears the stored one and a missing key
+    /// leaves it alone.
+    async fn upsert_agent_config(
+        &self,
+        agent_id: Uuid,
+        update: UpsertAgentConfig,
+    ) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        let api_key = update
+            .api_key
+            .map(|a| if a.is_empty() { None } else { Some(a) });
+        let now = now_millis();
+        match tables
+            .agent_configs
+            .iter_mut()
+            .find(|a| a.agent_id == agent_id)
+        {
+            Some(config) => {
+                if let Some(api_key) = api_key {
+                    config.api_key = api_key;
+                }
+                config.updated_at = now;
+            }
+            None => tables.agent_configs.push(AgentConfigRow {
+                created_at: now,
+                updated_at: now,
+                agent_id,
+                api_key: api_key.flatten(),
+            }),
+        }
+        Ok(1)
+    }
+
+    async fn get_agent_disabled_tools(&self, agent_id: Uuid) -> Result<Vec<String>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut tool_names = tables
+            .agent_disabled_tools
+            .iter()
+            .filter(|a| a.0 == agent_id)
+            .map(|a| a.1.clone())
+            .collect::<Vec<_>>();
+        tool_names.sort();
+        Ok(tool_names)
+    }
+
+    async fn update_agent_tool(
+        &self,
+        agent_id: Uuid,
+        tool_name: &str,
+        enabled: bool,
+    ) -> Result<(), AppError> {
+        let mut tables = self.tables.lock().await;
+        let disabled_tool = (agent_id, tool_name.to_string());
+        tables.agent_disabled_tools.retain(|a| *a != disabled_tool);
+        if !enabled {
+            tables.agent_disabled_tools.push(disabled_tool);
+        }
+        Ok(())
+    }
+}
@@ -6,9 +6,12 @@ use tokio::sync::Mutex;
 use uuid::Uuid;
 
 use crate::{
-    agent::repo::{
-        AgentRepo, CreateAgent, CreateAgentConfig, CreateAgentProvider, UpdateAgent,
-        UpdateAgentConfig, UpdateAgentProvider, UpdateCurrentAgent, UpsertAgentConfig,
+    agent::{
+        repo::{
+            AgentRepo, CreateAgent, CreateAgentConfig, CreateAgentProvider, UpdateAgent,
+            UpdateAgentConfig, UpdateAgentProvider, UpdateCurrentAgent, UpsertAgentConfig,
+        },
+        AgentCapability,
     },
     common::{
         entity::agent::{AgentConfigRow, AgentProviderRow, AgentRow},
@@ -38,8 +41,11 @@ impl<'a> TransactionalSqliteAgentRepo<'a> {
 
 #[async_trait]
 impl AgentRepo for SqliteAgentRepo {
-    async fn get_agents(&self) -> Result<Vec<AgentRow>, AppError> {
-        get_agents(&*self.db_pool).await
+    async fn get_agents(
+        &self,
+        capability: Option<AgentCapability>,
+    ) -> Result<Vec<AgentRow>, AppError> {
+        get_agents(&*self.db_pool, capability).await
     }
 
     async fn get_agent(&self, agent_id: Uuid) -> Result<Option<AgentRow>, AppError> {
@@ -103,13 +109,29 @@ impl AgentRepo for SqliteAgentRepo {
     ) -> Result<u64, AppError> {
         upsert_agent_config(&*self.db_pool, agent_id, upsert).await
     }
+
+    async fn get_agent_disabled_tools(&self, agent_id: Uuid) -> Result<Vec<String>, AppError> {
+        get_agent_disabled_tools(&*self.db_pool, agent_id).await
+    }
+
+    async fn update_agent_tool(
+        &self,
+        agent_id: Uuid,
+        tool_name: &str,
+        enabled: bool,
+    ) -> Result<(), AppError> {
+        update_agent_tool(&*self.db_pool, agent_id, tool_name, enabled).await
+    }
 }
 
 #[async_trait]
 impl<'a> AgentRepo for TransactionalSqliteAgentRepo<'a> {
-    async fn get_agents(&self) -> Result<Vec<AgentRow>, AppError> {
+    async fn get_agents(
+        &self,
+        capability: Option<AgentCapability>,
+    ) -> Result<Vec<AgentRow>, AppError> {
         let mut tx = self.tx.try_lock().map_err(AppError::from)?;
-        get_agents(&mut **tx).await
+        get_agents(&mut **tx, capability).await
     }
 
     async fn get_agent(&self, agent_id: Uuid) -> Result<Option<AgentRow>, AppError> {
@@ -184,13 +206,32 @@ impl<'a> AgentRepo for TransactionalSqliteAgentRepo<'a> {
         let mut tx = self.tx.try_lock().map_err(AppError::from)?;
         upsert_agent_config(&mut **tx, agent_id, upsert).await
     }
+
+    async fn get_agent_disabled_tools(&self, agent_id: Uuid) -> Result<Vec<String>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_agent_disabled_tools(&mut **tx, agent_id).await
+    }
+
+    async fn update_agent_tool(
+        &self,
+        agent_id: Uuid,
+        tool_name: &str,
+        enabled: bool,
+    ) -> Result<(), AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        update_agent_tool(&mut **tx, agent_id, tool_name, enabled).await
+    }
 }
 
-async fn get_agents<'a, E>(executor: E) -> Result<Vec<AgentRow>, AppError>
+async fn get_agents<'a, E>(
+    executor: E,
+    capability: Option<AgentCapability>,
+) -> Result<Vec<AgentRow>, AppError>
 where
     E: Executor<'a, Database = Sqlite>,
 {
-    sqlx::query_as::<_, AgentRow>("select * from agents")
+    sqlx::query_as::<_, AgentRow>("select * from agents where ?1 is null or capability = ?1")
+        .bind(capability)
         .fetch_all(executor)
         .await
         .map_err(AppError::from)
@@ -221,10 +262,12 @@ async fn create_agent<'a, E>(executor: E, create: CreateAgent) -> Result<AgentRo
 where
     E: Executor<'a, Database = Sqlite>,
 {
-    let (created_at, updated_at): (i64, i64) = sqlx::query_as("insert into agents (id, provider, model) values (?1, ?2, ?3) returning created_at, updated_at")
+    let (created_at, updated_at): (i64, i64) = sqlx::query_as("insert into agents (id, provider, model, vision, capability) values (?1, ?2, ?3, ?4, ?5) returning created_at, updated_at")
         .bind(&create.id)
         .bind(&create.provider)
         .bind(&create.model)
+        .bind(create.vision)
+        .bind(&create.capability)
         .fetch_one(executor)
         .await
         .map_err(AppError::from)?;
@@ -234,6 +277,8 @@ where
         id: create.id,
         provider: create.provider,
         model: create.model,
+        vision: create.vision,
+        capability: create.capability,
     })
 }
 
@@ -402,3 +447,43 @@ where
     let result = qb.build().execute(executor).await.map_err(AppError::from)?;
     Ok(result.rows_affected())
 }
+
+async fn get_agent_disabled_tools<'a, E>(
+    executor: E,
+    agent_id: Uuid,
+) -> Result<Vec<String>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_scalar::<_, String>(
+        "select tool_name from agent_disabled_tools where agent_id = ?1 order by tool_name asc",
+    )
+    .bind(agent_id)
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn update_agent_tool<'a, E>(
+    executor: E,
+    agent_id: Uuid,
+    tool_name: &str,
+    enabled: bool,
+) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let query = match enabled {
+        true => "delete from agent_disabled_tools where agent_id = ?1 and tool_name = ?2",
+        false => {
+            "insert into agent_disabled_tools (agent_id, tool_name) values (?1, ?2) on conflict do nothing"
+        }
+    };
+    sqlx::query(query)
+        .bind(agent_id)
+        .bind(tool_name)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
@@ -1,2 +1,11 @@
+pub mod attachment;
 pub mod cmds;
+pub mod document;
+pub mod embedding;
+pub mod encryption;
+pub mod export;
+pub mod import;
+pub mod recovery;
 pub mod repo;
+pub mod schema;
+pub mod search;
@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    chat::{document, repo::CreateChatMessageAttachment},
+    common::{entity::chat::ChatMessageAttachmentKind, error::AppError},
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageAttachmentCmd {
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    pub path: Option<String>,
+    pub data: Option<String>,
+}
+
+impl ChatMessageAttachmentCmd {
+    pub async fn into_create(
+        self,
+        message_id: Uuid,
+    ) -> Result<CreateChatMessageAttachment, AppError> {
+        let name = self.name.or_else(|| {
+            self.path.as_deref().and_then(|a| {
+                Path::new(a)
+                    .file_name()
+                    .map(|a| a.to_string_lossy().into_owned())
+            })
+        });
+        let mime_type = match self.mime_type {
+            Some(mime_type) => mime_type,
+            None => name
+                .as_deref()
+                .and_then(|a| guess_mime_type(a).or_else(|| document::guess_mime_type(a)))
+                .ok_or_else(|| AppError::UnsupportedAttachment(name.clone().unwrap_or_default()))?
+                .to_string(),
+        };
+        let data = self
+            .data
+            .map(|a| BASE64_STANDARD.decode(a).map_err(AppError::from))
+            .transpose()?;
+        if data.is_none() && self.path.is_none() {
+            return Err(AppError::UnsupportedAttachment(mime_type));
+        }
+
+        if mime_type.starts_with("image/") {
+            return Ok(CreateChatMessageAttachment {
+                id: Uuid::new_v4(),
+                message_id,
+                kind: ChatMessageAttachmentKind::Image,
+                name,
+                mime_type,
+                data,
+                path: self.path,
+                text: None,
+            });
+        }
+
+        let bytes = match (&data, &self.path) {
+            (Some(data), _) => data.clone(),
+            (None, Some(path)) => tokio::fs::read(path).await.map_err(AppError::from)?,
+            (None, None) => return Err(AppError::UnsupportedAttachment(mime_type)),
+        };
+        let extract_mime_type = mime_type.clone();
+        let text =
+            tokio::task::spawn_blocking(move || document::extract_text(&extract_mime_type, &bytes))
+                .await
+                .map_err(|e| AppError::Unknown(Some(Box::new(e))))??;
+        Ok(CreateChatMessageAttachment {
+            id: Uuid::new_v4(),
+            message_id,
+            kind: ChatMessageAttachmentKind::Document,
+            name,
+            mime_type,
+            data,
+            path: self.path,
+            text: Some(text),
+        })
+    }
+}
+
+pub fn guess_mime_type(name: &str) -> Option<&'static str> {
+    let extension = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        _ => return None,
+    })
+}
@@ -1,230 +1,651 @@
 use futures::pin_mut;
 use futures_util::StreamExt;
-use serde::Serialize;
+use jsonschema::Validator;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use slug::slugify;
-use sqlx::Pool;
-use sqlx::Sqlite;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::pin;
 use std::sync::Arc;
-use tauri::AppHandle;
-use tauri::Emitter;
 use uuid::Uuid;
 
+use crate::chat::attachment::ChatMessageAttachmentCmd;
+use crate::chat::document::DOCUMENT_CONTEXT_CHARS;
+use crate::chat::embedding::{ChatEmbeddingIndexer, ChatMessageSearchResult};
+use crate::chat::encryption::ChatEncryption;
+use crate::chat::export::{ChatExport, ChatExportFormat};
+use crate::chat::import::{self, ChatImportFormat, ChatImportReport};
+use crate::chat::recovery::{ChatTaskGuard, ChatTasks};
+use crate::chat::schema;
+use crate::chat::search::ChatSearchIndex;
+use crate::common::entity::agent::AgentRow;
+use crate::common::entity::chat::ChatCitation;
+use crate::common::entity::chat::ChatMessageAttachmentKind;
+use crate::common::entity::chat::ChatMessageAttachmentRow;
 use crate::common::entity::chat::ChatMessageRow;
 use crate::common::entity::chat::ChatRow;
+use crate::common::entity::chat::TagRow;
+use crate::event::{DomainEvent, EventBus};
+use crate::knowledge::{knowledge_citations, render_knowledge, KnowledgeIndexer, RETRIEVAL_LIMIT};
+use crate::tool::approval::ToolApprovals;
 use crate::{
-    agent::{Agent, AgentApi, AgentContext, AgentTextGenParamsApi},
-    chat::repo::{ChatRepo, CreateChatMessage, UpdateChatMessage},
-    common::{entity::chat::ChatMessageStatus, error::AppError, unit_of_work::UnitOfWorkFactory},
+    agent::{
+        Agent, AgentApi, AgentCapability, AgentContext, AgentSpeechParams, AgentTextGenOptions,
+        AgentToolCalls,
+    },
+    chat::repo::{
+        ChatFilter, ChatRepo, CreateChat, CreateChatMessage, CreateChatMessageAttachment,
+        CreateChatMessageSpeech, CreateTag, UpdateChat, UpdateChatMessage,
+    },
+    common::{
+        entity::chat::{ChatMessageKind, ChatMessageStatus, ChatSchemaViolation, ChatToolCall},
+        error::AppError,
+        unit_of_work::UnitOfWorkFactory,
+    },
 };
 
-#[derive(Serialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct ChatMessageResponseChunkPayload {
-    pub chat_id: Uuid,
-    pub id: Uuid,
-    pub text: String,
-}
+const MAX_TOOL_ROUNDS: usize = 8;
+const SEMANTIC_SEARCH_LIMIT: usize = 20;
+const SEARCH_LIMIT: usize = 50;
 
-#[derive(Serialize, Clone)]
+#[derive(Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
-pub struct ChatMessageRollbackPayload {
-    pub chat_id: Uuid,
-    pub message_id: Uuid,
+pub struct ChatFilterCmd {
+    pub pinned: Option<bool>,
+    pub starred: Option<bool>,
+    pub archived: Option<bool>,
+    pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub tag_ids: Vec<Uuid>,
 }
 
-#[derive(Serialize, Clone)]
+/// A message together with its attachments, including the images a model generated.
+#[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ChatMessageStatusChangedPayload {
-    pub chat_id: Uuid,
-    pub message_id: Uuid,
-    pub status: ChatMessageStatus,
+pub struct ChatMessageWithAttachments {
+    #[serde(flatten)]
+    pub message: ChatMessageRow,
+    pub attachments: Vec<ChatMessageAttachmentRow>,
 }
 
 #[tauri::command]
 pub async fn create_chat(
     content: String,
-    db_pool: tauri::State<'_, Arc<Pool<Sqlite>>>,
+    project_id: Option<Uuid>,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
 ) -> Result<Uuid, AppError> {
-    let chat_id = Uuid::new_v4();
-    let title = slugify(content);
-    sqlx::query("insert into chats (id, title) values (?1, ?2)")
-        .bind(chat_id)
-        .bind(title)
-        .execute(&**db_pool.inner())
-        .await
-        .map_err(|e| AppError::Unknown(Some(Box::new(e))))?;
-    Ok(chat_id)
+    let chat = chat_repo
+        .create_chat(CreateChat {
+            id: Uuid::new_v4(),
+            title: slugify(content),
+            created_at: None,
+            project_id,
+        })
+        .await?;
+    Ok(chat.id)
 }
 
 #[tauri::command]
 pub async fn send_chat_message(
     chat_id: Uuid,
     content: String,
-    app_handle: AppHandle,
+    attachments: Option<Vec<ChatMessageAttachmentCmd>>,
+    response_schema: Option<Value>,
+    knowledge_folder_ids: Option<Vec<Uuid>>,
     agent_context: tauri::State<'_, AgentContext>,
     unit_of_work_factory: tauri::State<'_, Arc<dyn UnitOfWorkFactory>>,
     static_chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+    tool_approvals: tauri::State<'_, Arc<ToolApprovals>>,
+    knowledge_indexer: tauri::State<'_, Arc<KnowledgeIndexer>>,
+    chat_tasks: tauri::State<'_, Arc<ChatTasks>>,
+    event_bus: tauri::State<'_, Arc<EventBus>>,
 ) -> Result<(), AppError> {
+    let response_validator = response_schema
+        .as_ref()
+        .map(schema::compile_response_schema)
+        .transpose()?;
+    let started = start_chat_message(
+        &event_bus,
+        &**unit_of_work_factory,
+        &chat_tasks,
+        chat_id,
+        content,
+        attachments.unwrap_or_default(),
+    )
+    .await?;
+    let agent = Agent::from(started.agent.clone());
+    let responder = ChatResponder {
+        event_bus: event_bus.inner().clone(),
+        chat_repo: static_chat_repo.inner().clone(),
+        agent_context: agent_context.inner().clone(),
+        tool_approvals: tool_approvals.inner().clone(),
+        chat_tasks: chat_tasks.inner().clone(),
+    };
+    let knowledge_indexer = knowledge_indexer.inner().clone();
+    let knowledge_query = started.user_chat_msg.content.clone();
+    tauri::async_runtime::spawn(async move {
+        let knowledge = match knowledge_folder_ids {
+            Some(folder_ids) if !folder_ids.is_empty() => knowledge_indexer
+                .search(&folder_ids, &knowledge_query, RETRIEVAL_LIMIT)
+                .await
+                .inspect_err(|e| log::error!("failed to search knowledge folders: {}", e))
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        let options = AgentTextGenOptions {
+            response_schema,
+            knowledge: (!knowledge.is_empty()).then(|| render_knowledge(&knowledge)),
+        };
+        responder
+            .respond(
+                agent,
+                started,
+                options,
+                knowledge_citations(&knowledge),
+                response_validator,
+            )
+            .await
+    });
+
+    Ok(())
+}
+
+/// A user message saved together with the empty model message its reply goes into.
+pub struct StartedChatMessage {
+    pub agent: AgentRow,
+    pub user_chat_msg: ChatMessageRow,
+    pub model_chat_msg: ChatMessageRow,
+    chat_task_guard: ChatTaskGuard,
+}
+
+/// Saves the user's message and a pending model message in one unit of work, once the
+/// agent for the chat is known to accept it. Nothing is saved or announced if any step
+/// fails.
+pub async fn start_chat_message(
+    event_bus: &EventBus,
+    unit_of_work_factory: &dyn UnitOfWorkFactory,
+    chat_tasks: &Arc<ChatTasks>,
+    chat_id: Uuid,
+    content: String,
+    attachments: Vec<ChatMessageAttachmentCmd>,
+) -> Result<StartedChatMessage, AppError> {
     let unit_of_work = unit_of_work_factory.create().await?;
+    let mut events = Vec::new();
     let (agent, user_chat_msg) = {
         let agent_repo = unit_of_work.agent_repo();
-        let current_agent = agent_repo
-            .get_current_agent()
-            .await?
-            .ok_or_else(|| AppError::AgentRequired)?;
-        let agent = Agent::from(current_agent);
         let chat_repo = unit_of_work.chat_repo();
+        let project_agent_id = match chat_repo
+            .get_chat(chat_id)
+            .await?
+            .and_then(|a| a.project_id)
+        {
+            Some(project_id) => unit_of_work
+                .project_repo()
+                .get_project(project_id)
+                .await?
+                .and_then(|a| a.default_agent_id),
+            None => None,
+        };
+        let project_agent = match project_agent_id {
+            Some(agent_id) => agent_repo.get_agent(agent_id).await?,
+            None => None,
+        };
+        let current_agent = match project_agent {
+            Some(agent) => agent,
+            None => agent_repo
+                .get_current_agent()
+                .await?
+                .ok_or_else(|| AppError::AgentRequired)?,
+        };
+        if current_agent.capability != AgentCapability::Chat {
+            return Err(AppError::AgentCapabilityUnsupported(current_agent.model));
+        }
+        let user_chat_msg_id = Uuid::new_v4();
+        let mut user_attachments = Vec::new();
+        for attachment in attachments {
+            user_attachments.push(attachment.into_create(user_chat_msg_id).await?);
+        }
+        if !current_agent.vision
+            && user_attachments
+                .iter()
+                .any(|a| a.kind == ChatMessageAttachmentKind::Image)
+        {
+            return Err(AppError::AgentVisionUnsupported(current_agent.model));
+        }
         let user_chat_msg = chat_repo
             .create_chat_message(CreateChatMessage {
-                id: Uuid::new_v4(),
+                created_at: None,
+                id: user_chat_msg_id,
                 chat_id,
                 role: "user".into(),
-                content: content.clone(),
+                content,
                 status: ChatMessageStatus::Completed,
+                kind: ChatMessageKind::Text,
+                tool_calls: None,
+                tool_call_id: None,
+                tool_name: None,
             })
             .await?;
-        let _ = app_handle
-            .emit("chat_message_created", &user_chat_msg)
-            .inspect_err(|e| {
-                log::error!("failed to emit response chunk: {e}");
-            });
+        for attachment in user_attachments {
+            let attachment = chat_repo.create_chat_message_attachment(attachment).await?;
+            let total_chars = attachment
+                .text
+                .as_deref()
+                .map(|a| a.chars().count())
+                .unwrap_or_default();
+            if total_chars > DOCUMENT_CONTEXT_CHARS {
+                events.push(DomainEvent::AttachmentTruncated {
+                    chat_id,
+                    message_id: user_chat_msg_id,
+                    attachment_id: attachment.id,
+                    name: attachment.name.clone(),
+                    included_chars: DOCUMENT_CONTEXT_CHARS,
+                    total_chars,
+                });
+            }
+        }
+        events.push(DomainEvent::ChatMessageCreated {
+            message: user_chat_msg.clone(),
+        });
 
-        (agent, user_chat_msg)
-        // (user_chat_msg, stream)
+        (current_agent, user_chat_msg)
     };
 
+    // Tracked from before it exists, so startup recovery never takes it for abandoned.
+    let model_chat_msg_id = Uuid::new_v4();
+    let chat_task_guard = chat_tasks.track(model_chat_msg_id);
     let model_chat_msg = {
         let chat_repo = unit_of_work.chat_repo();
         chat_repo
             .create_chat_message(CreateChatMessage {
-                id: Uuid::new_v4(),
+                created_at: None,
+                id: model_chat_msg_id,
                 chat_id,
                 role: "model".into(),
                 content: String::new(),
                 status: ChatMessageStatus::Pending,
+                kind: ChatMessageKind::Text,
+                tool_calls: None,
+                tool_call_id: None,
+                tool_name: None,
             })
             .await?
     };
-    let _ = app_handle
-        .emit("chat_message_created", model_chat_msg.clone())
-        .inspect_err(|e| {
-            log::error!("failed to emit response chunk: {e}");
-        });
+    events.push(DomainEvent::ChatMessageCreated {
+        message: model_chat_msg.clone(),
+    });
 
-    unit_of_work.commit().await.inspect_err(|_| {
-        let _ = app_handle.emit(
-            "chat_message_rollback",
-            ChatMessageRollbackPayload {
-                chat_id: chat_id,
-                message_id: user_chat_msg.id,
-            },
-        );
-        let _ = app_handle.emit(
-            "chat_message_rollback",
-            ChatMessageRollbackPayload {
-                chat_id: chat_id,
-                message_id: model_chat_msg.id,
-            },
-        );
-    })?;
-    let chat_repo = static_chat_repo.inner().clone();
-    let agent_context = agent_context.inner().clone();
-    tauri::async_runtime::spawn(async move {
-        let mut config = agent
-            .create_text_gen_params(agent_context.clone(), chat_id)
-            .await?
-            .ok_or_else(|| AppError::AgentTextGenParamsRequired)?;
-        config.push_message_str(&content);
-        let mut stream = agent
-            .generate_text(agent_context.clone(), config)
+    unit_of_work.commit().await?;
+    for event in events {
+        event_bus.publish(event);
+    }
+
+    Ok(StartedChatMessage {
+        agent,
+        user_chat_msg,
+        model_chat_msg,
+        chat_task_guard,
+    })
+}
+
+/// What a model's reply needs from the app's managed state. Commands build it from
+/// `tauri::State`; tests build it around in-memory repos and a recording subscriber.
+pub struct ChatResponder {
+    pub event_bus: Arc<EventBus>,
+    pub chat_repo: Arc<dyn ChatRepo>,
+    pub agent_context: AgentContext,
+    pub tool_approvals: Arc<ToolApprovals>,
+    pub chat_tasks: Arc<ChatTasks>,
+}
+
+impl ChatResponder {
+    /// Streams the agent's reply into the started message. Each round of tool calls is
+    /// answered with the tools' results and followed by a new model message, until the
+    /// agent answers in text or runs out of rounds.
+    pub async fn respond<A>(
+        &self,
+        agent: A,
+        started: StartedChatMessage,
+        options: AgentTextGenOptions,
+        citations: Vec<ChatCitation>,
+        response_validator: Option<Validator>,
+    ) -> Result<(), AppError>
+    where
+        A: AgentApi + Clone + Send + Sync,
+        A::TextGenParams: Send,
+    {
+        let event_bus = &self.event_bus;
+        let chat_repo = &self.chat_repo;
+        let agent_context = &self.agent_context;
+        let agent_id = started.agent.id;
+        let chat_id = started.model_chat_msg.chat_id;
+        let mut model_chat_msg = started.model_chat_msg;
+        let mut chat_task_guards = vec![started.chat_task_guard];
+        let mut tool_rounds = 0;
+        loop {
+            let response = stream_chat_response(
+                event_bus,
+                chat_repo,
+                agent_context,
+                agent.clone(),
+                options.clone(),
+                chat_id,
+                model_chat_msg.id,
+            )
             .await?;
-        let mut text = String::new();
-        let mut chunk_count = 0;
-        while let Some(item) = stream.next().await {
-            match item {
-                Ok(result) => {
-                    text.push_str(&result.text);
-                    chunk_count += 1;
-                    if chunk_count == 5 {
-                        chunk_count = 0;
-                        let _ = chat_repo
-                            .update_chat_message(
-                                model_chat_msg.id,
-                                UpdateChatMessage {
-                                    content: Some(text.clone()),
-                                    ..Default::default()
-                                },
-                            )
-                            .await
-                            .inspect_err(|e| {
-                                log::error!("failed to update chat message content: {e}");
-                            });
-                    }
-                    let _ = app_handle
-                        .emit(
-                            "chat_message_response_chunk",
-                            ChatMessageResponseChunkPayload {
-                                chat_id,
-                                id: model_chat_msg.id,
-                                text: result.text,
-                            },
-                        )
-                        .inspect_err(|e| {
-                            log::error!("failed to emit response chunk: {e}");
-                        });
+            if response.tool_calls.is_empty() || tool_rounds == MAX_TOOL_ROUNDS {
+                if !response.tool_calls.is_empty() {
+                    log::warn!("chat {chat_id} exceeded {MAX_TOOL_ROUNDS} tool rounds");
                 }
-                Err(err) => {
-                    log::error!("Stream error: {err}");
-                    let _ = chat_repo
-                        .update_chat_message(
-                            model_chat_msg.id,
-                            UpdateChatMessage {
-                                content: Some(text.clone()),
-                                status: Some(ChatMessageStatus::Failed),
-                                ..Default::default()
-                            },
-                        )
-                        .await
-                        .inspect_err(|e| {
-                            log::error!("failed to update chat message status and content: {e}");
-                        });
-                    return Err(AppError::Unknown(Some(Box::new(err))));
+                let schema_errors = response_validator
+                    .as_ref()
+                    .map(|a| schema::validate_response(a, &response.text))
+                    .unwrap_or_default();
+                if !schema_errors.is_empty() {
+                    reject_chat_message(
+                        event_bus,
+                        chat_repo,
+                        chat_id,
+                        model_chat_msg.id,
+                        response.text,
+                        schema_errors,
+                    )
+                    .await;
+                    break;
                 }
+                complete_chat_message(
+                    event_bus,
+                    chat_repo,
+                    chat_id,
+                    model_chat_msg.id,
+                    UpdateChatMessage {
+                        content: Some(response.text),
+                        citations: (!citations.is_empty()).then(|| citations.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await;
+                break;
             }
-        }
-        let _ = chat_repo
-            .update_chat_message(
+            tool_rounds += 1;
+
+            complete_chat_message(
+                event_bus,
+                chat_repo,
+                chat_id,
                 model_chat_msg.id,
                 UpdateChatMessage {
-                    content: match chunk_count {
-                        0 => None,
-                        _ => Some(text.clone()),
-                    },
-                    status: Some(ChatMessageStatus::Completed),
+                    content: Some(response.text),
+                    kind: Some(ChatMessageKind::ToolCall),
+                    tool_calls: Some(response.tool_calls.clone()),
                     ..Default::default()
                 },
             )
-            .await
-            .inspect_err(|e| {
-                log::error!("failed to update chat message status and content: {e}");
+            .await;
+            event_bus.publish(DomainEvent::ToolCallsRequested {
+                chat_id,
+                message_id: model_chat_msg.id,
+                tool_calls: response.tool_calls.clone(),
             });
-        let _ = app_handle
-            .emit(
-                "chat_message_status_changed",
-                ChatMessageStatusChangedPayload {
+
+            let disabled_tools = agent_context
+                .agent_repo
+                .get_agent_disabled_tools(agent_id)
+                .await?;
+            for call in response.tool_calls {
+                let content = if disabled_tools.contains(&call.name) {
+                    log::warn!("agent called disabled tool {}", call.name);
+                    json!({ "error": "this tool is disabled for the current agent" }).to_string()
+                } else if agent_context
+                    .tool_registry
+                    .requires_approval(&call.name)
+                    .await
+                    && !self
+                        .tool_approvals
+                        .request(event_bus, chat_repo, chat_id, model_chat_msg.id, &call)
+                        .await?
+                {
+                    json!({ "error": "the user declined to run this tool" }).to_string()
+                } else {
+                    agent_context.tool_registry.run(&call).await
+                };
+                let tool_chat_msg = chat_repo
+                    .create_chat_message(CreateChatMessage {
+                        created_at: None,
+                        id: Uuid::new_v4(),
+                        chat_id,
+                        role: "tool".into(),
+                        content,
+                        status: ChatMessageStatus::Completed,
+                        kind: ChatMessageKind::ToolResult,
+                        tool_calls: None,
+                        tool_call_id: Some(call.id),
+                        tool_name: Some(call.name),
+                    })
+                    .await?;
+                event_bus.publish(DomainEvent::ChatMessageCreated {
+                    message: tool_chat_msg,
+                });
+            }
+
+            let model_chat_msg_id = Uuid::new_v4();
+            chat_task_guards.push(self.chat_tasks.track(model_chat_msg_id));
+            model_chat_msg = chat_repo
+                .create_chat_message(CreateChatMessage {
+                    created_at: None,
+                    id: model_chat_msg_id,
                     chat_id,
-                    message_id: model_chat_msg.id,
-                    status: ChatMessageStatus::Completed,
-                },
-            )
-            .inspect_err(|e| {
-                log::error!("failed to emit chat_message_completed: {e}");
+                    role: "model".into(),
+                    content: String::new(),
+                    status: ChatMessageStatus::Pending,
+                    kind: ChatMessageKind::Text,
+                    tool_calls: None,
+                    tool_call_id: None,
+                    tool_name: None,
+                })
+                .await?;
+            event_bus.publish(DomainEvent::ChatMessageCreated {
+                message: model_chat_msg.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+struct ChatResponse {
+    text: String,
+    tool_calls: Vec<ChatToolCall>,
+}
+
+/// Streams one reply into `message_id`. If the agent fails, the message is marked as
+/// failed with whatever text arrived before the error.
+async fn stream_chat_response<A>(
+    event_bus: &EventBus,
+    chat_repo: &Arc<dyn ChatRepo>,
+    agent_context: &AgentContext,
+    agent: A,
+    options: AgentTextGenOptions,
+    chat_id: Uuid,
+    message_id: Uuid,
+) -> Result<ChatResponse, AppError>
+where
+    A: AgentApi + Send + Sync,
+    A::TextGenParams: Send,
+{
+    let mut text = String::new();
+    let result: Result<Vec<ChatToolCall>, AppError> = async {
+        let config = agent
+            .create_text_gen_params(agent_context.clone(), chat_id, options)
+            .await?
+            .ok_or_else(|| AppError::AgentTextGenParamsRequired)?;
+        let mut stream = agent.generate_text(agent_context.clone(), config).await?;
+        let mut tool_calls = AgentToolCalls::default();
+        let mut chunk_count = 0;
+        let mut image_count = 0;
+        while let Some(item) = stream.next().await {
+            let result = item?;
+            for delta in result.tool_calls {
+                tool_calls.push(delta);
+            }
+            for image in result.images {
+                image_count += 1;
+                let attachment = chat_repo
+                    .create_chat_message_attachment(CreateChatMessageAttachment {
+                        id: Uuid::new_v4(),
+                        message_id,
+                        kind: ChatMessageAttachmentKind::Image,
+                        name: Some(format!(
+                            "image-{}.{}",
+                            image_count,
+                            image.mime_type.strip_prefix("image/").unwrap_or("bin")
+                        )),
+                        mime_type: image.mime_type,
+                        data: Some(image.data),
+                        path: None,
+                        text: None,
+                    })
+                    .await?;
+                event_bus.publish(DomainEvent::ChunkReceived {
+                    chat_id,
+                    message_id,
+                    text: String::new(),
+                    attachment_id: Some(attachment.id),
+                });
+            }
+            if result.text.is_empty() {
+                continue;
+            }
+            text.push_str(&result.text);
+            chunk_count += 1;
+            if chunk_count == 5 {
+                chunk_count = 0;
+                let _ = chat_repo
+                    .update_chat_message(
+                        message_id,
+                        UpdateChatMessage {
+                            content: Some(text.clone()),
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                    .inspect_err(|e| {
+                        log::error!("failed to update chat message content: {e}");
+                    });
+            }
+            event_bus.publish(DomainEvent::ChunkReceived {
+                chat_id,
+                message_id,
+                text: result.text,
+                attachment_id: None,
             });
-        Ok::<(), AppError>(())
+        }
+        Ok(tool_calls.finish())
+    }
+    .await;
+    match result {
+        Ok(tool_calls) => Ok(ChatResponse { text, tool_calls }),
+        Err(e) => {
+            fail_chat_message(event_bus, chat_repo, chat_id, message_id, text, &e).await;
+            Err(e)
+        }
+    }
+}
+
+async fn complete_chat_message(
+    event_bus: &EventBus,
+    chat_repo: &Arc<dyn ChatRepo>,
+    chat_id: Uuid,
+    message_id: Uuid,
+    update: UpdateChatMessage,
+) {
+    let _ = chat_repo
+        .update_chat_message(
+            message_id,
+            UpdateChatMessage {
+                status: Some(ChatMessageStatus::Completed),
+                ..update
+            },
+        )
+        .await
+        .inspect_err(|e| {
+            log::error!("failed to update chat message status and content: {e}");
+        });
+    event_bus.publish(DomainEvent::MessageStatusChanged {
+        chat_id,
+        message_id,
+        status: ChatMessageStatus::Completed,
     });
+}
 
-    Ok(())
+/// Fails a reply the agent could not finish, keeping the text streamed so far.
+async fn fail_chat_message(
+    event_bus: &EventBus,
+    chat_repo: &Arc<dyn ChatRepo>,
+    chat_id: Uuid,
+    message_id: Uuid,
+    content: String,
+    error: &AppError,
+) {
+    event_bus.publish(DomainEvent::ResponseFailed {
+        chat_id,
+        message_id,
+        error: error.to_string(),
+    });
+    let _ = chat_repo
+        .update_chat_message(
+            message_id,
+            UpdateChatMessage {
+                content: Some(content),
+                status: Some(ChatMessageStatus::Failed),
+                ..Default::default()
+            },
+        )
+        .await
+        .inspect_err(|e| {
+            log::error!("failed to update chat message status and content: {e}");
+        });
+    event_bus.publish(DomainEvent::MessageStatusChanged {
+        chat_id,
+        message_id,
+        status: ChatMessageStatus::Failed,
+    });
+}
+
+/// Fails a structured response that does not match the requested schema, keeping the
+/// text so the user can see what the model produced.
+async fn reject_chat_message(
+    event_bus: &EventBus,
+    chat_repo: &Arc<dyn ChatRepo>,
+    chat_id: Uuid,
+    message_id: Uuid,
+    content: String,
+    schema_errors: Vec<ChatSchemaViolation>,
+) {
+    let _ = chat_repo
+        .update_chat_message(
+            message_id,
+            UpdateChatMessage {
+                content: Some(content),
+                status: Some(ChatMessageStatus::Failed),
+                schema_errors: Some(schema_errors.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .inspect_err(|e| {
+            log::error!("failed to update chat message status and schema errors: {e}");
+        });
+    event_bus.publish(DomainEvent::SchemaValidationFailed {
+        chat_id,
+        message_id,
+        errors: schema_errors,
+    });
+    event_bus.publish(DomainEvent::MessageStatusChanged {
+        chat_id,
+        message_id,
+        status: ChatMessageStatus::Failed,
+    });
 }
 
 #[tauri::command]
@@ -239,6 +660,878 @@ pub async fn get_chat(
 pub async fn get_chat_messages(
     id: Uuid,
     chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
-) -> Result<Vec<ChatMessageRow>, AppError> {
-    chat_repo.get_chat_messages(id).await
+) -> Result<Vec<ChatMessageWithAttachments>, AppError> {
+    let mut attachments = HashMap::<Uuid, Vec<ChatMessageAttachmentRow>>::new();
+    for attachment in chat_repo.get_chat_attachments(id).await? {
+        attachments
+            .entry(attachment.message_id)
+            .or_default()
+            .push(attachment);
+    }
+    Ok(chat_repo
+        .get_chat_messages(id)
+        .await?
+        .into_iter()
+        .map(|a| ChatMessageWithAttachments {
+            attachments: attachments.remove(&a.id).unwrap_or_default(),
+            message: a,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_chat_attachments(
+    id: Uuid,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<Vec<ChatMessageAttachmentRow>, AppError> {
+    chat_repo.get_chat_attachments(id).await
+}
+
+#[tauri::command]
+pub async fn get_chats(
+    filter: Option<ChatFilterCmd>,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<Vec<ChatRow>, AppError> {
+    let filter = filter.unwrap_or_default();
+    chat_repo
+        .get_chats(ChatFilter {
+            pinned: filter.pinned,
+            starred: filter.starred,
+            archived: Some(filter.archived.unwrap_or(false)),
+            project_id: filter.project_id,
+            tag_ids: filter.tag_ids,
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn pin_chat(
+    id: Uuid,
+    pinned: bool,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<u64, AppError> {
+    chat_repo
+        .update_chat(
+            id,
+            UpdateChat {
+                pinned: Some(pinned),
+                ..Default::default()
+            },
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn star_chat(
+    id: Uuid,
+    starred: bool,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<u64, AppError> {
+    chat_repo
+        .update_chat(
+            id,
+            UpdateChat {
+                starred: Some(starred),
+                ..Default::default()
+            },
+        )
+        .await
+}
+
+/// Archived chats are left out of `get_chats` unless asked for.
+#[tauri::command]
+pub async fn archive_chat(
+    id: Uuid,
+    archived: bool,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+    event_bus: tauri::State<'_, Arc<EventBus>>,
+) -> Result<u64, AppError> {
+    let count = chat_repo
+        .update_chat(
+            id,
+            UpdateChat {
+                archived: Some(archived),
+                ..Default::default()
+            },
+        )
+        .await?;
+    event_bus.publish(DomainEvent::ChatArchived {
+        chat_id: id,
+        archived,
+    });
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn star_chat_message(
+    id: Uuid,
+    starred: bool,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<(), AppError> {
+    chat_repo
+        .update_chat_message(
+            id,
+            UpdateChatMessage {
+                starred: Some(starred),
+                ..Default::default()
+            },
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn get_tags(
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<Vec<TagRow>, AppError> {
+    chat_repo.get_tags().await
+}
+
+#[tauri::command]
+pub async fn delete_tag(
+    id: Uuid,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<u64, AppError> {
+    chat_repo.delete_tag(id).await
+}
+
+#[tauri::command]
+pub async fn get_chat_tags(
+    id: Uuid,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<Vec<TagRow>, AppError> {
+    chat_repo.get_chat_tags(id).await
+}
+
+#[tauri::command]
+pub async fn tag_chat(
+    id: Uuid,
+    name: String,
+    unit_of_work_factory: tauri::State<'_, Arc<dyn UnitOfWorkFactory>>,
+) -> Result<TagRow, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidTag("name is required".into()));
+    }
+    let unit_of_work = unit_of_work_factory.create().await?;
+    let tag = {
+        let chat_repo = unit_of_work.chat_repo();
+        let tag = chat_repo
+            .upsert_tag(CreateTag {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+            })
+            .await?;
+        chat_repo.tag_chat(id, tag.id).await?;
+        tag
+    };
+    unit_of_work.commit().await?;
+    Ok(tag)
+}
+
+#[tauri::command]
+pub async fn untag_chat(
+    id: Uuid,
+    tag_id: Uuid,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<u64, AppError> {
+    chat_repo.untag_chat(id, tag_id).await
+}
+
+#[tauri::command]
+pub async fn export_chat(
+    id: Uuid,
+    format: ChatExportFormat,
+    path: PathBuf,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<(), AppError> {
+    let chat = chat_repo
+        .get_chat(id)
+        .await?
+        .ok_or_else(|| AppError::ChatNotFound(id))?;
+    ChatExport::load(&**chat_repo, vec![chat])
+        .await?
+        .write(format, &path)
+        .await
+}
+
+#[tauri::command]
+pub async fn export_all_chats(
+    format: ChatExportFormat,
+    path: PathBuf,
+    chat_repo: tauri::State<'_, Arc<dyn ChatRepo>>,
+) -> Result<(), AppError> {
+    let chats = chat_repo.get_chats(ChatFilter::default()).await?;
+    ChatExport::load(&**chat_repo, chats)
+        .await?
+        .write(format, &path)
+        .await
+}
+
+#[tauri::command]
+pub async fn import_chats(
+    path: PathBuf,
+    format: Option<ChatImportFormat>,
+    unit_of_work_factory: tauri::State<'_, Arc<dyn UnitOfWorkFactory>>,
+    chat_embedding_indexer: tauri::State<'_, Arc<ChatEmbeddingIndexer>>,
+) -> Result<ChatImportReport, AppError> {
+    let input = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(AppError::from)?;
+    let (chats, mut report) = import::parse(&input, format)?;
+    let unit_of_work = unit_of_work_factory.create().await?;
+    import::import(&*unit_of_work.chat_repo(), chats, &mut report).await?;
+    unit_of_work.commit().await?;
+    chat_embedding_indexer.notify();
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn semantic_search_chats(
+    query: String,
+    limit: Option<usize>,
+    chat_embedding_indexer: tauri::State<'_, Arc<ChatEmbeddingIndexer>>,
+) -> Result<Vec<ChatMessageSearchResult>, AppError> {
+    chat_embedding_indexer
+        .search(&query, limit.unwrap_or(SEMANTIC_SEARCH_LIMIT))
+        .await
+}
+
+#[tauri::command]
+pub async fn search_chats(
+    query: String,
+    limit: Option<usize>,
+    chat_search_index: tauri::State<'_, Arc<ChatSearchIndex>>,
+) -> Result<Vec<ChatMessageSearchResult>, AppError> {
+    Ok(chat_search_index.search(&query, limit.unwrap_or(SEARCH_LIMIT)))
+}
+
+#[tauri::command]
+pub async fn get_chat_encryption(
+    chat_encryption: tauri::State<'_, Arc<ChatEncryption>>,
+) -> Result<bool, AppError> {
+    Ok(chat_encryption.is_enabled())
+}
+
+/// Turns encryption of chat titles and message content on or off and rewrites the
+/// existing rows to match. Returns how many values were rewritten.
+#[tauri::command]
+pub async fn update_chat_encryption(
+    enabled: bool,
+    chat_encryption: tauri::State<'_, Arc<ChatEncryption>>,
+) -> Result<usize, AppError> {
+    chat_encryption.set_enabled(enabled).await
+}
+
+/// Reads a completed model message aloud with a speech agent and saves the audio as an
+/// attachment of the message. Audio for the same agent and voice is reused until the
+/// message content changes.
+#[tauri::command]
+pub async fn synthesize_message_audio(
+    message_id: Uuid,
+    agent_id: Uuid,
+    voice: Option<String>,
+    agent_context: tauri::State<'_, AgentContext>,
+    unit_of_work_factory: tauri::State<'_, Arc<dyn UnitOfWorkFactory>>,
+) -> Result<ChatMessageAttachmentRow, AppError> {
+    let message = agent_context
+        .chat_repo
+        .get_chat_message(message_id)
+        .await?
+        .ok_or(AppError::ChatMessageNotFound(message_id))?;
+    if message.role != "model"
+        || !matches!(message.status, ChatMessageStatus::Completed)
+        || message.content.trim().is_empty()
+    {
+        return Err(AppError::ChatMessageNotSpeakable(message_id));
+    }
+    let agent = agent_context
+        .agent_repo
+        .get_agent(agent_id)
+        .await?
+        .ok_or_else(|| AppError::AgentRequired)?;
+    if agent.capability != AgentCapability::Speech {
+        return Err(AppError::AgentCapabilityUnsupported(agent.model));
+    }
+    let agent = Agent::from(agent);
+    let voice = match voice.filter(|a| !a.trim().is_empty()) {
+        Some(voice) => voice,
+        None => agent
+            .default_speech_voice()
+            .ok_or_else(|| AppError::AgentCapabilityUnsupported("speech".into()))?
+            .to_string(),
+    };
+    if let Some(attachment) = agent_context
+        .chat_repo
+        .get_chat_message_speech(message_id, agent_id, &voice)
+        .await?
+    {
+        return Ok(attachment);
+    }
+
+    let speech = agent
+        .synthesize_speech(
+            agent_context.inner().clone(),
+            AgentSpeechParams {
+                input: message.content,
+                voice: voice.clone(),
+            },
+        )
+        .await?;
+    let unit_of_work = unit_of_work_factory.create().await?;
+    let attachment = {
+        let chat_repo = unit_of_work.chat_repo();
+        chat_repo
+            .delete_chat_message_speech(message_id, agent_id, &voice)
+            .await?;
+        let attachment = chat_repo
+            .create_chat_message_attachment(CreateChatMessageAttachment {
+                id: Uuid::new_v4(),
+                message_id,
+                kind: ChatMessageAttachmentKind::Audio,
+                name: Some(format!(
+                    "speech-{}.{}",
+                    slugify(&voice),
+                    speech.mime_type.strip_prefix("audio/").unwrap_or("bin")
+                )),
+                mime_type: speech.mime_type,
+                data: Some(speech.data),
+                path: None,
+                text: None,
+            })
+            .await?;
+        chat_repo
+            .create_chat_message_speech(CreateChatMessageSpeech {
+                attachment_id: attachment.id,
+                message_id,
+                agent_id,
+                voice,
+                content_revision: message.content_revision,
+            })
+            .await?;
+        attachment
+    };
+    unit_of_work.commit().await?;
+    Ok(attachment)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, pin::Pin, sync::Mutex};
+
+    use async_trait::async_trait;
+    use futures::Stream;
+
+    use super::*;
+    use crate::{
+        agent::{
+            repo::{memory::MemoryAgentRepo, AgentRepo, CreateAgent, UpdateCurrentAgent},
+            AgentProvider, AgentTextGenResult, AgentToolCallDelta,
+        },
+        chat::repo::memory::MemoryChatRepo,
+        cipher::Cipher,
+        common::{http::HttpClientManager, unit_of_work::memory::MemoryUnitOfWorkFactory},
+        database::memory::{create_database, MemoryDatabase},
+        event::memory::RecordedEvents,
+        project::repo::{memory::MemoryProjectRepo, CreateProject, ProjectRepo},
+        tool::{calculator::CalculatorTool, ToolRegistry},
+    };
+
+    type Turn = Vec<Result<AgentTextGenResult, AppError>>;
+
+    /// Streams one scripted turn per request, and has nothing to say once the script
+    /// runs out.
+    #[derive(Clone)]
+    struct ScriptedAgent {
+        turns: Arc<Mutex<VecDeque<Turn>>>,
+    }
+
+    impl ScriptedAgent {
+        fn new(turns: Vec<Turn>) -> Self {
+            Self {
+                turns: Arc::new(Mutex::new(turns.into())),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AgentApi for ScriptedAgent {
+        type TextGenParams = Turn;
+
+        async fn generate_text(
+            self,
+            _context: AgentContext,
+            params: Self::TextGenParams,
+        ) -> Result<
+            Pin<Box<dyn Stream<Item = Result<AgentTextGenResult, AppError>> + Send>>,
+            AppError,
+        > {
+            Ok(Box::pin(futures::stream::iter(params)))
+        }
+
+        async fn create_text_gen_params(
+            &self,
+            _context: AgentContext,
+            _chat_id: Uuid,
+            _options: AgentTextGenOptions,
+        ) -> Result<Option<Self::TextGenParams>, AppError> {
+            Ok(self.turns.lock().unwrap().pop_front())
+        }
+    }
+
+    struct PlainCipher;
+
+    impl Cipher for PlainCipher {
+        fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
+            Ok(plaintext.to_vec())
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, AppError> {
+            Ok(ciphertext.to_vec())
+        }
+
+        fn encrypt_str_base64(&self, plaintext: &str) -> Result<String, AppError> {
+            Ok(plaintext.to_string())
+        }
+
+        fn decrypt_base64_str(&self, ciphertext_base64: &str) -> Result<String, AppError> {
+            Ok(ciphertext_base64.to_string())
+        }
+    }
+
+    fn text(text: &str) -> Result<AgentTextGenResult, AppError> {
+        Ok(AgentTextGenResult {
+            text: text.into(),
+            tool_calls: Vec::new(),
+            images: Vec::new(),
+        })
+    }
+
+    fn tool_call(
+        index: usize,
+        id: Option<&str>,
+        name: Option<&str>,
+        arguments: &str,
+    ) -> Result<AgentTextGenResult, AppError> {
+        Ok(AgentTextGenResult {
+            text: String::new(),
+            tool_calls: vec![AgentToolCallDelta {
+                index,
+                id: id.map(Into::into),
+                name: name.map(Into::into),
+                arguments: arguments.into(),
+            }],
+            images: Vec::new(),
+        })
+    }
+
+    struct Fixture {
+        database: MemoryDatabase,
+        chat_repo: Arc<dyn ChatRepo>,
+        agent_repo: Arc<dyn AgentRepo>,
+        project_repo: Arc<dyn ProjectRepo>,
+        unit_of_work_factory: MemoryUnitOfWorkFactory,
+        chat_tasks: Arc<ChatTasks>,
+        event_bus: Arc<EventBus>,
+        events: Arc<RecordedEvents>,
+        agent_id: Uuid,
+        chat_id: Uuid,
+    }
+
+    impl Fixture {
+        async fn new() -> Self {
+            let database = create_database();
+            let chat_repo: Arc<dyn ChatRepo> = Arc::new(MemoryChatRepo::new(database.clone()));
+            let agent_repo: Arc<dyn AgentRepo> = Arc::new(MemoryAgentRepo::new(database.clone()));
+            let project_repo: Arc<dyn ProjectRepo> =
+                Arc::new(MemoryProjectRepo::new(database.clone()));
+            let agent_id = create_agent(&agent_repo, "chat-model", AgentCapability::Chat).await;
+            agent_repo
+                .update_current_agent(UpdateCurrentAgent { agent_id })
+                .await
+                .unwrap();
+            let chat_id = Uuid::new_v4();
+            chat_repo
+                .create_chat(CreateChat {
+                    id: chat_id,
+                    title: "test".into(),
+                    created_at: None,
+                    project_id: None,
+                })
+                .await
+                .unwrap();
+            let event_bus = Arc::new(EventBus::new());
+            let events = Arc::new(RecordedEvents::default());
+            event_bus.subscribe(events.clone());
+            Self {
+                unit_of_work_factory: MemoryUnitOfWorkFactory::new(database.clone()),
+                database,
+                chat_repo,
+                agent_repo,
+                project_repo,
+                chat_tasks: Arc::new(ChatTasks::new()),
+                event_bus,
+                events,
+                agent_id,
+                chat_id,
+            }
+        }
+
+        async fn start(&self, content: &str) -> Result<StartedChatMessage, AppError> {
+            start_chat_message(
+                &self.event_bus,
+                &self.unit_of_work_factory,
+                &self.chat_tasks,
+                self.chat_id,
+                content.into(),
+                Vec::new(),
+            )
+            .await
+        }
+
+        async fn responder(&self) -> ChatResponder {
+            let tool_registry = Arc::new(ToolRegistry::new());
+            tool_registry.register(Arc::new(CalculatorTool)).await;
+            let agent_context = AgentContext::new(
+                Arc::new(HttpClientManager::new()),
+                self.agent_repo.clone(),
+                self.chat_repo.clone(),
+                self.project_repo.clone(),
+                tool_registry,
+                Arc::new(PlainCipher),
+            );
+            ChatResponder {
+                event_bus: self.event_bus.clone(),
+                chat_repo: self.chat_repo.clone(),
+                agent_context,
+                tool_approvals: Arc::new(ToolApprovals::new()),
+                chat_tasks: self.chat_tasks.clone(),
+            }
+        }
+
+        async fn send(&self, content: &str, turns: Vec<Turn>) -> Result<(), AppError> {
+            self.send_with_schema(content, turns, None).await
+        }
+
+        async fn send_with_schema(
+            &self,
+            content: &str,
+            turns: Vec<Turn>,
+            response_schema: Option<Value>,
+        ) -> Result<(), AppError> {
+            let response_validator = response_schema
+                .as_ref()
+                .map(schema::compile_response_schema)
+                .transpose()?;
+            let started = self.start(content).await?;
+            self.responder()
+                .await
+                .respond(
+                    ScriptedAgent::new(turns),
+                    started,
+                    AgentTextGenOptions::default(),
+                    Vec::new(),
+                    response_validator,
+                )
+                .await
+        }
+
+        async fn messages(&self) -> Vec<ChatMessageRow> {
+            self.chat_repo
+                .get_chat_messages(self.chat_id)
+                .await
+                .unwrap()
+        }
+    }
+
+    async fn create_agent(
+        agent_repo: &Arc<dyn AgentRepo>,
+        model: &str,
+        capability: AgentCapability,
+    ) -> Uuid {
+        agent_repo
+            .create_agent(CreateAgent {
+                id: Uuid::new_v4(),
+                provider: AgentProvider::Groq,
+                model: model.into(),
+                vision: false,
+                capability,
+            })
+            .await
+            .unwrap()
+            .id
+    }
+
+    #[tokio::test]
+    async fn saves_user_message_and_pending_reply() {
+        let fixture = Fixture::new().await;
+        let started = fixture.start("hello").await.unwrap();
+
+        assert_eq!(started.agent.id, fixture.agent_id);
+        let messages = fixture.messages().await;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[0].content, "hello");
+        assert!(matches!(messages[0].status, ChatMessageStatus::Completed));
+        assert_eq!(messages[1].id, started.model_chat_msg.id);
+        assert_eq!(messages[1].role, "model");
+        assert!(matches!(messages[1].status, ChatMessageStatus::Pending));
+    }
+
+    #[tokio::test]
+    async fn streams_reply_into_pending_message() {
+        let fixture = Fixture::new().await;
+        fixture
+            .send("hello", vec![vec![text("Hi "), text("there"), text("!")]])
+            .await
+            .unwrap();
+
+        let messages = fixture.messages().await;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content, "Hi there!");
+        assert_eq!(messages[1].kind, ChatMessageKind::Text);
+        assert!(matches!(messages[1].status, ChatMessageStatus::Completed));
+        assert!(fixture
+            .chat_repo
+            .get_pending_chat_messages()
+            .await
+            .unwrap()
+            .is_empty());
+
+        let events = fixture.events.events();
+        let chunks = events
+            .iter()
+            .filter_map(|a| match a {
+                DomainEvent::ChunkReceived { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(chunks, ["Hi ", "there", "!"]);
+        assert!(matches!(
+            events.last(),
+            Some(DomainEvent::MessageStatusChanged {
+                message_id,
+                status: ChatMessageStatus::Completed,
+                ..
+            }) if *message_id == messages[1].id
+        ));
+    }
+
+    #[tokio::test]
+    async fn answers_tool_calls_before_replying() {
+        let fixture = Fixture::new().await;
+        fixture
+            .send(
+                "what is 2 + 3?",
+                vec![
+                    vec![
+                        tool_call(0, Some("call_1"), Some("calculate"), "{\"expression\":"),
+                        tool_call(0, None, None, "\"2 + 3\"}"),
+                    ],
+                    vec![text("It's 5.")],
+                ],
+            )
+            .await
+            .unwrap();
+
+        let messages = fixture.messages().await;
+        let roles = messages.iter().map(|a| a.role.as_str()).collect::<Vec<_>>();
+        assert_eq!(roles, ["user", "model", "tool", "model"]);
+
+        assert_eq!(messages[1].kind, ChatMessageKind::ToolCall);
+        assert!(matches!(messages[1].status, ChatMessageStatus::Completed));
+        let tool_calls = &messages[1].tool_calls.as_ref().unwrap().0;
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0].id, "call_1");
+        assert_eq!(tool_calls[0].arguments, json!({ "expression": "2 + 3" }));
+
+        assert_eq!(messages[2].kind, ChatMessageKind::ToolResult);
+        assert_eq!(messages[2].tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(messages[2].tool_name.as_deref(), Some("calculate"));
+        let result = serde_json::from_str::<Value>(&messages[2].content).unwrap();
+        assert_eq!(result["result"], json!(5.0));
+
+        assert_eq!(messages[3].content, "It's 5.");
+        assert!(matches!(messages[3].status, ChatMessageStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn does_not_run_disabled_tools() {
+        let fixture = Fixture::new().await;
+        fixture
+            .agent_repo
+            .update_agent_tool(fixture.agent_id, "calculate", false)
+            .await
+            .unwrap();
+        fixture
+            .send(
+                "what is 2 + 3?",
+                vec![
+                    vec![tool_call(
+                        0,
+                        Some("call_1"),
+                        Some("calculate"),
+                        "{\"expression\":\"2 + 3\"}",
+                    )],
+                    vec![text("I can't calculate that.")],
+                ],
+            )
+            .await
+            .unwrap();
+
+        let messages = fixture.messages().await;
+        assert_eq!(messages[2].role, "tool");
+        assert!(messages[2].content.contains("disabled"));
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_tool_rounds() {
+        let fixture = Fixture::new().await;
+        let turns = (0..=MAX_TOOL_ROUNDS)
+            .map(|i| {
+                vec![tool_call(
+                    0,
+                    Some(&format!("call_{}", i)),
+                    Some("calculate"),
+                    "{\"expression\":\"1\"}",
+                )]
+            })
+            .collect();
+        fixture.send("loop", turns).await.unwrap();
+
+        let messages = fixture.messages().await;
+        let tool_results = messages
+            .iter()
+            .filter(|a| a.kind == ChatMessageKind::ToolResult)
+            .count();
+        assert_eq!(tool_results, MAX_TOOL_ROUNDS);
+        let last = messages.last().unwrap();
+        assert_eq!(last.role, "model");
+        assert!(matches!(last.status, ChatMessageStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn fails_reply_that_does_not_match_schema() {
+        let fixture = Fixture::new().await;
+        fixture
+            .send_with_schema(
+                "give me json",
+                vec![vec![text("{\"answer\": 42}")]],
+                Some(json!({
+                    "type": "object",
+                    "properties": { "answer": { "type": "string" } },
+                    "required": ["answer"],
+                })),
+            )
+            .await
+            .unwrap();
+
+        let messages = fixture.messages().await;
+        assert!(matches!(messages[1].status, ChatMessageStatus::Failed));
+        assert_eq!(messages[1].content, "{\"answer\": 42}");
+        assert!(!messages[1].schema_errors.as_ref().unwrap().0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_partial_reply_when_stream_fails() {
+        let fixture = Fixture::new().await;
+        let result = fixture
+            .send(
+                "hello",
+                vec![vec![
+                    text("Partial"),
+                    Err(AppError::Mcp("connection reset".into())),
+                ]],
+            )
+            .await;
+
+        assert!(result.is_err());
+        let messages = fixture.messages().await;
+        assert!(matches!(messages[1].status, ChatMessageStatus::Failed));
+        assert_eq!(messages[1].content, "Partial");
+
+        let events = fixture.events.events();
+        assert!(events.iter().any(|a| matches!(
+            a,
+            DomainEvent::ResponseFailed { message_id, error, .. }
+                if *message_id == messages[1].id && error.contains("connection reset")
+        )));
+        assert!(matches!(
+            events.last(),
+            Some(DomainEvent::MessageStatusChanged {
+                status: ChatMessageStatus::Failed,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn fails_reply_when_agent_cannot_start() {
+        let fixture = Fixture::new().await;
+        let result = fixture.send("hello", Vec::new()).await;
+
+        assert!(matches!(result, Err(AppError::AgentTextGenParamsRequired)));
+        let messages = fixture.messages().await;
+        assert!(matches!(messages[1].status, ChatMessageStatus::Failed));
+        assert!(fixture
+            .chat_repo
+            .get_pending_chat_messages()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn uses_project_default_agent() {
+        let fixture = Fixture::new().await;
+        let project_agent_id =
+            create_agent(&fixture.agent_repo, "project-model", AgentCapability::Chat).await;
+        let project = fixture
+            .project_repo
+            .create_project(CreateProject {
+                id: Uuid::new_v4(),
+                name: "project".into(),
+                system_prompt: String::new(),
+                default_agent_id: Some(project_agent_id),
+            })
+            .await
+            .unwrap();
+        fixture.database.lock().await.chats[0].project_id = Some(project.id);
+
+        let started = fixture.start("hello").await.unwrap();
+        assert_eq!(started.agent.id, project_agent_id);
+    }
+
+    #[tokio::test]
+    async fn saves_nothing_without_chat_agent() {
+        let fixture = Fixture::new().await;
+        let agent_id =
+            create_agent(&fixture.agent_repo, "speech-model", AgentCapability::Speech).await;
+        fixture
+            .agent_repo
+            .update_current_agent(UpdateCurrentAgent { agent_id })
+            .await
+            .unwrap();
+
+        let result = fixture.start("hello").await;
+        assert!(matches!(
+            result,
+            Err(AppError::AgentCapabilityUnsupported(_))
+        ));
+        assert!(fixture.messages().await.is_empty());
+        assert!(fixture.events.events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn saves_nothing_when_chat_is_missing() {
+        let fixture = Fixture::new().await;
+        fixture
+            .chat_repo
+            .delete_chat(fixture.chat_id)
+            .await
+            .unwrap();
+
+        let result = fixture.start("hello").await;
+        assert!(matches!(result, Err(AppError::ChatNotFound(_))));
+        assert!(fixture.database.lock().await.chat_messages.is_empty());
+    }
 }
@@ -0,0 +1,120 @@
+use std::{
+    io::{Cursor, Read},
+    path::Path,
+};
+
+use quick_xml::{escape::resolve_xml_entity, events::Event, Reader};
+
+use crate::common::error::AppError;
+
+pub const DOCUMENT_CONTEXT_CHARS: usize = 120_000;
+
+const MIME_TYPE_PDF: &str = "application/pdf";
+const MIME_TYPE_DOCX: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+pub fn guess_mime_type(name: &str) -> Option<&'static str> {
+    let extension = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "pdf" => MIME_TYPE_PDF,
+        "docx" => MIME_TYPE_DOCX,
+        "md" | "markdown" => "text/markdown",
+        "csv" => "text/csv",
+        "txt" | "log" => "text/plain",
+        "json" => "application/json",
+        "html" | "htm" => "text/html",
+        "xml" => "application/xml",
+        "rs" | "py" | "js" | "jsx" | "ts" | "tsx" | "svelte" | "vue" | "go" | "java" | "kt"
+        | "swift" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "rb" | "php" | "sh" | "ps1"
+        | "sql" | "css" | "scss" | "toml" | "yaml" | "yml" | "ini" | "lua" | "dart" => "text/plain",
+        _ => return None,
+    })
+}
+
+pub fn extract_text(mime_type: &str, data: &[u8]) -> Result<String, AppError> {
+    match mime_type {
+        MIME_TYPE_PDF => pdf_extract::extract_text_from_mem(data)
+            .map_err(|e| AppError::DocumentExtraction(e.to_string())),
+        MIME_TYPE_DOCX => extract_docx_text(data),
+        _ => String::from_utf8(data.to_vec()).map_err(AppError::from),
+    }
+}
+
+/// Wraps extracted text in delimiters the model can tell apart from the user's own
+/// words. Text beyond `budget` characters is cut off with a note saying so.
+pub fn render_document(name: &str, text: &str, budget: usize) -> (String, usize) {
+    let total = text.chars().count();
+    let (body, included) = if total > budget {
+        let cut = text
+            .char_indices()
+            .nth(budget)
+            .map(|(i, _)| i)
+            .unwrap_or(text.len());
+        (
+            format!(
+                "{}\n[truncated: {} of {} characters included]",
+                &text[..cut],
+                budget,
+                total
+            ),
+            budget,
+        )
+    } else {
+        (text.to_string(), total)
+    };
+    (
+        format!(
+            "<document name=\"{}\">\n{}\n</document>",
+            name,
+            body.trim_end()
+        ),
+        included,
+    )
+}
+
+fn extract_docx_text(data: &[u8]) -> Result<String, AppError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|e| AppError::DocumentExtraction(e.to_string()))?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|e| AppError::DocumentExtraction(e.to_string()))?
+        .read_to_string(&mut xml)
+        .map_err(AppError::from)?;
+
+    let mut reader = Reader::from_str(&xml);
+    let mut text = String::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.name().as_ref() == b"w:t" => in_text = true,
+            Ok(Event::End(e)) => match e.name().as_ref() {
+                b"w:t" => in_text = false,
+                b"w:p" => text.push('\n'),
+                _ => {}
+            },
+            Ok(Event::Empty(e)) => match e.name().as_ref() {
+                b"w:tab" => text.push('\t'),
+                b"w:br" | b"w:cr" => text.push('\n'),
+                _ => {}
+            },
+            Ok(Event::Text(e)) if in_text => text.push_str(
+                &e.decode()
+                    .map_err(|e| AppError::DocumentExtraction(e.to_string()))?,
+            ),
+            Ok(Event::GeneralRef(e)) if in_text => {
+                if let Ok(Some(c)) = e.resolve_char_ref() {
+                    text.push(c);
+                } else if let Some(entity) =
+                    std::str::from_utf8(&e).ok().and_then(resolve_xml_entity)
+                {
+                    text.push_str(entity);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(AppError::DocumentExtraction(e.to_string())),
+            _ => {}
+        }
+    }
+    Ok(text)
+}
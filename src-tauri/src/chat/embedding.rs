@@ -0,0 +1,210 @@
+use std::{sync::Arc, time::Duration};
+
+use serde::Serialize;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::{
+    agent::{Agent, AgentContext, AgentEmbeddingParams, AgentEmbeddingTask, AgentProvider},
+    chat::repo::UpsertChatMessageEmbedding,
+    common::{
+        entity::chat::ChatMessageStatus,
+        error::AppError,
+        vector::{cosine_similarity, decode_embedding, encode_embedding},
+    },
+    event::{DomainEvent, EventSubscriber},
+    knowledge::EMBEDDING_MODEL,
+};
+
+const EMBEDDING_BATCH_SIZE: i64 = 100;
+const EMBEDDING_INPUT_CHARS: usize = 8_000;
+const INDEX_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageSearchResult {
+    pub message_id: Uuid,
+    pub chat_id: Uuid,
+    pub chat_title: String,
+    pub role: String,
+    pub content: String,
+    pub created_at: i64,
+    pub score: f32,
+}
+
+/// Embeds completed chat messages in the background so past conversations can be
+/// searched by meaning. Runs on an interval, whenever `notify` is called and whenever
+/// a reply completes; edited messages are picked up through their content revision.
+pub struct ChatEmbeddingIndexer {
+    agent_context: AgentContext,
+    notify: Notify,
+}
+
+impl ChatEmbeddingIndexer {
+    pub fn new(agent_context: AgentContext) -> Self {
+        Self {
+            agent_context,
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn notify(&self) {
+        self.notify.notify_one();
+    }
+
+    pub fn spawn(self: &Arc<Self>) {
+        let indexer = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                match indexer.index().await {
+                    Ok(0) => {}
+                    Ok(count) => log::info!("embedded {} chat messages", count),
+                    Err(e) => log::error!("failed to embed chat messages: {}", e),
+                }
+                tokio::select! {
+                    _ = indexer.notify.notified() => {}
+                    _ = tokio::time::sleep(INDEX_INTERVAL) => {}
+                }
+            }
+        });
+    }
+
+    async fn index(&self) -> Result<usize, AppError> {
+        let chat_repo = &self.agent_context.chat_repo;
+        chat_repo.delete_stale_chat_message_embeddings().await?;
+        let agent = match self.get_embedding_agent().await? {
+            Some(agent) => agent,
+            None => return Ok(0),
+        };
+
+        let mut count = 0;
+        loop {
+            let candidates = chat_repo
+                .get_chat_message_embedding_candidates(EMBEDDING_MODEL, EMBEDDING_BATCH_SIZE)
+                .await?;
+            if candidates.is_empty() {
+                return Ok(count);
+            }
+            let embeddings = agent
+                .clone()
+                .embed_texts(
+                    self.agent_context.clone(),
+                    AgentEmbeddingParams {
+                        model: EMBEDDING_MODEL.into(),
+                        texts: candidates
+                            .iter()
+                            .map(|a| a.content.chars().take(EMBEDDING_INPUT_CHARS).collect())
+                            .collect(),
+                        task: AgentEmbeddingTask::Document,
+                    },
+                )
+                .await?;
+            // A batch that is short of vectors would be fetched and paid for again forever.
+            if embeddings.len() != candidates.len() {
+                return Err(AppError::EmbeddingCountMismatch(
+                    candidates.len(),
+                    embeddings.len(),
+                ));
+            }
+            for (candidate, embedding) in candidates.into_iter().zip(embeddings) {
+                chat_repo
+                    .upsert_chat_message_embedding(UpsertChatMessageEmbedding {
+                        message_id: candidate.id,
+                        embedding_model: EMBEDDING_MODEL.into(),
+                        content_revision: candidate.content_revision,
+                        embedding: encode_embedding(&embedding),
+                    })
+                    .await?;
+                count += 1;
+            }
+        }
+    }
+
+    /// Ranks embedded messages across all chats by cosine similarity to `query`.
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ChatMessageSearchResult>, AppError> {
+        let agent = self
+            .get_embedding_agent()
+            .await?
+            .ok_or(AppError::AgentConfigRequired)?;
+        let query_embedding = agent
+            .embed_texts(
+                self.agent_context.clone(),
+                AgentEmbeddingParams {
+                    model: EMBEDDING_MODEL.into(),
+                    texts: vec![query.to_string()],
+                    task: AgentEmbeddingTask::Query,
+                },
+            )
+            .await?
+            .pop()
+            .unwrap_or_default();
+
+        let mut results = self
+            .agent_context
+            .chat_repo
+            .get_chat_message_embeddings(EMBEDDING_MODEL)
+            .await?
+            .into_iter()
+            .map(|a| ChatMessageSearchResult {
+                score: cosine_similarity(&query_embedding, &decode_embedding(&a.embedding)),
+                message_id: a.message_id,
+                chat_id: a.chat_id,
+                chat_title: a.chat_title,
+                role: a.role,
+                content: a.content,
+                created_at: a.created_at,
+            })
+            .collect::<Vec<_>>();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    /// Prefers the current agent, falling back to any Google agent with an API key,
+    /// since only Google provides embeddings.
+    async fn get_embedding_agent(&self) -> Result<Option<Agent>, AppError> {
+        let agent_repo = &self.agent_context.agent_repo;
+        let mut agents = agent_repo.get_agents(None).await?;
+        if let Some(current) = agent_repo.get_current_agent().await? {
+            if let Some(i) = agents.iter().position(|a| a.id == current.id) {
+                let agent = agents.remove(i);
+                agents.insert(0, agent);
+            }
+        }
+        for agent in agents {
+            if !matches!(agent.provider, AgentProvider::Google) {
+                continue;
+            }
+            let configured = agent_repo
+                .get_agent_config(agent.id)
+                .await?
+                .is_some_and(|a| a.api_key.is_some());
+            if configured {
+                return Ok(Some(Agent::from(agent)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl EventSubscriber for ChatEmbeddingIndexer {
+    fn handle(&self, event: &DomainEvent) {
+        match event {
+            DomainEvent::MessageStatusChanged {
+                status: ChatMessageStatus::Completed,
+                ..
+            } => self.notify(),
+            DomainEvent::ChatMessageCreated { message }
+            | DomainEvent::ChatMessageUpdated { message }
+                if matches!(message.status, ChatMessageStatus::Completed) =>
+            {
+                self.notify()
+            }
+            _ => {}
+        }
+    }
+}
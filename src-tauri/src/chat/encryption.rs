@@ -0,0 +1,121 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::{
+    chat::repo::{ChatFilter, ChatRepo},
+    cipher::Cipher,
+    common::error::AppError,
+};
+
+const SEALED_PREFIX: &str = "askkit:aes-gcm:";
+
+/// Field-level encryption of chat titles and message content. When enabled, text is
+/// stored as `SEALED_PREFIX` followed by base64 ciphertext; reading accepts both
+/// sealed and plain values, so rows can be migrated in place while the app is in use.
+/// `chat_repo` is the plain sqlite repo, used to migrate the stored values.
+pub struct ChatEncryption {
+    cipher: Arc<dyn Cipher>,
+    chat_repo: Arc<dyn ChatRepo>,
+    enabled: AtomicBool,
+}
+
+impl ChatEncryption {
+    pub async fn load(
+        cipher: Arc<dyn Cipher>,
+        chat_repo: Arc<dyn ChatRepo>,
+    ) -> Result<Self, AppError> {
+        let enabled = chat_repo.get_chat_encryption().await?;
+        Ok(Self {
+            cipher,
+            chat_repo,
+            enabled: AtomicBool::new(enabled),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Saves the setting, after which new writes follow it, then migrates existing
+    /// rows. Returns how many values were rewritten.
+    pub async fn set_enabled(&self, enabled: bool) -> Result<usize, AppError> {
+        self.chat_repo.update_chat_encryption(enabled).await?;
+        self.enabled.store(enabled, Ordering::Relaxed);
+        self.reseal_all().await
+    }
+
+    /// Blank text is left as is so queries that skip empty messages keep working.
+    fn should_seal(&self, text: &str) -> bool {
+        self.is_enabled() && !text.trim().is_empty()
+    }
+
+    pub fn seal(&self, text: String) -> Result<String, AppError> {
+        if !self.should_seal(&text) {
+            return Ok(text);
+        }
+        Ok(format!(
+            "{}{}",
+            SEALED_PREFIX,
+            self.cipher.encrypt_str_base64(&text)?
+        ))
+    }
+
+    /// Text that only looks sealed, or was sealed with a key that is gone, is returned
+    /// unchanged rather than failing the whole read.
+    pub fn open(&self, text: String) -> String {
+        match self.unseal(&text) {
+            Some(plaintext) => plaintext,
+            None => text,
+        }
+    }
+
+    fn unseal(&self, text: &str) -> Option<String> {
+        let ciphertext = text.strip_prefix(SEALED_PREFIX)?;
+        self.cipher
+            .decrypt_base64_str(ciphertext)
+            .inspect_err(|e| log::warn!("failed to decrypt chat content: {}", e))
+            .ok()
+    }
+
+    /// Brings stored titles and message contents in line with the current setting.
+    /// Rows changed concurrently are skipped, as they are written in the right form
+    /// anyway.
+    pub async fn reseal_all(&self) -> Result<usize, AppError> {
+        let chat_repo = &self.chat_repo;
+        let mut count = 0;
+        for chat in chat_repo.get_chats(ChatFilter::default()).await? {
+            if let Some(title) = self.reseal(&chat.title)? {
+                if chat_repo
+                    .reseal_chat_title(chat.id, &chat.title, &title)
+                    .await?
+                {
+                    count += 1;
+                }
+            }
+            for message in chat_repo.get_chat_messages(chat.id).await? {
+                if let Some(content) = self.reseal(&message.content)? {
+                    if chat_repo
+                        .reseal_chat_message_content(message.id, &message.content, &content)
+                        .await?
+                    {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    fn reseal(&self, stored: &str) -> Result<Option<String>, AppError> {
+        let (plaintext, sealed) = match self.unseal(stored) {
+            Some(plaintext) => (plaintext, true),
+            None => (stored.to_string(), false),
+        };
+        if sealed == self.should_seal(&plaintext) {
+            return Ok(None);
+        }
+        self.seal(plaintext).map(Some)
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    chat::repo::ChatRepo,
+    common::{
+        entity::chat::{
+            ChatMessageKind, ChatMessageRow, ChatMessageStatus, ChatRow, ChatToolCall, TagRow,
+        },
+        error::AppError,
+    },
+};
+
+pub const CHAT_EXPORT_VERSION: u32 = 1;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatExport {
+    pub version: u32,
+    pub app_version: String,
+    pub exported_at: i64,
+    pub chats: Vec<ChatExportChat>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatExportChat {
+    pub created_at: i64,
+    pub id: Uuid,
+    pub title: String,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub starred: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub messages: Vec<ChatExportMessage>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatExportMessage {
+    pub created_at: i64,
+    pub id: Uuid,
+    pub role: String,
+    pub content: String,
+    pub status: ChatMessageStatus,
+    #[serde(default)]
+    pub starred: bool,
+    #[serde(default)]
+    pub kind: ChatMessageKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ChatToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+}
+
+impl ChatExport {
+    pub fn new(chats: Vec<ChatExportChat>) -> Result<Self, AppError> {
+        Ok(Self {
+            version: CHAT_EXPORT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").into(),
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(AppError::from)?
+                .as_millis() as i64,
+            chats,
+        })
+    }
+
+    pub async fn load(chat_repo: &dyn ChatRepo, chats: Vec<ChatRow>) -> Result<Self, AppError> {
+        let mut export_chats = Vec::with_capacity(chats.len());
+        for chat in chats {
+            let messages = chat_repo.get_chat_messages(chat.id).await?;
+            let tags = chat_repo.get_chat_tags(chat.id).await?;
+            export_chats.push(ChatExportChat::from_rows(chat, messages, tags));
+        }
+        Self::new(export_chats)
+    }
+
+    pub fn render(&self, format: ChatExportFormat) -> Result<String, AppError> {
+        match format {
+            ChatExportFormat::Markdown => Ok(render_markdown(&self.chats)),
+            ChatExportFormat::Json => serde_json::to_string_pretty(self).map_err(AppError::from),
+            ChatExportFormat::Html => Ok(render_html(&self.chats)),
+        }
+    }
+
+    pub async fn write(&self, format: ChatExportFormat, path: &Path) -> Result<(), AppError> {
+        let content = self.render(format)?;
+        tokio::fs::write(path, content)
+            .await
+            .map_err(AppError::from)
+    }
+}
+
+impl ChatExportChat {
+    pub fn from_rows(chat: ChatRow, messages: Vec<ChatMessageRow>, tags: Vec<TagRow>) -> Self {
+        Self {
+            created_at: chat.created_at,
+            id: chat.id,
+            title: chat.title,
+            pinned: chat.pinned,
+            starred: chat.starred,
+            tags: tags.into_iter().map(|a| a.name).collect(),
+            messages: messages
+                .into_iter()
+                .map(|a| ChatExportMessage {
+                    created_at: a.created_at,
+                    id: a.id,
+                    role: a.role,
+                    content: a.content,
+                    status: a.status,
+                    starred: a.starred,
+                    kind: a.kind,
+                    tool_calls: a.tool_calls.map(|a| a.0),
+                    tool_call_id: a.tool_call_id,
+                    tool_name: a.tool_name,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn role_heading(role: &str) -> &str {
+    match role {
+        "user" => "User",
+        "model" => "Model",
+        "tool" => "Tool",
+        role => role,
+    }
+}
+
+fn message_body(message: &ChatExportMessage) -> String {
+    let mut body = message.content.trim_end().to_string();
+    for call in message.tool_calls.iter().flatten() {
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        body.push_str(&format!("-> {}({})", call.name, call.arguments));
+    }
+    body
+}
+
+fn status_note(status: &ChatMessageStatus) -> Option<&'static str> {
+    match status {
+        ChatMessageStatus::Completed => None,
+        ChatMessageStatus::Pending => Some("pending"),
+        ChatMessageStatus::Failed => Some("failed"),
+    }
+}
+
+fn render_markdown(chats: &[ChatExportChat]) -> String {
+    let mut out = String::new();
+    for (i, chat) in chats.iter().enumerate() {
+        if i > 0 {
+            out.push_str("\n---\n\n");
+        }
+        out.push_str(&format!("# {}\n\n", chat.title));
+        for message in &chat.messages {
+            match status_note(&message.status) {
+                Some(note) => out.push_str(&format!(
+                    "## {} _({})_\n\n",
+                    role_heading(&message.role),
+                    note
+                )),
+                None => out.push_str(&format!("## {}\n\n", role_heading(&message.role))),
+            }
+            out.push_str(&message_body(message));
+            out.push_str("\n\n");
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:48rem;margin:2rem auto;padding:0 1rem;line-height:1.5;color:#1f2328}\
+h1{border-bottom:1px solid #d0d7de;padding-bottom:.3rem}\
+section.message{margin:1rem 0;padding:.75rem 1rem;border-radius:.5rem;background:#f6f8fa}\
+section.message.user{background:#ddf4ff}\
+section.message h2{font-size:.875rem;margin:0 0 .5rem;text-transform:uppercase;color:#656d76}\
+section.message pre{white-space:pre-wrap;word-wrap:break-word;margin:0;font-family:inherit}";
+
+fn render_html(chats: &[ChatExportChat]) -> String {
+    let title = match chats {
+        [chat] => escape_html(&chat.title),
+        _ => "askkit".into(),
+    };
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        title, HTML_STYLE
+    );
+    for chat in chats {
+        out.push_str(&format!(
+            "<article>\n<h1>{}</h1>\n",
+            escape_html(&chat.title)
+        ));
+        for message in &chat.messages {
+            let heading = match status_note(&message.status) {
+                Some(note) => format!("{} ({})", role_heading(&message.role), note),
+                None => role_heading(&message.role).to_string(),
+            };
+            out.push_str(&format!(
+                "<section class=\"message {}\">\n<h2>{}</h2>\n<pre>{}</pre>\n</section>\n",
+                escape_html(&message.role),
+                escape_html(&heading),
+                escape_html(&message_body(message))
+            ));
+        }
+        out.push_str("</article>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    chat::{
+        export::{ChatExportChat, CHAT_EXPORT_VERSION},
+        repo::{ChatRepo, CreateChat, CreateChatMessage, CreateTag, UpdateChat, UpdateChatMessage},
+    },
+    common::{
+        entity::chat::{ChatMessageKind, ChatMessageStatus, ChatToolCall},
+        error::AppError,
+    },
+};
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatImportFormat {
+    Askkit,
+    Chatgpt,
+}
+
+#[derive(Serialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatImportReport {
+    pub imported_chats: u64,
+    pub imported_messages: u64,
+    pub skipped: Vec<ChatImportSkipped>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatImportSkipped {
+    pub index: usize,
+    pub title: Option<String>,
+    pub reason: String,
+}
+
+pub struct ImportedChat {
+    pub id: Uuid,
+    pub title: String,
+    pub created_at: Option<i64>,
+    pub pinned: bool,
+    pub starred: bool,
+    pub tags: Vec<String>,
+    pub messages: Vec<ImportedChatMessage>,
+}
+
+pub struct ImportedChatMessage {
+    pub id: Uuid,
+    pub created_at: Option<i64>,
+    pub role: String,
+    pub content: String,
+    pub status: ChatMessageStatus,
+    pub starred: bool,
+    pub kind: ChatMessageKind,
+    pub tool_calls: Option<Vec<ChatToolCall>>,
+    pub tool_call_id: Option<String>,
+    pub tool_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatgptConversation {
+    id: Option<String>,
+    conversation_id: Option<String>,
+    title: Option<String>,
+    create_time: Option<f64>,
+    current_node: Option<String>,
+    mapping: HashMap<String, ChatgptNode>,
+}
+
+#[derive(Deserialize)]
+struct ChatgptNode {
+    message: Option<ChatgptMessage>,
+    parent: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatgptMessage {
+    id: Option<String>,
+    author: ChatgptAuthor,
+    content: Option<ChatgptContent>,
+    create_time: Option<f64>,
+    status: Option<String>,
+    #[serde(default)]
+    metadata: HashMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct ChatgptAuthor {
+    role: String,
+}
+
+#[derive(Deserialize)]
+struct ChatgptContent {
+    content_type: String,
+    #[serde(default)]
+    parts: Vec<Value>,
+    text: Option<String>,
+}
+
+/// Parses `input` into chats ready to be inserted. Items that cannot be read are
+/// recorded in the returned report instead of failing the whole import.
+pub fn parse(
+    input: &str,
+    format: Option<ChatImportFormat>,
+) -> Result<(Vec<ImportedChat>, ChatImportReport), AppError> {
+    let value = serde_json::from_str::<Value>(input).map_err(AppError::from)?;
+    let format = match format {
+        Some(format) => format,
+        None => detect_format(&value)?,
+    };
+    match format {
+        ChatImportFormat::Askkit => parse_askkit(value),
+        ChatImportFormat::Chatgpt => parse_chatgpt(value),
+    }
+}
+
+/// Inserts the parsed chats through `chat_repo`. Chats that already exist are skipped
+/// so the same file can be imported twice without duplicating history.
+pub async fn import(
+    chat_repo: &dyn ChatRepo,
+    chats: Vec<ImportedChat>,
+    report: &mut ChatImportReport,
+) -> Result<(), AppError> {
+    for (index, chat) in chats.into_iter().enumerate() {
+        if chat_repo.get_chat(chat.id).await?.is_some() {
+            report.skipped.push(ChatImportSkipped {
+                index,
+                title: Some(chat.title),
+                reason: "chat already exists".into(),
+            });
+            continue;
+        }
+        let created = chat_repo
+            .create_chat(CreateChat {
+                id: chat.id,
+                title: chat.title,
+                created_at: chat.created_at,
+                project_id: None,
+            })
+            .await?;
+        if chat.pinned || chat.starred {
+            chat_repo
+                .update_chat(
+                    created.id,
+                    UpdateChat {
+                        pinned: Some(chat.pinned),
+                        starred: Some(chat.starred),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+        }
+        for name in chat.tags {
+            let tag = chat_repo
+                .upsert_tag(CreateTag {
+                    id: Uuid::new_v4(),
+                    name,
+                })
+                .await?;
+            chat_repo.tag_chat(created.id, tag.id).await?;
+        }
+        for message in chat.messages {
+            let starred = message.starred;
+            let created_message = chat_repo
+                .create_chat_message(CreateChatMessage {
+                    created_at: message.created_at.or(Some(created.created_at)),
+                    id: message.id,
+                    chat_id: created.id,
+                    role: message.role,
+                    content: message.content,
+                    status: message.status,
+                    kind: message.kind,
+                    tool_calls: message.tool_calls,
+                    tool_call_id: message.tool_call_id,
+                    tool_name: message.tool_name,
+                })
+                .await?;
+            if starred {
+                chat_repo
+                    .update_chat_message(
+                        created_message.id,
+                        UpdateChatMessage {
+                            starred: Some(true),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+            }
+            report.imported_messages += 1;
+        }
+        report.imported_chats += 1;
+    }
+    Ok(())
+}
+
+fn detect_format(value: &Value) -> Result<ChatImportFormat, AppError> {
+    match value {
+        Value::Object(map) if map.contains_key("version") && map.contains_key("chats") => {
+            Ok(ChatImportFormat::Askkit)
+        }
+        Value::Array(_) => Ok(ChatImportFormat::Chatgpt),
+        _ => Err(AppError::ChatImport("unrecognized import format".into())),
+    }
+}
+
+fn parse_askkit(mut value: Value) -> Result<(Vec<ImportedChat>, ChatImportReport), AppError> {
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| AppError::ChatImport("missing export version".into()))?;
+    if version > CHAT_EXPORT_VERSION as u64 {
+        return Err(AppError::ChatImport(format!(
+            "unsupported export version: {}",
+            version
+        )));
+    }
+    let items = match value.get_mut("chats").map(Value::take) {
+        Some(Value::Array(items)) => items,
+        _ => return Err(AppError::ChatImport("expected an array of chats".into())),
+    };
+    let mut report = ChatImportReport::default();
+    let mut chats = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        let title = item.get("title").and_then(Value::as_str).map(String::from);
+        let chat = match serde_json::from_value::<ChatExportChat>(item) {
+            Ok(chat) => chat,
+            Err(e) => {
+                report.skipped.push(ChatImportSkipped {
+                    index,
+                    title,
+                    reason: format!("malformed chat: {}", e),
+                });
+                continue;
+            }
+        };
+        chats.push(ImportedChat {
+            id: chat.id,
+            title: chat.title,
+            created_at: Some(chat.created_at),
+            pinned: chat.pinned,
+            starred: chat.starred,
+            tags: chat.tags,
+            messages: chat
+                .messages
+                .into_iter()
+                .map(|a| ImportedChatMessage {
+                    id: a.id,
+                    created_at: Some(a.created_at),
+                    role: a.role,
+                    content: a.content,
+                    status: a.status,
+                    starred: a.starred,
+                    kind: a.kind,
+                    tool_calls: a.tool_calls,
+                    tool_call_id: a.tool_call_id,
+                    tool_name: a.tool_name,
+                })
+                .collect(),
+        });
+    }
+    Ok((chats, report))
+}
+
+fn parse_chatgpt(value: Value) -> Result<(Vec<ImportedChat>, ChatImportReport), AppError> {
+    let items = match value {
+        Value::Array(items) => items,
+        _ => {
+            return Err(AppError::ChatImport(
+                "expected an array of conversations".into(),
+            ))
+        }
+    };
+    let mut report = ChatImportReport::default();
+    let mut chats = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        let title = item.get("title").and_then(Value::as_str).map(String::from);
+        let conversation = match serde_json::from_value::<ChatgptConversation>(item) {
+            Ok(conversation) => conversation,
+            Err(e) => {
+                report.skipped.push(ChatImportSkipped {
+                    index,
+                    title,
+                    reason: format!("malformed conversation: {}", e),
+                });
+                continue;
+            }
+        };
+        match flatten_chatgpt_conversation(conversation) {
+            Ok(chat) if chat.messages.is_empty() => report.skipped.push(ChatImportSkipped {
+                index,
+                title: Some(chat.title),
+                reason: "conversation has no importable messages".into(),
+            }),
+            Ok(chat) => chats.push(chat),
+            Err(reason) => report.skipped.push(ChatImportSkipped {
+                index,
+                title,
+                reason,
+            }),
+        }
+    }
+    Ok((chats, report))
+}
+
+/// Walks the `mapping` tree from `current_node` back to the root, which yields the
+/// branch that was last visible in ChatGPT, and keeps the user and assistant turns.
+fn flatten_chatgpt_conversation(conversation: ChatgptConversation) -> Result<ImportedChat, String> {
+    let ChatgptConversation {
+        id,
+        conversation_id,
+        title,
+        create_time,
+        current_node,
+        mut mapping,
+    } = conversation;
+    let mut node_id = current_node.ok_or_else(|| "missing current_node".to_string())?;
+    let mut branch = Vec::new();
+    loop {
+        let node = mapping
+            .remove(&node_id)
+            .ok_or_else(|| format!("mapping is missing node {}", node_id))?;
+        if let Some(message) = node.message {
+            branch.push(message);
+        }
+        match node.parent {
+            Some(parent) => node_id = parent,
+            None => break,
+        }
+    }
+    branch.reverse();
+
+    let created_at = create_time.map(seconds_to_millis);
+    let messages = branch
+        .into_iter()
+        .filter_map(|message| {
+            let role = match message.author.role.as_str() {
+                "user" => "user",
+                "assistant" => "model",
+                _ => return None,
+            };
+            let hidden = message
+                .metadata
+                .get("is_visually_hidden_from_conversation")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            if hidden {
+                return None;
+            }
+            let content = chatgpt_content_text(message.content?)?;
+            Some(ImportedChatMessage {
+                id: parse_uuid(message.id.as_deref()),
+                created_at: message.create_time.map(seconds_to_millis).or(created_at),
+                role: role.into(),
+                content,
+                status: match message.status.as_deref() {
+                    Some("in_progress") => ChatMessageStatus::Failed,
+                    _ => ChatMessageStatus::Completed,
+                },
+                starred: false,
+                kind: ChatMessageKind::Text,
+                tool_calls: None,
+                tool_call_id: None,
+                tool_name: None,
+            })
+        })
+        .collect();
+
+    Ok(ImportedChat {
+        id: parse_uuid(id.as_deref().or(conversation_id.as_deref())),
+        title: title
+            .filter(|a| !a.trim().is_empty())
+            .unwrap_or_else(|| "Untitled".into()),
+        created_at,
+        pinned: false,
+        starred: false,
+        tags: Vec::new(),
+        messages,
+    })
+}
+
+fn chatgpt_content_text(content: ChatgptContent) -> Option<String> {
+    let text = match content.content_type.as_str() {
+        "text" | "multimodal_text" => content
+            .parts
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "code" => content.text?,
+        _ => return None,
+    };
+    if text.trim().is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+fn parse_uuid(id: Option<&str>) -> Uuid {
+    id.and_then(|a| Uuid::parse_str(a).ok())
+        .unwrap_or_else(Uuid::new_v4)
+}
+
+fn seconds_to_millis(seconds: f64) -> i64 {
+    (seconds * 1000.0) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn skips_malformed_askkit_chats() {
+        let input = json!({
+            "version": CHAT_EXPORT_VERSION,
+            "appVersion": "0.0.0",
+            "exportedAt": 0,
+            "chats": [
+                {
+                    "createdAt": 1,
+                    "id": Uuid::new_v4(),
+                    "title": "kept",
+                    "messages": [],
+                },
+                {
+                    "createdAt": 2,
+                    "id": "not a uuid",
+                    "title": "broken",
+                    "messages": [],
+                },
+            ],
+        });
+
+        let (chats, report) = parse(&input.to_string(), None).unwrap();
+
+        assert_eq!(chats.len(), 1);
+        assert_eq!(chats[0].title, "kept");
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].index, 1);
+        assert_eq!(report.skipped[0].title.as_deref(), Some("broken"));
+    }
+}
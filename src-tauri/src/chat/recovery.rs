@@ -0,0 +1,189 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use uuid::Uuid;
+
+use crate::{
+    chat::repo::ChatRepo,
+    common::{entity::chat::ChatMessageStatus, error::AppError},
+    event::{DomainEvent, EventBus},
+};
+
+/// The model messages a response task is currently streaming into. Anything else left
+/// pending belongs to a task that died with a previous run of the app.
+#[derive(Default)]
+pub struct ChatTasks {
+    message_ids: Mutex<HashSet<Uuid>>,
+}
+
+impl ChatTasks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn track(self: &Arc<Self>, message_id: Uuid) -> ChatTaskGuard {
+        self.message_ids.lock().unwrap().insert(message_id);
+        ChatTaskGuard {
+            chat_tasks: self.clone(),
+            message_id,
+        }
+    }
+
+    fn is_live(&self, message_id: Uuid) -> bool {
+        self.message_ids.lock().unwrap().contains(&message_id)
+    }
+
+    /// Whether any response is still streaming or waiting on a tool approval.
+    pub fn has_live(&self) -> bool {
+        !self.message_ids.lock().unwrap().is_empty()
+    }
+}
+
+/// Stops tracking the message when dropped, however the task ends.
+pub struct ChatTaskGuard {
+    chat_tasks: Arc<ChatTasks>,
+    message_id: Uuid,
+}
+
+impl Drop for ChatTaskGuard {
+    fn drop(&mut self) {
+        self.chat_tasks
+            .message_ids
+            .lock()
+            .unwrap()
+            .remove(&self.message_id);
+    }
+}
+
+/// Marks pending messages without a live task as failed, keeping whatever content was
+/// streamed before the app went away.
+pub async fn recover_pending_chat_messages(
+    event_bus: &EventBus,
+    chat_repo: &Arc<dyn ChatRepo>,
+    chat_tasks: &ChatTasks,
+) -> Result<usize, AppError> {
+    let mut count = 0;
+    for message in chat_repo.get_pending_chat_messages().await? {
+        if chat_tasks.is_live(message.id) {
+            continue;
+        }
+        // The task may have finished the reply since the pending messages were read.
+        if chat_repo.fail_pending_chat_message(message.id).await? == 0 {
+            continue;
+        }
+        count += 1;
+        event_bus.publish(DomainEvent::MessageStatusChanged {
+            chat_id: message.chat_id,
+            message_id: message.id,
+            status: ChatMessageStatus::Failed,
+        });
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chat::repo::{memory::MemoryChatRepo, CreateChat, CreateChatMessage},
+        common::entity::chat::ChatMessageKind,
+        database::memory::create_database,
+        event::memory::RecordedEvents,
+    };
+
+    async fn create_message(
+        chat_repo: &Arc<dyn ChatRepo>,
+        chat_id: Uuid,
+        status: ChatMessageStatus,
+    ) -> Uuid {
+        chat_repo
+            .create_chat_message(CreateChatMessage {
+                created_at: None,
+                id: Uuid::new_v4(),
+                chat_id,
+                role: "model".into(),
+                content: String::new(),
+                status,
+                kind: ChatMessageKind::Text,
+                tool_calls: None,
+                tool_call_id: None,
+                tool_name: None,
+            })
+            .await
+            .unwrap()
+            .id
+    }
+
+    #[tokio::test]
+    async fn fails_only_abandoned_pending_messages() {
+        let chat_repo: Arc<dyn ChatRepo> = Arc::new(MemoryChatRepo::new(create_database()));
+        let chat_id = Uuid::new_v4();
+        chat_repo
+            .create_chat(CreateChat {
+                id: chat_id,
+                title: "test".into(),
+                created_at: None,
+                project_id: None,
+            })
+            .await
+            .unwrap();
+        let abandoned = create_message(&chat_repo, chat_id, ChatMessageStatus::Pending).await;
+        let live = create_message(&chat_repo, chat_id, ChatMessageStatus::Pending).await;
+        let completed = create_message(&chat_repo, chat_id, ChatMessageStatus::Completed).await;
+        let chat_tasks = Arc::new(ChatTasks::new());
+        let _guard = chat_tasks.track(live);
+        let event_bus = EventBus::new();
+        let events = Arc::new(RecordedEvents::default());
+        event_bus.subscribe(events.clone());
+
+        let count = recover_pending_chat_messages(&event_bus, &chat_repo, &chat_tasks)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 1);
+        let status = |id| {
+            let chat_repo = chat_repo.clone();
+            async move {
+                chat_repo
+                    .get_chat_message(id)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .status
+            }
+        };
+        assert!(matches!(status(abandoned).await, ChatMessageStatus::Failed));
+        assert!(matches!(status(live).await, ChatMessageStatus::Pending));
+        assert!(matches!(
+            status(completed).await,
+            ChatMessageStatus::Completed
+        ));
+        assert_eq!(
+            chat_repo
+                .fail_pending_chat_message(completed)
+                .await
+                .unwrap(),
+            0
+        );
+        let events = events.events();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            DomainEvent::MessageStatusChanged { message_id, .. } if message_id == abandoned
+        ));
+    }
+
+    #[test]
+    fn live_until_every_guard_is_dropped() {
+        let chat_tasks = Arc::new(ChatTasks::new());
+        assert!(!chat_tasks.has_live());
+        let first = chat_tasks.track(Uuid::new_v4());
+        let second = chat_tasks.track(Uuid::new_v4());
+        drop(first);
+        assert!(chat_tasks.has_live());
+        drop(second);
+        assert!(!chat_tasks.has_live());
+    }
+}
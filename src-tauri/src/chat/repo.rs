@@ -2,18 +2,37 @@ use async_trait::async_trait;
 use uuid::Uuid;
 
 use crate::common::{
-    entity::chat::{ChatMessageRow, ChatMessageStatus, ChatRow},
+    entity::chat::{
+        ChatCitation, ChatMessageAttachmentKind, ChatMessageAttachmentRow,
+        ChatMessageEmbeddingCandidateRow, ChatMessageEmbeddingRow, ChatMessageKind, ChatMessageRow,
+        ChatMessageStatus, ChatRow, ChatSchemaViolation, ChatToolCall, TagRow,
+    },
     error::AppError,
 };
 
+pub mod encrypted;
+#[cfg(test)]
+pub mod memory;
 pub mod sqlite;
 
+pub struct CreateChat {
+    pub id: Uuid,
+    pub title: String,
+    pub created_at: Option<i64>,
+    pub project_id: Option<Uuid>,
+}
+
 pub struct CreateChatMessage {
+    pub created_at: Option<i64>,
     pub id: Uuid,
     pub chat_id: Uuid,
     pub role: String,
     pub content: String,
     pub status: ChatMessageStatus,
+    pub kind: ChatMessageKind,
+    pub tool_calls: Option<Vec<ChatToolCall>>,
+    pub tool_call_id: Option<String>,
+    pub tool_name: Option<String>,
 }
 
 #[derive(Default)]
@@ -21,11 +40,66 @@ pub struct UpdateChatMessage {
     pub role: Option<String>,
     pub content: Option<String>,
     pub status: Option<ChatMessageStatus>,
+    pub starred: Option<bool>,
+    pub kind: Option<ChatMessageKind>,
+    pub tool_calls: Option<Vec<ChatToolCall>>,
+    pub schema_errors: Option<Vec<ChatSchemaViolation>>,
+    pub citations: Option<Vec<ChatCitation>>,
+}
+
+#[derive(Default)]
+pub struct UpdateChat {
+    pub title: Option<String>,
+    pub pinned: Option<bool>,
+    pub starred: Option<bool>,
+    pub archived: Option<bool>,
+}
+
+#[derive(Default)]
+pub struct ChatFilter {
+    pub pinned: Option<bool>,
+    pub starred: Option<bool>,
+    pub archived: Option<bool>,
+    pub project_id: Option<Uuid>,
+    pub tag_ids: Vec<Uuid>,
+}
+
+pub struct CreateChatMessageAttachment {
+    pub id: Uuid,
+    pub message_id: Uuid,
+    pub kind: ChatMessageAttachmentKind,
+    pub name: Option<String>,
+    pub mime_type: String,
+    pub data: Option<Vec<u8>>,
+    pub path: Option<String>,
+    pub text: Option<String>,
+}
+
+pub struct CreateTag {
+    pub id: Uuid,
+    pub name: String,
+}
+
+pub struct CreateChatMessageSpeech {
+    pub attachment_id: Uuid,
+    pub message_id: Uuid,
+    pub agent_id: Uuid,
+    pub voice: String,
+    pub content_revision: i64,
+}
+
+pub struct UpsertChatMessageEmbedding {
+    pub message_id: Uuid,
+    pub embedding_model: String,
+    pub content_revision: i64,
+    pub embedding: Vec<u8>,
 }
 
 #[async_trait]
 pub trait ChatRepo: Send + Sync {
     async fn get_chat_messages(&self, chat_id: Uuid) -> Result<Vec<ChatMessageRow>, AppError>;
+    async fn get_chat_message(&self, id: Uuid) -> Result<Option<ChatMessageRow>, AppError>;
+    async fn get_pending_chat_messages(&self) -> Result<Vec<ChatMessageRow>, AppError>;
     async fn create_chat_message(
         &self,
         message: CreateChatMessage,
@@ -35,5 +109,81 @@ pub trait ChatRepo: Send + Sync {
         id: Uuid,
         update: UpdateChatMessage,
     ) -> Result<(), AppError>;
+    async fn delete_chat_message(&self, id: Uuid) -> Result<u64, AppError>;
+    /// Marks the message as failed only if it is still pending, so a reply that
+    /// completes in the meantime is left alone. Returns the number of rows changed.
+    async fn fail_pending_chat_message(&self, id: Uuid) -> Result<u64, AppError>;
+    async fn create_chat_message_attachment(
+        &self,
+        create: CreateChatMessageAttachment,
+    ) -> Result<ChatMessageAttachmentRow, AppError>;
+    async fn get_chat_attachments(
+        &self,
+        chat_id: Uuid,
+    ) -> Result<Vec<ChatMessageAttachmentRow>, AppError>;
+    async fn create_chat(&self, create: CreateChat) -> Result<ChatRow, AppError>;
     async fn get_chat(&self, id: Uuid) -> Result<Option<ChatRow>, AppError>;
+    async fn update_chat(&self, id: Uuid, update: UpdateChat) -> Result<u64, AppError>;
+    async fn get_chats(&self, filter: ChatFilter) -> Result<Vec<ChatRow>, AppError>;
+    async fn delete_chat(&self, id: Uuid) -> Result<u64, AppError>;
+    async fn get_tags(&self) -> Result<Vec<TagRow>, AppError>;
+    async fn upsert_tag(&self, create: CreateTag) -> Result<TagRow, AppError>;
+    async fn delete_tag(&self, id: Uuid) -> Result<u64, AppError>;
+    async fn get_chat_tags(&self, chat_id: Uuid) -> Result<Vec<TagRow>, AppError>;
+    async fn tag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<(), AppError>;
+    async fn untag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<u64, AppError>;
+    async fn get_chat_tool_approvals(&self, chat_id: Uuid) -> Result<Vec<String>, AppError>;
+    async fn create_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<(), AppError>;
+    async fn delete_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<u64, AppError>;
+    async fn get_chat_message_embedding_candidates(
+        &self,
+        embedding_model: &str,
+        limit: i64,
+    ) -> Result<Vec<ChatMessageEmbeddingCandidateRow>, AppError>;
+    async fn upsert_chat_message_embedding(
+        &self,
+        upsert: UpsertChatMessageEmbedding,
+    ) -> Result<(), AppError>;
+    async fn delete_stale_chat_message_embeddings(&self) -> Result<u64, AppError>;
+    async fn get_chat_message_embeddings(
+        &self,
+        embedding_model: &str,
+    ) -> Result<Vec<ChatMessageEmbeddingRow>, AppError>;
+    async fn get_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<Option<ChatMessageAttachmentRow>, AppError>;
+    async fn create_chat_message_speech(
+        &self,
+        create: CreateChatMessageSpeech,
+    ) -> Result<(), AppError>;
+    async fn delete_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<u64, AppError>;
+    async fn get_chat_encryption(&self) -> Result<bool, AppError>;
+    async fn update_chat_encryption(&self, enabled: bool) -> Result<(), AppError>;
+    /// Replaces a stored title with the same title sealed or opened, if it is still
+    /// `from`. Returns whether it was replaced.
+    async fn reseal_chat_title(&self, id: Uuid, from: &str, to: &str) -> Result<bool, AppError>;
+    /// Like `reseal_chat_title`, and keeps the content revision so embeddings and
+    /// synthesized speech stay valid.
+    async fn reseal_chat_message_content(
+        &self,
+        id: Uuid,
+        from: &str,
+        to: &str,
+    ) -> Result<bool, AppError>;
 }
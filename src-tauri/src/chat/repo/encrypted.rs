@@ -0,0 +1,318 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    chat::{
+        encryption::ChatEncryption,
+        repo::{
+            ChatFilter, ChatRepo, CreateChat, CreateChatMessage, CreateChatMessageAttachment,
+            CreateChatMessageSpeech, CreateTag, UpdateChat, UpdateChatMessage,
+            UpsertChatMessageEmbedding,
+        },
+        search::{ChatSearchIndexChange, ChatSearchIndexWriter},
+    },
+    common::{
+        entity::chat::{
+            ChatMessageAttachmentRow, ChatMessageEmbeddingCandidateRow, ChatMessageEmbeddingRow,
+            ChatMessageKind, ChatMessageRow, ChatRow, TagRow,
+        },
+        error::AppError,
+    },
+};
+
+/// Seals chat titles and message content on the way into `inner` and opens them on
+/// the way out, feeding the plaintext to the search index as it is written.
+pub struct EncryptedChatRepo {
+    inner: Box<dyn ChatRepo>,
+    encryption: Arc<ChatEncryption>,
+    search_index: Arc<dyn ChatSearchIndexWriter>,
+}
+
+impl EncryptedChatRepo {
+    pub fn new(
+        inner: Box<dyn ChatRepo>,
+        encryption: Arc<ChatEncryption>,
+        search_index: Arc<dyn ChatSearchIndexWriter>,
+    ) -> Self {
+        Self {
+            inner,
+            encryption,
+            search_index,
+        }
+    }
+
+    fn open_chat(&self, mut chat: ChatRow) -> ChatRow {
+        chat.title = self.encryption.open(chat.title);
+        chat
+    }
+
+    fn open_message(&self, mut message: ChatMessageRow) -> ChatMessageRow {
+        message.content = self.encryption.open(message.content);
+        message
+    }
+}
+
+#[async_trait]
+impl ChatRepo for EncryptedChatRepo {
+    async fn get_chat_messages(&self, chat_id: Uuid) -> Result<Vec<ChatMessageRow>, AppError> {
+        let messages = self.inner.get_chat_messages(chat_id).await?;
+        Ok(messages.into_iter().map(|a| self.open_message(a)).collect())
+    }
+
+    async fn get_chat_message(&self, id: Uuid) -> Result<Option<ChatMessageRow>, AppError> {
+        let message = self.inner.get_chat_message(id).await?;
+        Ok(message.map(|a| self.open_message(a)))
+    }
+
+    async fn get_pending_chat_messages(&self) -> Result<Vec<ChatMessageRow>, AppError> {
+        let messages = self.inner.get_pending_chat_messages().await?;
+        Ok(messages.into_iter().map(|a| self.open_message(a)).collect())
+    }
+
+    async fn create_chat_message(
+        &self,
+        mut message: CreateChatMessage,
+    ) -> Result<ChatMessageRow, AppError> {
+        let content = message.content;
+        message.content = self.encryption.seal(content.clone())?;
+        let mut created = self.inner.create_chat_message(message).await?;
+        created.content = content;
+        self.search_index
+            .write(ChatSearchIndexChange::PutMessage(created.clone()));
+        Ok(created)
+    }
+
+    async fn update_chat_message(
+        &self,
+        id: Uuid,
+        mut update: UpdateChatMessage,
+    ) -> Result<(), AppError> {
+        let content = update.content.take();
+        if let Some(content) = &content {
+            update.content = Some(self.encryption.seal(content.clone())?);
+        }
+        let kind = update.kind;
+        self.inner.update_chat_message(id, update).await?;
+        if kind.is_some_and(|a| a != ChatMessageKind::Text) {
+            self.search_index
+                .write(ChatSearchIndexChange::RemoveMessage(id));
+        } else if let Some(content) = content {
+            self.search_index
+                .write(ChatSearchIndexChange::PutMessageContent(id, content));
+        }
+        Ok(())
+    }
+
+    async fn delete_chat_message(&self, id: Uuid) -> Result<u64, AppError> {
+        let deleted = self.inner.delete_chat_message(id).await?;
+        self.search_index
+            .write(ChatSearchIndexChange::RemoveMessage(id));
+        Ok(deleted)
+    }
+
+    async fn fail_pending_chat_message(&self, id: Uuid) -> Result<u64, AppError> {
+        self.inner.fail_pending_chat_message(id).await
+    }
+
+    async fn create_chat_message_attachment(
+        &self,
+        create: CreateChatMessageAttachment,
+    ) -> Result<ChatMessageAttachmentRow, AppError> {
+        self.inner.create_chat_message_attachment(create).await
+    }
+
+    async fn get_chat_attachments(
+        &self,
+        chat_id: Uuid,
+    ) -> Result<Vec<ChatMessageAttachmentRow>, AppError> {
+        self.inner.get_chat_attachments(chat_id).await
+    }
+
+    async fn create_chat(&self, mut create: CreateChat) -> Result<ChatRow, AppError> {
+        let title = create.title;
+        create.title = self.encryption.seal(title.clone())?;
+        let mut created = self.inner.create_chat(create).await?;
+        created.title = title;
+        self.search_index.write(ChatSearchIndexChange::PutChatTitle(
+            created.id,
+            created.title.clone(),
+        ));
+        Ok(created)
+    }
+
+    async fn get_chat(&self, id: Uuid) -> Result<Option<ChatRow>, AppError> {
+        let chat = self.inner.get_chat(id).await?;
+        Ok(chat.map(|a| self.open_chat(a)))
+    }
+
+    async fn update_chat(&self, id: Uuid, mut update: UpdateChat) -> Result<u64, AppError> {
+        let title = update.title.take();
+        if let Some(title) = &title {
+            update.title = Some(self.encryption.seal(title.clone())?);
+        }
+        let updated = self.inner.update_chat(id, update).await?;
+        if let Some(title) = title {
+            self.search_index
+                .write(ChatSearchIndexChange::PutChatTitle(id, title));
+        }
+        Ok(updated)
+    }
+
+    async fn get_chats(&self, filter: ChatFilter) -> Result<Vec<ChatRow>, AppError> {
+        let chats = self.inner.get_chats(filter).await?;
+        Ok(chats.into_iter().map(|a| self.open_chat(a)).collect())
+    }
+
+    async fn delete_chat(&self, id: Uuid) -> Result<u64, AppError> {
+        let deleted = self.inner.delete_chat(id).await?;
+        self.search_index
+            .write(ChatSearchIndexChange::RemoveChat(id));
+        Ok(deleted)
+    }
+
+    async fn get_tags(&self) -> Result<Vec<TagRow>, AppError> {
+        self.inner.get_tags().await
+    }
+
+    async fn upsert_tag(&self, create: CreateTag) -> Result<TagRow, AppError> {
+        self.inner.upsert_tag(create).await
+    }
+
+    async fn delete_tag(&self, id: Uuid) -> Result<u64, AppError> {
+        self.inner.delete_tag(id).await
+    }
+
+    async fn get_chat_tags(&self, chat_id: Uuid) -> Result<Vec<TagRow>, AppError> {
+        self.inner.get_chat_tags(chat_id).await
+    }
+
+    async fn tag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<(), AppError> {
+        self.inner.tag_chat(chat_id, tag_id).await
+    }
+
+    async fn untag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<u64, AppError> {
+        self.inner.untag_chat(chat_id, tag_id).await
+    }
+
+    async fn get_chat_tool_approvals(&self, chat_id: Uuid) -> Result<Vec<String>, AppError> {
+        self.inner.get_chat_tool_approvals(chat_id).await
+    }
+
+    async fn create_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<(), AppError> {
+        self.inner
+            .create_chat_tool_approval(chat_id, tool_name)
+            .await
+    }
+
+    async fn delete_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<u64, AppError> {
+        self.inner
+            .delete_chat_tool_approval(chat_id, tool_name)
+            .await
+    }
+
+    async fn get_chat_message_embedding_candidates(
+        &self,
+        embedding_model: &str,
+        limit: i64,
+    ) -> Result<Vec<ChatMessageEmbeddingCandidateRow>, AppError> {
+        let candidates = self
+            .inner
+            .get_chat_message_embedding_candidates(embedding_model, limit)
+            .await?;
+        Ok(candidates
+            .into_iter()
+            .map(|mut a| {
+                a.content = self.encryption.open(a.content);
+                a
+            })
+            .collect())
+    }
+
+    async fn upsert_chat_message_embedding(
+        &self,
+        upsert: UpsertChatMessageEmbedding,
+    ) -> Result<(), AppError> {
+        self.inner.upsert_chat_message_embedding(upsert).await
+    }
+
+    async fn delete_stale_chat_message_embeddings(&self) -> Result<u64, AppError> {
+        self.inner.delete_stale_chat_message_embeddings().await
+    }
+
+    async fn get_chat_message_embeddings(
+        &self,
+        embedding_model: &str,
+    ) -> Result<Vec<ChatMessageEmbeddingRow>, AppError> {
+        let embeddings = self
+            .inner
+            .get_chat_message_embeddings(embedding_model)
+            .await?;
+        Ok(embeddings
+            .into_iter()
+            .map(|mut a| {
+                a.chat_title = self.encryption.open(a.chat_title);
+                a.content = self.encryption.open(a.content);
+                a
+            })
+            .collect())
+    }
+
+    async fn get_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<Option<ChatMessageAttachmentRow>, AppError> {
+        self.inner
+            .get_chat_message_speech(message_id, agent_id, voice)
+            .await
+    }
+
+    async fn create_chat_message_speech(
+        &self,
+        create: CreateChatMessageSpeech,
+    ) -> Result<(), AppError> {
+        self.inner.create_chat_message_speech(create).await
+    }
+
+    async fn delete_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<u64, AppError> {
+        self.inner
+            .delete_chat_message_speech(message_id, agent_id, voice)
+            .await
+    }
+
+    async fn get_chat_encryption(&self) -> Result<bool, AppError> {
+        self.inner.get_chat_encryption().await
+    }
+
+    async fn update_chat_encryption(&self, enabled: bool) -> Result<(), AppError> {
+        self.inner.update_chat_encryption(enabled).await
+    }
+
+    async fn reseal_chat_title(&self, id: Uuid, from: &str, to: &str) -> Result<bool, AppError> {
+        self.inner.reseal_chat_title(id, from, to).await
+    }
+
+    async fn reseal_chat_message_content(
+        &self,
+        id: Uuid,
+        from: &str,
+        to: &str,
+    ) -> Result<bool, AppError> {
+        self.inner.reseal_chat_message_content(id, from, to).await
+    }
+}
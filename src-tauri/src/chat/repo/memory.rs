@@ -0,0 +1,597 @@
+use async_trait::async_trait;
+use sqlx::types::Json;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    chat::repo::{
+        ChatFilter, ChatRepo, CreateChat, CreateChatMessage, CreateChatMessageAttachment,
+        CreateChatMessageSpeech, CreateTag, UpdateChat, UpdateChatMessage,
+        UpsertChatMessageEmbedding,
+    },
+    common::{
+        entity::chat::{
+            ChatMessageAttachmentRow, ChatMessageEmbeddingCandidateRow, ChatMessageEmbeddingRow,
+            ChatMessageKind, ChatMessageRow, ChatMessageStatus, ChatRow, TagRow,
+        },
+        error::AppError,
+    },
+    database::memory::{
+        now_millis, ChatMessageEmbeddingRecord, ChatMessageSpeechRecord, MemoryTables,
+    },
+};
+
+/// Serves both the shared tables and a unit of work's copy of them.
+pub struct MemoryChatRepo {
+    tables: Arc<Mutex<MemoryTables>>,
+}
+
+impl MemoryChatRepo {
+    pub fn new(tables: Arc<Mutex<MemoryTables>>) -> Self {
+        Self { tables }
+    }
+}
+
+#[async_trait]
+impl ChatRepo for MemoryChatRepo {
+    async fn get_chat_messages(&self, chat_id: Uuid) -> Result<Vec<ChatMessageRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut messages = tables
+            .chat_messages
+            .iter()
+            .filter(|a| a.chat_id == chat_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|a| a.created_at);
+        Ok(messages)
+    }
+
+    async fn get_chat_message(&self, id: Uuid) -> Result<Option<ChatMessageRow>, AppError> {
+        let tables = self.tables.lock().await;
+        Ok(tables.chat_messages.iter().find(|a| a.id == id).cloned())
+    }
+
+    async fn get_pending_chat_messages(&self) -> Result<Vec<ChatMessageRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut messages = tables
+            .chat_messages
+            .iter()
+            .filter(|a| matches!(a.status, ChatMessageStatus::Pending))
+            .cloned()
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|a| a.created_at);
+        Ok(messages)
+    }
+
+    async fn create_chat_message(
+        &self,
+        message: CreateChatMessage,
+    ) -> Result<ChatMessageRow, AppError> {
+        let mut tables = self.tables.lock().await;
+        if !tables.chats.iter().any(|a| a.id == message.chat_id) {
+            return Err(AppError::ChatNotFound(message.chat_id));
+        }
+        let created_at = message.created_at.unwrap_or_else(now_millis);
+        let row = ChatMessageRow {
+            created_at,
+            updated_at: created_at,
+            id: message.id,
+            chat_id: message.chat_id,
+            role: message.role,
+            content: message.content,
+            status: message.status,
+            starred: false,
+            kind: message.kind,
+            tool_calls: message.tool_calls.map(Json),
+            tool_call_id: message.tool_call_id,
+            tool_name: message.tool_name,
+            schema_errors: None,
+            citations: None,
+            content_revision: 0,
+        };
+        tables.chat_messages.push(row.clone());
+        Ok(row)
+    }
+
+    async fn update_chat_message(
+        &self,
+        id: Uuid,
+        update: UpdateChatMessage,
+    ) -> Result<(), AppError> {
+        let mut tables = self.tables.lock().await;
+        let Some(message) = tables.chat_messages.iter_mut().find(|a| a.id == id) else {
+            return Ok(());
+        };
+        message.updated_at = now_millis();
+        if let Some(role) = update.role {
+            message.role = role;
+        }
+        if let Some(content) = update.content {
+            if content != message.content {
+                message.content_revision += 1;
+            }
+            message.content = content;
+        }
+        if let Some(status) = update.status {
+            message.status = status;
+        }
+        if let Some(starred) = update.starred {
+            message.starred = starred;
+        }
+        if let Some(kind) = update.kind {
+            message.kind = kind;
+        }
+        if let Some(tool_calls) = update.tool_calls {
+            message.tool_calls = Some(Json(tool_calls));
+        }
+        if let Some(schema_errors) = update.schema_errors {
+            message.schema_errors = Some(Json(schema_errors));
+        }
+        if let Some(citations) = update.citations {
+            message.citations = Some(Json(citations));
+        }
+        Ok(())
+    }
+
+    async fn delete_chat_message(&self, id: Uuid) -> Result<u64, AppError> {
+        Ok(self.tables.lock().await.delete_chat_message(id))
+    }
+
+    async fn fail_pending_chat_message(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        match tables
+            .chat_messages
+            .iter_mut()
+            .find(|a| a.id == id && matches!(a.status, ChatMessageStatus::Pending))
+        {
+            Some(message) => {
+                message.status = ChatMessageStatus::Failed;
+                message.updated_at = now_millis();
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    async fn create_chat_message_attachment(
+        &self,
+        create: CreateChatMessageAttachment,
+    ) -> Result<ChatMessageAttachmentRow, AppError> {
+        let mut tables = self.tables.lock().await;
+        if !tables
+            .chat_messages
+            .iter()
+            .any(|a| a.id == create.message_id)
+        {
+            return Err(AppError::ChatMessageNotFound(create.message_id));
+        }
+        let row = ChatMessageAttachmentRow {
+            created_at: now_millis(),
+            id: create.id,
+            message_id: create.message_id,
+            kind: create.kind,
+            name: create.name,
+            mime_type: create.mime_type,
+            data: create.data,
+            path: create.path,
+            text: create.text,
+        };
+        tables.chat_message_attachments.push(row.clone());
+        Ok(row)
+    }
+
+    async fn get_chat_attachments(
+        &self,
+        chat_id: Uuid,
+    ) -> Result<Vec<ChatMessageAttachmentRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut attachments = tables
+            .chat_message_attachments
+            .iter()
+            .filter(|a| {
+                tables
+                    .chat_messages
+                    .iter()
+                    .any(|b| b.id == a.message_id && b.chat_id == chat_id)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        attachments.sort_by_key(|a| a.created_at);
+        Ok(attachments)
+    }
+
+    async fn create_chat(&self, create: CreateChat) -> Result<ChatRow, AppError> {
+        let mut tables = self.tables.lock().await;
+        let created_at = create.created_at.unwrap_or_else(now_millis);
+        let row = ChatRow {
+            created_at,
+            updated_at: created_at,
+            id: create.id,
+            title: create.title,
+            pinned: false,
+            starred: false,
+            project_id: create.project_id,
+            archived_at: None,
+        };
+        tables.chats.push(row.clone());
+        Ok(row)
+    }
+
+    async fn get_chat(&self, id: Uuid) -> Result<Option<ChatRow>, AppError> {
+        let tables = self.tables.lock().await;
+        Ok(tables.chats.iter().find(|a| a.id == id).cloned())
+    }
+
+    async fn update_chat(&self, id: Uuid, update: UpdateChat) -> Result<u64, AppError> {
+        if update.title.is_none()
+            && update.pinned.is_none()
+            && update.starred.is_none()
+            && update.archived.is_none()
+        {
+            return Ok(0);
+        }
+        let mut tables = self.tables.lock().await;
+        let Some(chat) = tables.chats.iter_mut().find(|a| a.id == id) else {
+            return Ok(0);
+        };
+        chat.updated_at = now_millis();
+        if let Some(title) = update.title {
+            chat.title = title;
+        }
+        if let Some(pinned) = update.pinned {
+            chat.pinned = pinned;
+        }
+        if let Some(starred) = update.starred {
+            chat.starred = starred;
+        }
+        if let Some(archived) = update.archived {
+            chat.archived_at = match archived {
+                true => chat.archived_at.or_else(|| Some(now_millis())),
+                false => None,
+            };
+        }
+        Ok(1)
+    }
+
+    async fn get_chats(&self, filter: ChatFilter) -> Result<Vec<ChatRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut chats = tables
+            .chats
+            .iter()
+            .filter(|a| filter.pinned.is_none_or(|b| a.pinned == b))
+            .filter(|a| filter.starred.is_none_or(|b| a.starred == b))
+            .filter(|a| filter.archived.is_none_or(|b| a.archived_at.is_some() == b))
+            .filter(|a| filter.project_id.is_none_or(|b| a.project_id == Some(b)))
+            .filter(|a| {
+                filter
+                    .tag_ids
+                    .iter()
+                    .all(|b| tables.chat_tags.contains(&(a.id, *b)))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        chats.sort_by_key(|a| (!a.pinned, -a.created_at));
+        Ok(chats)
+    }
+
+    async fn delete_chat(&self, id: Uuid) -> Result<u64, AppError> {
+        Ok(self.tables.lock().await.delete_chat(id))
+    }
+
+    async fn get_tags(&self) -> Result<Vec<TagRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut tags = tables.tags.clone();
+        tags.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tags)
+    }
+
+    async fn upsert_tag(&self, create: CreateTag) -> Result<TagRow, AppError> {
+        let mut tables = self.tables.lock().await;
+        if let Some(tag) = tables.tags.iter().find(|a| a.name == create.name) {
+            return Ok(tag.clone());
+        }
+        let row = TagRow {
+            created_at: now_millis(),
+            id: create.id,
+            name: create.name,
+        };
+        tables.tags.push(row.clone());
+        Ok(row)
+    }
+
+    async fn delete_tag(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        let count = tables.tags.len();
+        tables.tags.retain(|a| a.id != id);
+        tables.chat_tags.retain(|a| a.1 != id);
+        Ok((count - tables.tags.len()) as u64)
+    }
+
+    async fn get_chat_tags(&self, chat_id: Uuid) -> Result<Vec<TagRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut tags = tables
+            .tags
+            .iter()
+            .filter(|a| tables.chat_tags.contains(&(chat_id, a.id)))
+            .cloned()
+            .collect::<Vec<_>>();
+        tags.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tags)
+    }
+
+    async fn tag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<(), AppError> {
+        let mut tables = self.tables.lock().await;
+        if !tables.chats.iter().any(|a| a.id == chat_id) {
+            return Err(AppError::ChatNotFound(chat_id));
+        }
+        if !tables.chat_tags.contains(&(chat_id, tag_id)) {
+            tables.chat_tags.push((chat_id, tag_id));
+        }
+        Ok(())
+    }
+
+    async fn untag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        let count = tables.chat_tags.len();
+        tables.chat_tags.retain(|a| *a != (chat_id, tag_id));
+        Ok((count - tables.chat_tags.len()) as u64)
+    }
+
+    async fn get_chat_tool_approvals(&self, chat_id: Uuid) -> Result<Vec<String>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut tool_names = tables
+            .chat_tool_approvals
+            .iter()
+            .filter(|a| a.0 == chat_id)
+            .map(|a| a.1.clone())
+            .collect::<Vec<_>>();
+        tool_names.sort();
+        Ok(tool_names)
+    }
+
+    async fn create_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<(), AppError> {
+        let mut tables = self.tables.lock().await;
+        let approval = (chat_id, tool_name.to_string());
+        if !tables.chat_tool_approvals.contains(&approval) {
+            tables.chat_tool_approvals.push(approval);
+        }
+        Ok(())
+    }
+
+    async fn delete_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        let count = tables.chat_tool_approvals.len();
+        tables
+            .chat_tool_approvals
+            .retain(|a| a.0 != chat_id || a.1 != tool_name);
+        Ok((count - tables.chat_tool_approvals.len()) as u64)
+    }
+
+    async fn get_chat_message_embedding_candidates(
+        &self,
+        embedding_model: &str,
+        limit: i64,
+    ) -> Result<Vec<ChatMessageEmbeddingCandidateRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut messages = tables
+            .chat_messages
+            .iter()
+            .filter(|a| is_embeddable(a))
+            .filter(|a| {
+                !tables.chat_message_embeddings.iter().any(|b| {
+                    b.message_id == a.id
+                        && b.embedding_model == embedding_model
+                        && b.content_revision == a.content_revision
+                })
+            })
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|a| a.created_at);
+        Ok(messages
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|a| ChatMessageEmbeddingCandidateRow {
+                id: a.id,
+                content: a.content.clone(),
+                content_revision: a.content_revision,
+            })
+            .collect())
+    }
+
+    async fn upsert_chat_message_embedding(
+        &self,
+        upsert: UpsertChatMessageEmbedding,
+    ) -> Result<(), AppError> {
+        let mut tables = self.tables.lock().await;
+        if !tables
+            .chat_messages
+            .iter()
+            .any(|a| a.id == upsert.message_id)
+        {
+            return Err(AppError::ChatMessageNotFound(upsert.message_id));
+        }
+        tables
+            .chat_message_embeddings
+            .retain(|a| a.message_id != upsert.message_id);
+        tables
+            .chat_message_embeddings
+            .push(ChatMessageEmbeddingRecord {
+                message_id: upsert.message_id,
+                embedding_model: upsert.embedding_model,
+                content_revision: upsert.content_revision,
+                embedding: upsert.embedding,
+            });
+        Ok(())
+    }
+
+    async fn delete_stale_chat_message_embeddings(&self) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        let embeddable = tables
+            .chat_messages
+            .iter()
+            .filter(|a| is_embeddable(a))
+            .map(|a| a.id)
+            .collect::<Vec<_>>();
+        let count = tables.chat_message_embeddings.len();
+        tables
+            .chat_message_embeddings
+            .retain(|a| embeddable.contains(&a.message_id));
+        Ok((count - tables.chat_message_embeddings.len()) as u64)
+    }
+
+    async fn get_chat_message_embeddings(
+        &self,
+        embedding_model: &str,
+    ) -> Result<Vec<ChatMessageEmbeddingRow>, AppError> {
+        let tables = self.tables.lock().await;
+        Ok(tables
+            .chat_message_embeddings
+            .iter()
+            .filter(|a| a.embedding_model == embedding_model)
+            .filter_map(|a| {
+                let message = tables
+                    .chat_messages
+                    .iter()
+                    .find(|b| b.id == a.message_id && b.content_revision == a.content_revision)?;
+                let chat = tables.chats.iter().find(|b| b.id == message.chat_id)?;
+                Some(ChatMessageEmbeddingRow {
+                    message_id: message.id,
+                    chat_id: chat.id,
+                    chat_title: chat.title.clone(),
+                    role: message.role.clone(),
+                    content: message.content.clone(),
+                    created_at: message.created_at,
+                    embedding: a.embedding.clone(),
+                })
+            })
+            .collect())
+    }
+
+    async fn get_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<Option<ChatMessageAttachmentRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let Some(message) = tables.chat_messages.iter().find(|a| a.id == message_id) else {
+            return Ok(None);
+        };
+        Ok(tables
+            .chat_message_speeches
+            .iter()
+            .find(|a| {
+                a.message_id == message_id
+                    && a.agent_id == agent_id
+                    && a.voice == voice
+                    && a.content_revision == message.content_revision
+            })
+            .and_then(|a| {
+                tables
+                    .chat_message_attachments
+                    .iter()
+                    .find(|b| b.id == a.attachment_id)
+            })
+            .cloned())
+    }
+
+    async fn create_chat_message_speech(
+        &self,
+        create: CreateChatMessageSpeech,
+    ) -> Result<(), AppError> {
+        let mut tables = self.tables.lock().await;
+        tables.chat_message_speeches.push(ChatMessageSpeechRecord {
+            attachment_id: create.attachment_id,
+            message_id: create.message_id,
+            agent_id: create.agent_id,
+            voice: create.voice,
+            content_revision: create.content_revision,
+        });
+        Ok(())
+    }
+
+    async fn delete_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        let attachment_ids = tables
+            .chat_message_speeches
+            .iter()
+            .filter(|a| a.message_id == message_id && a.agent_id == agent_id && a.voice == voice)
+            .map(|a| a.attachment_id)
+            .collect::<Vec<_>>();
+        let count = tables.chat_message_attachments.len();
+        tables
+            .chat_message_attachments
+            .retain(|a| !attachment_ids.contains(&a.id));
+        tables.delete_orphaned_speeches();
+        Ok((count - tables.chat_message_attachments.len()) as u64)
+    }
+
+    async fn get_chat_encryption(&self) -> Result<bool, AppError> {
+        Ok(self.tables.lock().await.chat_encryption)
+    }
+
+    async fn update_chat_encryption(&self, enabled: bool) -> Result<(), AppError> {
+        self.tables.lock().await.chat_encryption = enabled;
+        Ok(())
+    }
+
+    async fn reseal_chat_title(&self, id: Uuid, from: &str, to: &str) -> Result<bool, AppError> {
+        let mut tables = self.tables.lock().await;
+        match tables
+            .chats
+            .iter_mut()
+            .find(|a| a.id == id && a.title == from)
+        {
+            Some(chat) => {
+                chat.title = to.to_string();
+                chat.updated_at = now_millis();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn reseal_chat_message_content(
+        &self,
+        id: Uuid,
+        from: &str,
+        to: &str,
+    ) -> Result<bool, AppError> {
+        if from == to {
+            return Ok(false);
+        }
+        let mut tables = self.tables.lock().await;
+        match tables
+            .chat_messages
+            .iter_mut()
+            .find(|a| a.id == id && a.content == from)
+        {
+            Some(message) => {
+                message.content = to.to_string();
+                message.updated_at = now_millis();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+fn is_embeddable(message: &ChatMessageRow) -> bool {
+    matches!(message.status, ChatMessageStatus::Completed)
+        && message.kind == ChatMessageKind::Text
+        && (message.role == "user" || message.role == "model")
+        && !message.content.trim().is_empty()
+}
@@ -1,13 +1,22 @@
 use async_trait::async_trait;
-use sqlx::{Executor, Pool, QueryBuilder, Sqlite, SqliteTransaction};
+use sqlx::{
+    types::Json, Executor, Pool, QueryBuilder, Sqlite, SqliteConnection, SqliteTransaction,
+};
 use std::sync::Arc;
 use tauri::async_runtime::Mutex;
 use uuid::Uuid;
 
 use crate::{
-    chat::repo::{ChatRepo, CreateChatMessage, UpdateChatMessage},
+    chat::repo::{
+        ChatFilter, ChatRepo, CreateChat, CreateChatMessage, CreateChatMessageAttachment,
+        CreateChatMessageSpeech, CreateTag, UpdateChat, UpdateChatMessage,
+        UpsertChatMessageEmbedding,
+    },
     common::{
-        entity::chat::{ChatMessageRow, ChatRow},
+        entity::chat::{
+            ChatMessageAttachmentRow, ChatMessageEmbeddingCandidateRow, ChatMessageEmbeddingRow,
+            ChatMessageRow, ChatRow, TagRow,
+        },
         error::AppError,
     },
 };
@@ -38,6 +47,14 @@ impl ChatRepo for SqliteChatRepo {
         get_chat_messages(&*self.db_pool, chat_id).await
     }
 
+    async fn get_chat_message(&self, id: Uuid) -> Result<Option<ChatMessageRow>, AppError> {
+        get_chat_message(&*self.db_pool, id).await
+    }
+
+    async fn get_pending_chat_messages(&self) -> Result<Vec<ChatMessageRow>, AppError> {
+        get_pending_chat_messages(&*self.db_pool).await
+    }
+
     async fn create_chat_message(
         &self,
         message: CreateChatMessage,
@@ -53,9 +70,169 @@ impl ChatRepo for SqliteChatRepo {
         update_chat_message(&*self.db_pool, id, update).await
     }
 
+    async fn delete_chat_message(&self, id: Uuid) -> Result<u64, AppError> {
+        delete_chat_message(&*self.db_pool, id).await
+    }
+
+    async fn fail_pending_chat_message(&self, id: Uuid) -> Result<u64, AppError> {
+        fail_pending_chat_message(&*self.db_pool, id).await
+    }
+
+    async fn create_chat_message_attachment(
+        &self,
+        create: CreateChatMessageAttachment,
+    ) -> Result<ChatMessageAttachmentRow, AppError> {
+        create_chat_message_attachment(&*self.db_pool, create).await
+    }
+
+    async fn get_chat_attachments(
+        &self,
+        chat_id: Uuid,
+    ) -> Result<Vec<ChatMessageAttachmentRow>, AppError> {
+        get_chat_attachments(&*self.db_pool, chat_id).await
+    }
+
+    async fn create_chat(&self, create: CreateChat) -> Result<ChatRow, AppError> {
+        create_chat(&*self.db_pool, create).await
+    }
+
     async fn get_chat(&self, id: Uuid) -> Result<Option<ChatRow>, AppError> {
         get_chat(&*self.db_pool, id).await
     }
+
+    async fn update_chat(&self, id: Uuid, update: UpdateChat) -> Result<u64, AppError> {
+        update_chat(&*self.db_pool, id, update).await
+    }
+
+    async fn get_chats(&self, filter: ChatFilter) -> Result<Vec<ChatRow>, AppError> {
+        get_chats(&*self.db_pool, filter).await
+    }
+
+    async fn delete_chat(&self, id: Uuid) -> Result<u64, AppError> {
+        delete_chat(&*self.db_pool, id).await
+    }
+
+    async fn get_tags(&self) -> Result<Vec<TagRow>, AppError> {
+        get_tags(&*self.db_pool).await
+    }
+
+    async fn upsert_tag(&self, create: CreateTag) -> Result<TagRow, AppError> {
+        upsert_tag(&*self.db_pool, create).await
+    }
+
+    async fn delete_tag(&self, id: Uuid) -> Result<u64, AppError> {
+        delete_tag(&*self.db_pool, id).await
+    }
+
+    async fn get_chat_tags(&self, chat_id: Uuid) -> Result<Vec<TagRow>, AppError> {
+        get_chat_tags(&*self.db_pool, chat_id).await
+    }
+
+    async fn tag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<(), AppError> {
+        if get_chat(&*self.db_pool, chat_id).await?.is_none() {
+            return Err(AppError::ChatNotFound(chat_id));
+        }
+        tag_chat(&*self.db_pool, chat_id, tag_id).await
+    }
+
+    async fn untag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<u64, AppError> {
+        untag_chat(&*self.db_pool, chat_id, tag_id).await
+    }
+
+    async fn get_chat_tool_approvals(&self, chat_id: Uuid) -> Result<Vec<String>, AppError> {
+        get_chat_tool_approvals(&*self.db_pool, chat_id).await
+    }
+
+    async fn create_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<(), AppError> {
+        create_chat_tool_approval(&*self.db_pool, chat_id, tool_name).await
+    }
+
+    async fn delete_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<u64, AppError> {
+        delete_chat_tool_approval(&*self.db_pool, chat_id, tool_name).await
+    }
+
+    async fn get_chat_message_embedding_candidates(
+        &self,
+        embedding_model: &str,
+        limit: i64,
+    ) -> Result<Vec<ChatMessageEmbeddingCandidateRow>, AppError> {
+        get_chat_message_embedding_candidates(&*self.db_pool, embedding_model, limit).await
+    }
+
+    async fn upsert_chat_message_embedding(
+        &self,
+        upsert: UpsertChatMessageEmbedding,
+    ) -> Result<(), AppError> {
+        upsert_chat_message_embedding(&*self.db_pool, upsert).await
+    }
+
+    async fn delete_stale_chat_message_embeddings(&self) -> Result<u64, AppError> {
+        delete_stale_chat_message_embeddings(&*self.db_pool).await
+    }
+
+    async fn get_chat_message_embeddings(
+        &self,
+        embedding_model: &str,
+    ) -> Result<Vec<ChatMessageEmbeddingRow>, AppError> {
+        get_chat_message_embeddings(&*self.db_pool, embedding_model).await
+    }
+
+    async fn get_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<Option<ChatMessageAttachmentRow>, AppError> {
+        get_chat_message_speech(&*self.db_pool, message_id, agent_id, voice).await
+    }
+
+    async fn create_chat_message_speech(
+        &self,
+        create: CreateChatMessageSpeech,
+    ) -> Result<(), AppError> {
+        create_chat_message_speech(&*self.db_pool, create).await
+    }
+
+    async fn delete_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<u64, AppError> {
+        delete_chat_message_speech(&*self.db_pool, message_id, agent_id, voice).await
+    }
+
+    async fn get_chat_encryption(&self) -> Result<bool, AppError> {
+        get_chat_encryption(&*self.db_pool).await
+    }
+
+    async fn update_chat_encryption(&self, enabled: bool) -> Result<(), AppError> {
+        update_chat_encryption(&*self.db_pool, enabled).await
+    }
+
+    async fn reseal_chat_title(&self, id: Uuid, from: &str, to: &str) -> Result<bool, AppError> {
+        reseal_chat_title(&*self.db_pool, id, from, to).await
+    }
+
+    async fn reseal_chat_message_content(
+        &self,
+        id: Uuid,
+        from: &str,
+        to: &str,
+    ) -> Result<bool, AppError> {
+        let mut tx = self.db_pool.begin().await.map_err(AppError::from)?;
+        let resealed = reseal_chat_message_content(&mut tx, id, from, to).await?;
+        tx.commit().await.map_err(AppError::from)?;
+        Ok(resealed)
+    }
 }
 
 #[async_trait]
@@ -65,6 +242,16 @@ impl<'a> ChatRepo for TransactionalSqliteChatRepo<'a> {
         get_chat_messages(&mut **tx, chat_id).await
     }
 
+    async fn get_chat_message(&self, id: Uuid) -> Result<Option<ChatMessageRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_chat_message(&mut **tx, id).await
+    }
+
+    async fn get_pending_chat_messages(&self) -> Result<Vec<ChatMessageRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_pending_chat_messages(&mut **tx).await
+    }
+
     async fn create_chat_message(
         &self,
         message: CreateChatMessage,
@@ -82,10 +269,195 @@ impl<'a> ChatRepo for TransactionalSqliteChatRepo<'a> {
         update_chat_message(&mut **tx, id, update).await
     }
 
+    async fn delete_chat_message(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        delete_chat_message(&mut **tx, id).await
+    }
+
+    async fn fail_pending_chat_message(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        fail_pending_chat_message(&mut **tx, id).await
+    }
+
+    async fn create_chat_message_attachment(
+        &self,
+        create: CreateChatMessageAttachment,
+    ) -> Result<ChatMessageAttachmentRow, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        create_chat_message_attachment(&mut **tx, create).await
+    }
+
+    async fn get_chat_attachments(
+        &self,
+        chat_id: Uuid,
+    ) -> Result<Vec<ChatMessageAttachmentRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_chat_attachments(&mut **tx, chat_id).await
+    }
+
+    async fn create_chat(&self, create: CreateChat) -> Result<ChatRow, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        create_chat(&mut **tx, create).await
+    }
+
     async fn get_chat(&self, id: Uuid) -> Result<Option<ChatRow>, AppError> {
         let mut tx = self.tx.try_lock().map_err(AppError::from)?;
         get_chat(&mut **tx, id).await
     }
+
+    async fn update_chat(&self, id: Uuid, update: UpdateChat) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        update_chat(&mut **tx, id, update).await
+    }
+
+    async fn get_chats(&self, filter: ChatFilter) -> Result<Vec<ChatRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_chats(&mut **tx, filter).await
+    }
+
+    async fn delete_chat(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        delete_chat(&mut **tx, id).await
+    }
+
+    async fn get_tags(&self) -> Result<Vec<TagRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_tags(&mut **tx).await
+    }
+
+    async fn upsert_tag(&self, create: CreateTag) -> Result<TagRow, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        upsert_tag(&mut **tx, create).await
+    }
+
+    async fn delete_tag(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        delete_tag(&mut **tx, id).await
+    }
+
+    async fn get_chat_tags(&self, chat_id: Uuid) -> Result<Vec<TagRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_chat_tags(&mut **tx, chat_id).await
+    }
+
+    async fn tag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<(), AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        if get_chat(&mut **tx, chat_id).await?.is_none() {
+            return Err(AppError::ChatNotFound(chat_id));
+        }
+        tag_chat(&mut **tx, chat_id, tag_id).await
+    }
+
+    async fn untag_chat(&self, chat_id: Uuid, tag_id: Uuid) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        untag_chat(&mut **tx, chat_id, tag_id).await
+    }
+
+    async fn get_chat_tool_approvals(&self, chat_id: Uuid) -> Result<Vec<String>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_chat_tool_approvals(&mut **tx, chat_id).await
+    }
+
+    async fn create_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<(), AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        create_chat_tool_approval(&mut **tx, chat_id, tool_name).await
+    }
+
+    async fn delete_chat_tool_approval(
+        &self,
+        chat_id: Uuid,
+        tool_name: &str,
+    ) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        delete_chat_tool_approval(&mut **tx, chat_id, tool_name).await
+    }
+
+    async fn get_chat_message_embedding_candidates(
+        &self,
+        embedding_model: &str,
+        limit: i64,
+    ) -> Result<Vec<ChatMessageEmbeddingCandidateRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_chat_message_embedding_candidates(&mut **tx, embedding_model, limit).await
+    }
+
+    async fn upsert_chat_message_embedding(
+        &self,
+        upsert: UpsertChatMessageEmbedding,
+    ) -> Result<(), AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        upsert_chat_message_embedding(&mut **tx, upsert).await
+    }
+
+    async fn delete_stale_chat_message_embeddings(&self) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        delete_stale_chat_message_embeddings(&mut **tx).await
+    }
+
+    async fn get_chat_message_embeddings(
+        &self,
+        embedding_model: &str,
+    ) -> Result<Vec<ChatMessageEmbeddingRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_chat_message_embeddings(&mut **tx, embedding_model).await
+    }
+
+    async fn get_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<Option<ChatMessageAttachmentRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_chat_message_speech(&mut **tx, message_id, agent_id, voice).await
+    }
+
+    async fn create_chat_message_speech(
+        &self,
+        create: CreateChatMessageSpeech,
+    ) -> Result<(), AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        create_chat_message_speech(&mut **tx, create).await
+    }
+
+    async fn delete_chat_message_speech(
+        &self,
+        message_id: Uuid,
+        agent_id: Uuid,
+        voice: &str,
+    ) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        delete_chat_message_speech(&mut **tx, message_id, agent_id, voice).await
+    }
+
+    async fn get_chat_encryption(&self) -> Result<bool, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_chat_encryption(&mut **tx).await
+    }
+
+    async fn update_chat_encryption(&self, enabled: bool) -> Result<(), AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        update_chat_encryption(&mut **tx, enabled).await
+    }
+
+    async fn reseal_chat_title(&self, id: Uuid, from: &str, to: &str) -> Result<bool, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        reseal_chat_title(&mut **tx, id, from, to).await
+    }
+
+    async fn reseal_chat_message_content(
+        &self,
+        id: Uuid,
+        from: &str,
+        to: &str,
+    ) -> Result<bool, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        reseal_chat_message_content(&mut tx, id, from, to).await
+    }
 }
 
 async fn get_chat_messages<'a, E>(
@@ -104,6 +476,29 @@ where
     .map_err(AppError::from)
 }
 
+async fn get_chat_message<'a, E>(executor: E, id: Uuid) -> Result<Option<ChatMessageRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ChatMessageRow>("select * from chat_messages where id = ?1")
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn get_pending_chat_messages<'a, E>(executor: E) -> Result<Vec<ChatMessageRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ChatMessageRow>(
+        "select * from chat_messages where status = 'pending' order by created_at asc",
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
 async fn create_chat_message<'a, E>(
     executor: E,
     message: CreateChatMessage,
@@ -111,23 +506,43 @@ async fn create_chat_message<'a, E>(
 where
     E: Executor<'a, Database = Sqlite>,
 {
-    let created_at = sqlx::query_scalar::<_, i64>("insert into chat_messages (id, chat_id, role, content, status) values (?1, ?2, ?3, ?4, ?5) returning created_at")
+    let (created_at, updated_at): (i64, i64) = sqlx::query_as("insert into chat_messages (id, chat_id, role, content, status, created_at, updated_at, kind, tool_calls, tool_call_id, tool_name) values (?1, ?2, ?3, ?4, ?5, coalesce(?6, cast(unixepoch('now', 'subsecond') * 1000 as integer)), coalesce(?6, cast(unixepoch('now', 'subsecond') * 1000 as integer)), ?7, ?8, ?9, ?10) returning created_at, updated_at")
             .bind(message.id)
             .bind(message.chat_id)
             .bind(&message.role)
             .bind(&message.content)
             .bind(&message.status)
+            .bind(message.created_at)
+            .bind(message.kind)
+            .bind(message.tool_calls.as_ref().map(Json))
+            .bind(&message.tool_call_id)
+            .bind(&message.tool_name)
             .fetch_one(executor)
             .await
-            .map_err(AppError::from)?;
+            .map_err(|e| match &e {
+                // The chat is the only row a new message refers to.
+                sqlx::Error::Database(error) if error.is_foreign_key_violation() => {
+                    AppError::ChatNotFound(message.chat_id)
+                }
+                _ => AppError::from(e),
+            })?;
 
     Ok(ChatMessageRow {
         created_at,
+        updated_at,
         id: message.id,
         chat_id: message.chat_id,
         role: message.role,
         content: message.content,
         status: message.status,
+        starred: false,
+        kind: message.kind,
+        tool_calls: message.tool_calls.map(Json),
+        tool_call_id: message.tool_call_id,
+        tool_name: message.tool_name,
+        schema_errors: None,
+        citations: None,
+        content_revision: 0,
     })
 }
 
@@ -150,12 +565,128 @@ where
     if let Some(status) = update.status {
         sep.push("status = ").push_bind_unseparated(status);
     }
+    if let Some(starred) = update.starred {
+        sep.push("starred = ").push_bind_unseparated(starred);
+    }
+    if let Some(kind) = update.kind {
+        sep.push("kind = ").push_bind_unseparated(kind);
+    }
+    if let Some(tool_calls) = update.tool_calls {
+        sep.push("tool_calls = ")
+            .push_bind_unseparated(Json(tool_calls));
+    }
+    if let Some(schema_errors) = update.schema_errors {
+        sep.push("schema_errors = ")
+            .push_bind_unseparated(Json(schema_errors));
+    }
+    if let Some(citations) = update.citations {
+        sep.push("citations = ")
+            .push_bind_unseparated(Json(citations));
+    }
     qb.push(" where id = ").push_bind(id);
 
     qb.build().execute(executor).await.map_err(AppError::from)?;
     Ok(())
 }
 
+async fn fail_pending_chat_message<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query(
+        "update chat_messages set status = 'failed' where id = ?1 and status = 'pending'",
+    )
+    .bind(id)
+    .execute(executor)
+    .await
+    .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn delete_chat_message<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from chat_messages where id = ?1")
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn create_chat_message_attachment<'a, E>(
+    executor: E,
+    create: CreateChatMessageAttachment,
+) -> Result<ChatMessageAttachmentRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let created_at = sqlx::query_scalar::<_, i64>("insert into chat_message_attachments (id, message_id, kind, name, mime_type, data, path, text) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) returning created_at")
+        .bind(create.id)
+        .bind(create.message_id)
+        .bind(create.kind)
+        .bind(&create.name)
+        .bind(&create.mime_type)
+        .bind(&create.data)
+        .bind(&create.path)
+        .bind(&create.text)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(ChatMessageAttachmentRow {
+        created_at,
+        id: create.id,
+        message_id: create.message_id,
+        kind: create.kind,
+        name: create.name,
+        mime_type: create.mime_type,
+        data: create.data,
+        path: create.path,
+        text: create.text,
+    })
+}
+
+async fn get_chat_attachments<'a, E>(
+    executor: E,
+    chat_id: Uuid,
+) -> Result<Vec<ChatMessageAttachmentRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ChatMessageAttachmentRow>("select chat_message_attachments.* from chat_message_attachments inner join chat_messages on chat_messages.id = chat_message_attachments.message_id where chat_messages.chat_id = ?1 order by chat_message_attachments.created_at asc")
+        .bind(chat_id)
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn create_chat<'a, E>(executor: E, create: CreateChat) -> Result<ChatRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let (created_at, updated_at): (i64, i64) = sqlx::query_as("insert into chats (id, title, created_at, updated_at, project_id) values (?1, ?2, coalesce(?3, cast(unixepoch('now', 'subsecond') * 1000 as integer)), coalesce(?3, cast(unixepoch('now', 'subsecond') * 1000 as integer)), ?4) returning created_at, updated_at")
+        .bind(create.id)
+        .bind(&create.title)
+        .bind(create.created_at)
+        .bind(create.project_id)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(ChatRow {
+        created_at,
+        updated_at,
+        id: create.id,
+        title: create.title,
+        pinned: false,
+        starred: false,
+        project_id: create.project_id,
+        archived_at: None,
+    })
+}
+
 async fn get_chat<'a, E>(executor: E, id: Uuid) -> Result<Option<ChatRow>, AppError>
 where
     E: Executor<'a, Database = Sqlite>,
@@ -166,3 +697,453 @@ where
         .await
         .map_err(AppError::from)
 }
+
+async fn update_chat<'a, E>(executor: E, id: Uuid, update: UpdateChat) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    if update.title.is_none()
+        && update.pinned.is_none()
+        && update.starred.is_none()
+        && update.archived.is_none()
+    {
+        return Ok(0);
+    }
+    let mut qb = QueryBuilder::new("update chats set ");
+    let mut sep = qb.separated(", ");
+    if let Some(title) = update.title {
+        sep.push("title = ").push_bind_unseparated(title);
+    }
+    if let Some(pinned) = update.pinned {
+        sep.push("pinned = ").push_bind_unseparated(pinned);
+    }
+    if let Some(starred) = update.starred {
+        sep.push("starred = ").push_bind_unseparated(starred);
+    }
+    if let Some(archived) = update.archived {
+        sep.push(if archived {
+            "archived_at = coalesce(archived_at, cast(unixepoch('now', 'subsecond') * 1000 as integer))"
+        } else {
+            "archived_at = null"
+        });
+    }
+    qb.push(" where id = ").push_bind(id);
+
+    let result = qb.build().execute(executor).await.map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn get_chats<'a, E>(executor: E, filter: ChatFilter) -> Result<Vec<ChatRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let mut qb = QueryBuilder::new("select chats.* from chats where 1 = 1");
+    if let Some(pinned) = filter.pinned {
+        qb.push(" and pinned = ").push_bind(pinned);
+    }
+    if let Some(starred) = filter.starred {
+        qb.push(" and starred = ").push_bind(starred);
+    }
+    if let Some(archived) = filter.archived {
+        qb.push(if archived {
+            " and archived_at is not null"
+        } else {
+            " and archived_at is null"
+        });
+    }
+    if let Some(project_id) = filter.project_id {
+        qb.push(" and project_id = ").push_bind(project_id);
+    }
+    for tag_id in filter.tag_ids {
+        qb.push(" and exists (select 1 from chat_tags where chat_tags.chat_id = chats.id and chat_tags.tag_id = ")
+            .push_bind(tag_id)
+            .push(")");
+    }
+    qb.push(" order by pinned desc, created_at desc");
+    qb.build_query_as::<ChatRow>()
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn delete_chat<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from chats where id = ?1")
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn get_tags<'a, E>(executor: E) -> Result<Vec<TagRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, TagRow>("select * from tags order by name asc")
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn upsert_tag<'a, E>(executor: E, create: CreateTag) -> Result<TagRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, TagRow>("insert into tags (id, name) values (?1, ?2) on conflict (name) do update set name = excluded.name returning *")
+        .bind(create.id)
+        .bind(create.name)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn delete_tag<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from tags where id = ?1")
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn get_chat_tags<'a, E>(executor: E, chat_id: Uuid) -> Result<Vec<TagRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, TagRow>("select tags.* from tags inner join chat_tags on chat_tags.tag_id = tags.id where chat_tags.chat_id = ?1 order by tags.name asc")
+        .bind(chat_id)
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn tag_chat<'a, E>(executor: E, chat_id: Uuid, tag_id: Uuid) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("insert into chat_tags (chat_id, tag_id) values (?1, ?2) on conflict do nothing")
+        .bind(chat_id)
+        .bind(tag_id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn untag_chat<'a, E>(executor: E, chat_id: Uuid, tag_id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from chat_tags where chat_id = ?1 and tag_id = ?2")
+        .bind(chat_id)
+        .bind(tag_id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn get_chat_tool_approvals<'a, E>(executor: E, chat_id: Uuid) -> Result<Vec<String>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_scalar::<_, String>(
+        "select tool_name from chat_tool_approvals where chat_id = ?1 order by tool_name asc",
+    )
+    .bind(chat_id)
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn create_chat_tool_approval<'a, E>(
+    executor: E,
+    chat_id: Uuid,
+    tool_name: &str,
+) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query(
+        "insert into chat_tool_approvals (chat_id, tool_name) values (?1, ?2) on conflict do nothing",
+    )
+    .bind(chat_id)
+    .bind(tool_name)
+    .execute(executor)
+    .await
+    .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn delete_chat_tool_approval<'a, E>(
+    executor: E,
+    chat_id: Uuid,
+    tool_name: &str,
+) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result =
+        sqlx::query("delete from chat_tool_approvals where chat_id = ?1 and tool_name = ?2")
+            .bind(chat_id)
+            .bind(tool_name)
+            .execute(executor)
+            .await
+            .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+const EMBEDDABLE_CHAT_MESSAGE: &str = "m.status = 'completed' and m.kind = 'text' and m.role in ('user', 'model') and trim(m.content) != ''";
+
+async fn get_chat_message_embedding_candidates<'a, E>(
+    executor: E,
+    embedding_model: &str,
+    limit: i64,
+) -> Result<Vec<ChatMessageEmbeddingCandidateRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ChatMessageEmbeddingCandidateRow>(&format!(
+        "select m.id, m.content, m.content_revision from chat_messages m left join chat_message_embeddings e on e.message_id = m.id where {} and (e.message_id is null or e.embedding_model != ?1 or e.content_revision != m.content_revision) order by m.created_at asc limit ?2",
+        EMBEDDABLE_CHAT_MESSAGE
+    ))
+    .bind(embedding_model)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn upsert_chat_message_embedding<'a, E>(
+    executor: E,
+    upsert: UpsertChatMessageEmbedding,
+) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("insert into chat_message_embeddings (message_id, embedding_model, content_revision, embedding) values (?1, ?2, ?3, ?4) on conflict (message_id) do update set embedding_model = excluded.embedding_model, content_revision = excluded.content_revision, embedding = excluded.embedding")
+        .bind(upsert.message_id)
+        .bind(&upsert.embedding_model)
+        .bind(upsert.content_revision)
+        .bind(&upsert.embedding)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// Deleted messages lose their embedding through the foreign key; this removes the
+/// embeddings of messages that are no longer worth searching, e.g. emptied ones.
+async fn delete_stale_chat_message_embeddings<'a, E>(executor: E) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query(&format!(
+        "delete from chat_message_embeddings where message_id not in (select m.id from chat_messages m where {})",
+        EMBEDDABLE_CHAT_MESSAGE
+    ))
+    .execute(executor)
+    .await
+    .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn get_chat_message_embeddings<'a, E>(
+    executor: E,
+    embedding_model: &str,
+) -> Result<Vec<ChatMessageEmbeddingRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ChatMessageEmbeddingRow>(
+        "select e.message_id, m.chat_id, c.title as chat_title, m.role, m.content, m.created_at, e.embedding from chat_message_embeddings e inner join chat_messages m on m.id = e.message_id inner join chats c on c.id = m.chat_id where e.embedding_model = ?1 and e.content_revision = m.content_revision",
+    )
+    .bind(embedding_model)
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Only returns audio synthesized from the message's current content.
+async fn get_chat_message_speech<'a, E>(
+    executor: E,
+    message_id: Uuid,
+    agent_id: Uuid,
+    voice: &str,
+) -> Result<Option<ChatMessageAttachmentRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ChatMessageAttachmentRow>("select a.* from chat_message_attachments a inner join chat_message_speeches s on s.attachment_id = a.id inner join chat_messages m on m.id = s.message_id where s.message_id = ?1 and s.agent_id = ?2 and s.voice = ?3 and s.content_revision = m.content_revision")
+        .bind(message_id)
+        .bind(agent_id)
+        .bind(voice)
+        .fetch_optional(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn create_chat_message_speech<'a, E>(
+    executor: E,
+    create: CreateChatMessageSpeech,
+) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("insert into chat_message_speeches (attachment_id, message_id, agent_id, voice, content_revision) values (?1, ?2, ?3, ?4, ?5)")
+        .bind(create.attachment_id)
+        .bind(create.message_id)
+        .bind(create.agent_id)
+        .bind(&create.voice)
+        .bind(create.content_revision)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// Deletes the audio attachment itself; its speech row goes with it.
+async fn delete_chat_message_speech<'a, E>(
+    executor: E,
+    message_id: Uuid,
+    agent_id: Uuid,
+    voice: &str,
+) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from chat_message_attachments where id in (select attachment_id from chat_message_speeches where message_id = ?1 and agent_id = ?2 and voice = ?3)")
+        .bind(message_id)
+        .bind(agent_id)
+        .bind(voice)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn get_chat_encryption<'a, E>(executor: E) -> Result<bool, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let enabled = sqlx::query_scalar::<_, bool>("select enabled from chat_encryption where id = 1")
+        .fetch_optional(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(enabled.unwrap_or(false))
+}
+
+async fn update_chat_encryption<'a, E>(executor: E, enabled: bool) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("insert into chat_encryption (id, enabled) values (1, ?1) on conflict (id) do update set enabled = excluded.enabled")
+        .bind(enabled)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn reseal_chat_title<'a, E>(
+    executor: E,
+    id: Uuid,
+    from: &str,
+    to: &str,
+) -> Result<bool, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("update chats set title = ?3 where id = ?1 and title = ?2")
+        .bind(id)
+        .bind(from)
+        .bind(to)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// The content trigger bumps the revision by one, so it is taken back right after
+/// within the same transaction.
+async fn reseal_chat_message_content(
+    conn: &mut SqliteConnection,
+    id: Uuid,
+    from: &str,
+    to: &str,
+) -> Result<bool, AppError> {
+    if from == to {
+        return Ok(false);
+    }
+    let result =
+        sqlx::query("update chat_messages set content = ?3 where id = ?1 and content = ?2")
+            .bind(id)
+            .bind(from)
+            .bind(to)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    sqlx::query("update chat_messages set content_revision = content_revision - 1 where id = ?1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::common::entity::chat::{ChatMessageKind, ChatMessageStatus};
+
+    #[tokio::test]
+    async fn reports_missing_chat_like_memory_repo() {
+        let db_pool = Arc::new(
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+                .await
+                .unwrap(),
+        );
+        sqlx::migrate!("./migrations").run(&*db_pool).await.unwrap();
+        let chat_repo = SqliteChatRepo::new(db_pool);
+        let chat_id = Uuid::new_v4();
+
+        let result = chat_repo
+            .create_chat_message(CreateChatMessage {
+                created_at: None,
+                id: Uuid::new_v4(),
+                chat_id,
+                role: "user".into(),
+                content: "hello".into(),
+                status: ChatMessageStatus::Completed,
+                kind: ChatMessageKind::Text,
+                tool_calls: None,
+                tool_call_id: None,
+                tool_name: None,
+            })
+            .await;
+        assert!(matches!(result, Err(AppError::ChatNotFound(id)) if id == chat_id));
+
+        let tag = chat_repo
+            .upsert_tag(CreateTag {
+                id: Uuid::new_v4(),
+                name: "work".into(),
+            })
+            .await
+            .unwrap();
+        let result = chat_repo.tag_chat(chat_id, tag.id).await;
+        assert!(matches!(result, Err(AppError::ChatNotFound(id)) if id == chat_id));
+    }
+}
@@ -0,0 +1,38 @@
+use jsonschema::Validator;
+use serde_json::Value;
+
+use crate::common::{entity::chat::ChatSchemaViolation, error::AppError};
+
+pub fn compile_response_schema(schema: &Value) -> Result<Validator, AppError> {
+    jsonschema::validator_for(schema).map_err(|e| AppError::InvalidResponseSchema(e.to_string()))
+}
+
+/// Checks a structured response against the requested schema. A response that is not
+/// JSON at all is reported as a single violation at the root.
+pub fn validate_response(validator: &Validator, text: &str) -> Vec<ChatSchemaViolation> {
+    let instance = match serde_json::from_str::<Value>(strip_code_fence(text)) {
+        Ok(instance) => instance,
+        Err(e) => {
+            return vec![ChatSchemaViolation {
+                path: String::new(),
+                message: format!("response is not valid JSON: {}", e),
+            }]
+        }
+    };
+    validator
+        .iter_errors(&instance)
+        .map(|e| ChatSchemaViolation {
+            path: e.instance_path().to_string(),
+            message: e.to_string(),
+        })
+        .collect()
+}
+
+/// Models occasionally wrap JSON in a Markdown code block even in JSON mode.
+fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    match text.strip_prefix("```").and_then(|a| a.strip_suffix("```")) {
+        Some(inner) => inner.trim_start_matches("json").trim(),
+        None => text,
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use uuid::Uuid;
+
+use crate::{
+    chat::{
+        embedding::ChatMessageSearchResult,
+        repo::{ChatFilter, ChatRepo},
+    },
+    common::{
+        entity::chat::{ChatMessageKind, ChatMessageRow},
+        error::AppError,
+    },
+};
+
+struct IndexedChatMessage {
+    chat_id: Uuid,
+    role: String,
+    content: String,
+    normalized: String,
+    created_at: i64,
+}
+
+/// A write to the search index, described so it can be held back until the data it
+/// reflects is committed.
+pub enum ChatSearchIndexChange {
+    PutChatTitle(Uuid, String),
+    PutMessage(ChatMessageRow),
+    PutMessageContent(Uuid, String),
+    RemoveMessage(Uuid),
+    RemoveChat(Uuid),
+}
+
+/// Where `EncryptedChatRepo` sends index writes: straight into the index, or into a
+/// unit of work's buffer.
+pub trait ChatSearchIndexWriter: Send + Sync {
+    fn write(&self, change: ChatSearchIndexChange);
+}
+
+/// Keyword search over decrypted chat history. Encrypted content can't be matched in
+/// SQL, so the index is built from the repo at startup and kept current by
+/// `EncryptedChatRepo` as messages and titles are written.
+pub struct ChatSearchIndex {
+    messages: RwLock<HashMap<Uuid, IndexedChatMessage>>,
+    titles: RwLock<HashMap<Uuid, String>>,
+}
+
+impl ChatSearchIndex {
+    pub fn new() -> Self {
+        Self {
+            messages: RwLock::new(HashMap::new()),
+            titles: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Entries written while rebuilding are newer than what was read, so they win.
+    pub async fn rebuild(&self, chat_repo: &dyn ChatRepo) -> Result<usize, AppError> {
+        let mut messages = HashMap::new();
+        let mut titles = HashMap::new();
+        for chat in chat_repo.get_chats(ChatFilter::default()).await? {
+            for message in chat_repo.get_chat_messages(chat.id).await? {
+                if let Some(indexed) = index_message(&message) {
+                    messages.insert(message.id, indexed);
+                }
+            }
+            titles.insert(chat.id, chat.title);
+        }
+        let count = messages.len();
+        if let Ok(mut current) = self.messages.write() {
+            for (id, indexed) in messages {
+                current.entry(id).or_insert(indexed);
+            }
+        }
+        if let Ok(mut current) = self.titles.write() {
+            for (id, title) in titles {
+                current.entry(id).or_insert(title);
+            }
+        }
+        Ok(count)
+    }
+
+    pub fn put_chat_title(&self, chat_id: Uuid, title: &str) {
+        if let Ok(mut titles) = self.titles.write() {
+            titles.insert(chat_id, title.to_string());
+        }
+    }
+
+    pub fn put_message(&self, message: &ChatMessageRow) {
+        if let (Some(indexed), Ok(mut messages)) = (index_message(message), self.messages.write()) {
+            messages.insert(message.id, indexed);
+        }
+    }
+
+    pub fn put_message_content(&self, id: Uuid, content: &str) {
+        if let Ok(mut messages) = self.messages.write() {
+            if let Some(indexed) = messages.get_mut(&id) {
+                indexed.content = content.to_string();
+                indexed.normalized = content.to_lowercase();
+            }
+        }
+    }
+
+    pub fn remove_message(&self, id: Uuid) {
+        if let Ok(mut messages) = self.messages.write() {
+            messages.remove(&id);
+        }
+    }
+
+    pub fn apply(&self, change: ChatSearchIndexChange) {
+        match change {
+            ChatSearchIndexChange::PutChatTitle(chat_id, title) => {
+                self.put_chat_title(chat_id, &title)
+            }
+            ChatSearchIndexChange::PutMessage(message) => self.put_message(&message),
+            ChatSearchIndexChange::PutMessageContent(id, content) => {
+                self.put_message_content(id, &content)
+            }
+            ChatSearchIndexChange::RemoveMessage(id) => self.remove_message(id),
+            ChatSearchIndexChange::RemoveChat(chat_id) => self.remove_chat(chat_id),
+        }
+    }
+
+    pub fn remove_chat(&self, chat_id: Uuid) {
+        if let Ok(mut messages) = self.messages.write() {
+            messages.retain(|_, indexed| indexed.chat_id != chat_id);
+        }
+        if let Ok(mut titles) = self.titles.write() {
+            titles.remove(&chat_id);
+        }
+    }
+
+    /// Matches messages that contain every word of `query`, ignoring case, and ranks
+    /// them by how often the words occur, newest first on ties.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<ChatMessageSearchResult> {
+        let terms = query
+            .split_whitespace()
+            .map(|a| a.to_lowercase())
+            .collect::<Vec<_>>();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let (Ok(messages), Ok(titles)) = (self.messages.read(), self.titles.read()) else {
+            return Vec::new();
+        };
+        let mut results = messages
+            .iter()
+            .filter_map(|(id, indexed)| {
+                let mut score = 0;
+                for term in &terms {
+                    let count = indexed.normalized.matches(term.as_str()).count();
+                    if count == 0 {
+                        return None;
+                    }
+                    score += count;
+                }
+                Some(ChatMessageSearchResult {
+                    message_id: *id,
+                    chat_id: indexed.chat_id,
+                    chat_title: titles.get(&indexed.chat_id).cloned().unwrap_or_default(),
+                    role: indexed.role.clone(),
+                    content: indexed.content.clone(),
+                    created_at: indexed.created_at,
+                    score: score as f32,
+                })
+            })
+            .collect::<Vec<_>>();
+        results.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b.created_at.cmp(&a.created_at))
+        });
+        results.truncate(limit);
+        results
+    }
+}
+
+impl ChatSearchIndexWriter for ChatSearchIndex {
+    fn write(&self, change: ChatSearchIndexChange) {
+        self.apply(change);
+    }
+}
+
+/// Holds the index writes of a unit of work so a rollback leaves the index as it was.
+pub struct BufferedChatSearchIndex {
+    index: Arc<ChatSearchIndex>,
+    changes: Mutex<Vec<ChatSearchIndexChange>>,
+}
+
+impl BufferedChatSearchIndex {
+    pub fn new(index: Arc<ChatSearchIndex>) -> Self {
+        Self {
+            index,
+            changes: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Applies the held writes in the order they were made. Call once committed.
+    pub fn flush(&self) {
+        if let Ok(mut changes) = self.changes.lock() {
+            for change in changes.drain(..) {
+                self.index.apply(change);
+            }
+        }
+    }
+}
+
+impl ChatSearchIndexWriter for BufferedChatSearchIndex {
+    fn write(&self, change: ChatSearchIndexChange) {
+        if let Ok(mut changes) = self.changes.lock() {
+            changes.push(change);
+        }
+    }
+}
+
+fn index_message(message: &ChatMessageRow) -> Option<IndexedChatMessage> {
+    if message.kind != ChatMessageKind::Text || !matches!(message.role.as_str(), "user" | "model") {
+        return None;
+    }
+    Some(IndexedChatMessage {
+        chat_id: message.chat_id,
+        role: message.role.clone(),
+        content: message.content.clone(),
+        normalized: message.content.to_lowercase(),
+        created_at: message.created_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::entity::chat::ChatMessageStatus;
+
+    #[test]
+    fn holds_buffered_writes_until_flushed() {
+        let index = Arc::new(ChatSearchIndex::new());
+        let chat_id = Uuid::new_v4();
+        let buffer = BufferedChatSearchIndex::new(index.clone());
+        buffer.write(ChatSearchIndexChange::PutChatTitle(chat_id, "Plans".into()));
+        buffer.write(ChatSearchIndexChange::PutMessage(ChatMessageRow {
+            created_at: 1,
+            updated_at: 1,
+            id: Uuid::new_v4(),
+            chat_id,
+            role: "user".into(),
+            content: "book the ferry".into(),
+            status: ChatMessageStatus::Completed,
+            starred: false,
+            kind: ChatMessageKind::Text,
+            tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
+            schema_errors: None,
+            citations: None,
+            content_revision: 0,
+        }));
+        assert!(index.search("ferry", 10).is_empty());
+
+        buffer.flush();
+        let results = index.search("ferry", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chat_title, "Plans");
+
+        let dropped = BufferedChatSearchIndex::new(index.clone());
+        dropped.write(ChatSearchIndexChange::RemoveChat(chat_id));
+        drop(dropped);
+        assert_eq!(index.search("ferry", 10).len(), 1);
+    }
+}
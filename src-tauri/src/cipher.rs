@@ -4,8 +4,9 @@ use aes_gcm::{
 };
 use base64::{prelude::BASE64_STANDARD, Engine};
 use keyring::Entry;
+use std::sync::OnceLock;
 
-use crate::common::error::AppError;
+use crate::{common::error::AppError, profile::KEYRING_SERVICE};
 
 pub trait Cipher: Send + Sync {
     fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, AppError>;
@@ -14,25 +15,43 @@ pub trait Cipher: Send + Sync {
     fn decrypt_base64_str(&self, ciphertext_base64: &str) -> Result<String, AppError>;
 }
 
-pub struct KeyringAesGcmCipher {}
+/// Reads the key from the profile's keyring entry on first use and keeps it for the
+/// life of the app, since chat content is decrypted far more often than API keys.
+pub struct KeyringAesGcmCipher {
+    keyring_user: String,
+    cipher: OnceLock<Aes256Gcm>,
+}
 
 impl KeyringAesGcmCipher {
-    pub fn new() -> Self {
-        KeyringAesGcmCipher {}
+    pub fn new(keyring_user: String) -> Self {
+        KeyringAesGcmCipher {
+            keyring_user,
+            cipher: OnceLock::new(),
+        }
+    }
+
+    fn cipher(&self) -> Result<&Aes256Gcm, AppError> {
+        if let Some(cipher) = self.cipher.get() {
+            return Ok(cipher);
+        }
+        let cipher = create_aes256gcm(&self.keyring_user)?;
+        Ok(self.cipher.get_or_init(|| cipher))
     }
 }
 
 impl Cipher for KeyringAesGcmCipher {
     fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
-        let cipher = create_aes256gcm()?;
+        let cipher = self.cipher()?;
         let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
         let ciphertext = cipher.encrypt(&nonce, plaintext).map_err(AppError::from)?;
         Ok([nonce.as_slice(), ciphertext.as_slice()].concat())
     }
 
     fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, AppError> {
-        println!("decrypt ciphertext {:?}", ciphertext);
-        let cipher = create_aes256gcm()?;
+        if ciphertext.len() < 12 {
+            return Err(AppError::from(aes_gcm::Error));
+        }
+        let cipher = self.cipher()?;
         let nonce = GenericArray::from_slice(&ciphertext[..12]);
         let ciphertext = &ciphertext[12..];
         cipher.decrypt(nonce, ciphertext).map_err(AppError::from)
@@ -53,9 +72,13 @@ impl Cipher for KeyringAesGcmCipher {
     }
 }
 
-fn create_aes256gcm() -> Result<Aes256Gcm, AppError> {
-    let entry = Entry::new("askkit", "local").map_err(AppError::from)?;
-    let key = entry
+fn create_aes256gcm(keyring_user: &str) -> Result<Aes256Gcm, AppError> {
+    Ok(Aes256Gcm::new(&keyring_key(keyring_user)?))
+}
+
+fn keyring_key(keyring_user: &str) -> Result<Key<Aes256Gcm>, AppError> {
+    let entry = Entry::new(KEYRING_SERVICE, keyring_user).map_err(AppError::from)?;
+    entry
         .get_secret()
         .map(|a| Key::<Aes256Gcm>::from_slice(a.as_slice()).to_owned())
         .or_else(|e| match e {
@@ -65,6 +88,25 @@ fn create_aes256gcm() -> Result<Aes256Gcm, AppError> {
                 Ok(key)
             }
             e => Err(AppError::from(e)),
-        })?;
-    Ok(Aes256Gcm::new(&key))
+        })
+}
+
+/// Returns the key of a keyring entry as base64, creating it if needed, so it can be
+/// carried to another device.
+pub fn export_keyring_key(keyring_user: &str) -> Result<String, AppError> {
+    Ok(BASE64_STANDARD.encode(keyring_key(keyring_user)?))
+}
+
+/// Replaces the key of a keyring entry with one exported from another device.
+pub fn import_keyring_key(keyring_user: &str, key_base64: &str) -> Result<(), AppError> {
+    let key = BASE64_STANDARD
+        .decode(key_base64.trim())
+        .map_err(AppError::from)?;
+    if key.len() != 32 {
+        return Err(AppError::from(aes_gcm::Error));
+    }
+    Entry::new(KEYRING_SERVICE, keyring_user)
+        .map_err(AppError::from)?
+        .set_secret(&key)
+        .map_err(AppError::from)
 }
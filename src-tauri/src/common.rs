@@ -2,3 +2,4 @@ pub mod error;
 pub mod http;
 pub mod entity;
 pub mod unit_of_work;
+pub mod vector;
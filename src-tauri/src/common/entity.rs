@@ -1,3 +1,9 @@
 pub mod agent;
 pub mod chat;
-pub mod user;
\ No newline at end of file
+pub mod knowledge;
+pub mod mcp;
+pub mod project;
+pub mod retention;
+pub mod sync;
+pub mod tool;
+pub mod user;
@@ -1,18 +1,20 @@
 use serde::Serialize;
 use uuid::Uuid;
 
-use crate::agent::AgentProvider;
+use crate::agent::{AgentCapability, AgentProvider};
 
-#[derive(sqlx::FromRow, Serialize)]
+#[derive(sqlx::FromRow, Serialize, Clone)]
 pub struct AgentRow {
     pub created_at: i64,
     pub updated_at: i64,
     pub id: Uuid,
     pub provider: AgentProvider,
     pub model: String,
+    pub vision: bool,
+    pub capability: AgentCapability,
 }
 
-#[derive(sqlx::FromRow, Serialize, Debug)]
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
 pub struct AgentConfigRow {
     pub created_at: i64,
     pub updated_at: i64,
@@ -20,7 +22,7 @@ pub struct AgentConfigRow {
     pub api_key: Option<String>,
 }
 
-#[derive(sqlx::FromRow)]
+#[derive(sqlx::FromRow, Clone)]
 pub struct AgentProviderRow {
     pub created_at: i64,
     pub updated_at: i64,
@@ -1,25 +1,42 @@
-use serde::Serialize;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use sqlx::types::Json;
 use uuid::Uuid;
 
-#[derive(Serialize, Clone, sqlx::FromRow)]
+#[derive(Serialize, Clone, Debug, sqlx::FromRow)]
 pub struct ChatRow {
     pub created_at: i64,
+    pub updated_at: i64,
     pub id: Uuid,
     pub title: String,
+    pub pinned: bool,
+    pub starred: bool,
+    pub project_id: Option<Uuid>,
+    pub archived_at: Option<i64>,
 }
 
 #[derive(sqlx::FromRow, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ChatMessageRow {
     pub created_at: i64,
+    pub updated_at: i64,
     pub id: Uuid,
     pub chat_id: Uuid,
     pub role: String,
     pub content: String,
     pub status: ChatMessageStatus,
+    pub starred: bool,
+    pub kind: ChatMessageKind,
+    pub tool_calls: Option<Json<Vec<ChatToolCall>>>,
+    pub tool_call_id: Option<String>,
+    pub tool_name: Option<String>,
+    pub schema_errors: Option<Json<Vec<ChatSchemaViolation>>>,
+    pub citations: Option<Json<Vec<ChatCitation>>>,
+    pub content_revision: i64,
 }
 
-#[derive(Debug, Serialize, Clone, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
 #[serde(rename_all = "snake_case")]
 #[sqlx(type_name = "text", rename_all = "snake_case")]
 pub enum ChatMessageStatus {
@@ -27,3 +44,102 @@ pub enum ChatMessageStatus {
     Completed,
     Failed,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum ChatMessageKind {
+    #[default]
+    Text,
+    ToolCall,
+    ToolResult,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+}
+
+/// A place where a structured response does not match the requested JSON Schema.
+/// `path` is a JSON pointer into the response.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatSchemaViolation {
+    pub path: String,
+    pub message: String,
+}
+
+/// A knowledge chunk that was put into the prompt for a response. `index` is the
+/// number the model was told to cite it by.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatCitation {
+    pub index: usize,
+    pub chunk_id: Uuid,
+    pub path: String,
+    pub ordinal: i64,
+    pub score: f32,
+}
+
+/// A completed message whose embedding is missing or was computed from an older
+/// revision of its content.
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct ChatMessageEmbeddingCandidateRow {
+    pub id: Uuid,
+    pub content: String,
+    pub content_revision: i64,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct ChatMessageEmbeddingRow {
+    pub message_id: Uuid,
+    pub chat_id: Uuid,
+    pub chat_title: String,
+    pub role: String,
+    pub content: String,
+    pub created_at: i64,
+    pub embedding: Vec<u8>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TagRow {
+    pub created_at: i64,
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageAttachmentRow {
+    pub created_at: i64,
+    pub id: Uuid,
+    pub message_id: Uuid,
+    pub kind: ChatMessageAttachmentKind,
+    pub name: Option<String>,
+    pub mime_type: String,
+    #[serde(serialize_with = "serialize_base64")]
+    pub data: Option<Vec<u8>>,
+    pub path: Option<String>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum ChatMessageAttachmentKind {
+    Image,
+    Document,
+    Audio,
+}
+
+fn serialize_base64<S>(data: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match data {
+        Some(data) => serializer.serialize_some(&BASE64_STANDARD.encode(data)),
+        None => serializer.serialize_none(),
+    }
+}
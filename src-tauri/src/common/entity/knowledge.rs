@@ -0,0 +1,37 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeFolderRow {
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub id: Uuid,
+    pub path: String,
+    pub agent_id: Uuid,
+    pub embedding_model: String,
+    pub indexed_at: Option<i64>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeFileRow {
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub id: Uuid,
+    pub folder_id: Uuid,
+    pub path: String,
+    pub size: i64,
+    pub modified_at: i64,
+}
+
+/// A chunk joined with the file it was cut from, as needed for retrieval.
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct KnowledgeChunkRow {
+    pub id: Uuid,
+    pub folder_id: Uuid,
+    pub path: String,
+    pub ordinal: i64,
+    pub content: String,
+    pub embedding: Vec<u8>,
+}
@@ -0,0 +1,18 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerRow {
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub id: Uuid,
+    pub name: String,
+    pub command: String,
+    pub args: Json<Vec<String>>,
+    pub env: Json<HashMap<String, String>>,
+    pub enabled: bool,
+}
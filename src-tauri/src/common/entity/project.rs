@@ -0,0 +1,23 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectRow {
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub id: Uuid,
+    pub name: String,
+    pub system_prompt: String,
+    pub default_agent_id: Option<Uuid>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectDocumentRow {
+    pub created_at: i64,
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub name: String,
+    pub content: String,
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum RetentionAction {
+    Archive,
+    Delete,
+}
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicyRow {
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub enabled: bool,
+    pub max_age_days: Option<i64>,
+    pub action: RetentionAction,
+    pub keep_pinned: bool,
+    pub max_database_bytes: Option<i64>,
+    pub last_run_at: Option<i64>,
+}
+
+/// A chat as retention sees it. `last_active_at` is its newest message, or its
+/// creation for empty chats, and `size` roughly what deleting it frees in bytes.
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct ChatRetentionRow {
+    pub id: Uuid,
+    pub pinned: bool,
+    pub archived_at: Option<i64>,
+    pub last_active_at: i64,
+    pub size: i64,
+}
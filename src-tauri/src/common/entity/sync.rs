@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum SyncEntity {
+    Chat,
+    ChatMessage,
+    AgentConfig,
+}
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStateRow {
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub device_id: Uuid,
+    pub folder: Option<String>,
+    pub last_synced_at: Option<i64>,
+}
+
+/// The version of an entity this device last wrote or merged. A missing
+/// `fingerprint` marks a deletion, so older changes can't bring the entity back.
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct SyncEntityRow {
+    pub entity: SyncEntity,
+    pub entity_id: Uuid,
+    pub fingerprint: Option<String>,
+    pub changed_at: i64,
+    pub device_id: Uuid,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct SyncCursorRow {
+    pub device_id: Uuid,
+    pub line: i64,
+}
@@ -0,0 +1,10 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolDirectoryRow {
+    pub created_at: i64,
+    pub id: Uuid,
+    pub path: String,
+}
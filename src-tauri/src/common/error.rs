@@ -39,6 +39,58 @@ pub enum AppError {
     AgentRequired,
     #[error("Agent text gen params required error")]
     AgentTextGenParamsRequired,
+    #[error("Chat not found error: {0}")]
+    ChatNotFound(uuid::Uuid),
+    #[error("Chat message not found error: {0}")]
+    ChatMessageNotFound(uuid::Uuid),
+    #[error("Chat message cannot be read aloud: {0}")]
+    ChatMessageNotSpeakable(uuid::Uuid),
+    #[error("Chat import error: {0}")]
+    ChatImport(String),
+    #[error("Agent does not support image input: {0}")]
+    AgentVisionUnsupported(String),
+    #[error("Agent does not support this capability: {0}")]
+    AgentCapabilityUnsupported(String),
+    #[error("Agent config required error")]
+    AgentConfigRequired,
+    #[error("Tool not found: {0}")]
+    ToolNotFound(String),
+    #[error("MCP error: {0}")]
+    Mcp(String),
+    #[error("Tool approval not found: {0}")]
+    ToolApprovalNotFound(String),
+    #[error("Invalid tool arguments: {0}")]
+    ToolInvalidArguments(String),
+    #[error("Tool path not allowed: {0}")]
+    ToolPathNotAllowed(String),
+    #[error("Invalid response schema: {0}")]
+    InvalidResponseSchema(String),
+    #[error("Invalid backup: {0}")]
+    InvalidBackup(String),
+    #[error("Invalid retention policy: {0}")]
+    InvalidRetentionPolicy(String),
+    #[error("Profile not found: {0}")]
+    ProfileNotFound(uuid::Uuid),
+    #[error("Invalid profile: {0}")]
+    InvalidProfile(String),
+    #[error("Chat responses are still in progress")]
+    ChatResponsesInProgress,
+    #[error("Sync folder is not configured")]
+    SyncNotConfigured,
+    #[error("Invalid sync folder: {0}")]
+    InvalidSyncFolder(String),
+    #[error("Embedding count mismatch: expected {0}, got {1}")]
+    EmbeddingCountMismatch(usize, usize),
+    #[error("Knowledge folder not found: {0}")]
+    KnowledgeFolderNotFound(uuid::Uuid),
+    #[error("Invalid knowledge folder: {0}")]
+    InvalidKnowledgeFolder(String),
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+    #[error("Unsupported attachment: {0}")]
+    UnsupportedAttachment(String),
+    #[error("Document extraction error: {0}")]
+    DocumentExtraction(String),
     #[error("Mutex try lock error: {0}")]
     TryLock(tokio::sync::TryLockError),
     #[error("Transaction is still in use")]
@@ -229,10 +281,163 @@ impl Serialize for AppError {
                 state = serializer.serialize_struct("AppError", 1)?;
                 state.serialize_field("kind", "AgentRequiredError")?;
             }
+            AppError::AgentConfigRequired => {
+                state = serializer.serialize_struct("AppError", 1)?;
+                state.serialize_field("kind", "AgentConfigRequiredError")?;
+            }
             AppError::AgentTextGenParamsRequired => {
                 state = serializer.serialize_struct("AppError", 1)?;
                 state.serialize_field("kind", "AgentTextGenParamsRequiredError")?;
             }
+            AppError::ChatNotFound(id) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ChatNotFoundError")?;
+                state.serialize_field("message", &format!("chat not found: {}", id))?;
+            }
+            AppError::ChatMessageNotFound(id) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ChatMessageNotFoundError")?;
+                state.serialize_field("message", &format!("chat message not found: {}", id))?;
+            }
+            AppError::ChatMessageNotSpeakable(id) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ChatMessageNotSpeakableError")?;
+                state.serialize_field(
+                    "message",
+                    &format!(
+                        "only completed model messages with text can be read aloud: {}",
+                        id
+                    ),
+                )?;
+            }
+            AppError::ChatImport(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ChatImportError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::AgentCapabilityUnsupported(model) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "AgentCapabilityUnsupportedError")?;
+                state.serialize_field(
+                    "message",
+                    &format!("agent does not support this capability: {}", model),
+                )?;
+            }
+            AppError::ToolNotFound(name) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ToolNotFoundError")?;
+                state.serialize_field("message", &format!("tool not found: {}", name))?;
+            }
+            AppError::Mcp(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "McpError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::ToolApprovalNotFound(tool_call_id) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ToolApprovalNotFoundError")?;
+                state.serialize_field(
+                    "message",
+                    &format!("no pending approval for tool call: {}", tool_call_id),
+                )?;
+            }
+            AppError::InvalidResponseSchema(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "InvalidResponseSchemaError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::InvalidBackup(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "InvalidBackupError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::InvalidRetentionPolicy(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "InvalidRetentionPolicyError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::ProfileNotFound(id) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ProfileNotFoundError")?;
+                state.serialize_field("message", &format!("profile not found: {}", id))?;
+            }
+            AppError::InvalidProfile(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "InvalidProfileError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::ChatResponsesInProgress => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ChatResponsesInProgressError")?;
+                state.serialize_field("message", "chat responses are still in progress")?;
+            }
+            AppError::SyncNotConfigured => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "SyncNotConfiguredError")?;
+                state.serialize_field("message", "sync folder is not configured")?;
+            }
+            AppError::InvalidSyncFolder(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "InvalidSyncFolderError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::EmbeddingCountMismatch(expected, actual) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "EmbeddingCountMismatchError")?;
+                state.serialize_field(
+                    "message",
+                    &format!("expected {} embeddings, got {}", expected, actual),
+                )?;
+            }
+            AppError::KnowledgeFolderNotFound(id) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "KnowledgeFolderNotFoundError")?;
+                state.serialize_field("message", &format!("knowledge folder not found: {}", id))?;
+            }
+            AppError::InvalidKnowledgeFolder(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "InvalidKnowledgeFolderError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::InvalidTag(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "InvalidTagError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::ToolInvalidArguments(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ToolInvalidArgumentsError")?;
+                state.serialize_field("message", message)?;
+            }
+            AppError::ToolPathNotAllowed(path) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "ToolPathNotAllowedError")?;
+                state.serialize_field(
+                    "message",
+                    &format!("path is outside the allowed directories: {}", path),
+                )?;
+            }
+            AppError::AgentVisionUnsupported(model) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "AgentVisionUnsupportedError")?;
+                state.serialize_field(
+                    "message",
+                    &format!("agent does not support image input: {}", model),
+                )?;
+            }
+            AppError::UnsupportedAttachment(attachment) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "UnsupportedAttachmentError")?;
+                state.serialize_field(
+                    "message",
+                    &format!("unsupported attachment: {}", attachment),
+                )?;
+            }
+            AppError::DocumentExtraction(message) => {
+                state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", "DocumentExtractionError")?;
+                state.serialize_field("message", message)?;
+            }
             AppError::TryLock(error) => {
                 state = serializer.serialize_struct("AppError", 2)?;
                 state.serialize_field("kind", "TryLockError")?;
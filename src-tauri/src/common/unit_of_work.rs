@@ -5,14 +5,23 @@ use tokio::sync::Mutex;
 
 use crate::{
     agent::repo::{sqlite::TransactionalSqliteAgentRepo, AgentRepo},
-    chat::repo::{sqlite::TransactionalSqliteChatRepo, ChatRepo},
+    chat::{
+        encryption::ChatEncryption,
+        repo::{encrypted::EncryptedChatRepo, sqlite::TransactionalSqliteChatRepo, ChatRepo},
+        search::{BufferedChatSearchIndex, ChatSearchIndex},
+    },
     common::error::AppError,
+    project::repo::{sqlite::TransactionalSqliteProjectRepo, ProjectRepo},
 };
 
+#[cfg(test)]
+pub mod memory;
+
 #[async_trait]
 pub trait UnitOfWork: Send + Sync {
     fn chat_repo(&self) -> Box<dyn ChatRepo>;
     fn agent_repo(&self) -> Box<dyn AgentRepo>;
+    fn project_repo(&self) -> Box<dyn ProjectRepo>;
     async fn commit(self: Box<Self>) -> Result<(), AppError>;
 }
 
@@ -21,25 +30,45 @@ pub trait UnitOfWorkFactory: Send + Sync {
     async fn create(&self) -> Result<Box<dyn UnitOfWork>, AppError>;
 }
 
+/// Search index writes are held until the transaction commits, so a unit of work that
+/// fails or is dropped leaves the index matching the database.
 pub struct SqliteUnitOfWork {
     tx: Arc<Mutex<SqliteTransaction<'static>>>,
+    chat_encryption: Arc<ChatEncryption>,
+    chat_search_index: Arc<BufferedChatSearchIndex>,
 }
 
 pub struct SqliteUnitOfWorkFactory {
     db_pool: Arc<Pool<Sqlite>>,
+    chat_encryption: Arc<ChatEncryption>,
+    chat_search_index: Arc<ChatSearchIndex>,
 }
 
 impl SqliteUnitOfWork {
-    pub fn new(tx: SqliteTransaction<'static>) -> Self {
+    pub fn new(
+        tx: SqliteTransaction<'static>,
+        chat_encryption: Arc<ChatEncryption>,
+        chat_search_index: Arc<ChatSearchIndex>,
+    ) -> Self {
         Self {
             tx: Arc::new(Mutex::new(tx)),
+            chat_encryption,
+            chat_search_index: Arc::new(BufferedChatSearchIndex::new(chat_search_index)),
         }
     }
 }
 
 impl SqliteUnitOfWorkFactory {
-    pub fn new(db_pool: Arc<Pool<Sqlite>>) -> Self {
-        Self { db_pool }
+    pub fn new(
+        db_pool: Arc<Pool<Sqlite>>,
+        chat_encryption: Arc<ChatEncryption>,
+        chat_search_index: Arc<ChatSearchIndex>,
+    ) -> Self {
+        Self {
+            db_pool,
+            chat_encryption,
+            chat_search_index,
+        }
     }
 }
 
@@ -47,20 +76,32 @@ impl SqliteUnitOfWorkFactory {
 impl UnitOfWorkFactory for SqliteUnitOfWorkFactory {
     async fn create(&self) -> Result<Box<dyn UnitOfWork>, AppError> {
         let tx = self.db_pool.begin().await.map_err(AppError::from)?;
-        Ok(Box::new(SqliteUnitOfWork::new(tx)))
+        Ok(Box::new(SqliteUnitOfWork::new(
+            tx,
+            self.chat_encryption.clone(),
+            self.chat_search_index.clone(),
+        )))
     }
 }
 
 #[async_trait]
 impl UnitOfWork for SqliteUnitOfWork {
     fn chat_repo(&self) -> Box<dyn ChatRepo> {
-        Box::new(TransactionalSqliteChatRepo::new(self.tx.clone()))
+        Box::new(EncryptedChatRepo::new(
+            Box::new(TransactionalSqliteChatRepo::new(self.tx.clone())),
+            self.chat_encryption.clone(),
+            self.chat_search_index.clone(),
+        ))
     }
 
     fn agent_repo(&self) -> Box<dyn AgentRepo> {
         Box::new(TransactionalSqliteAgentRepo::new(self.tx.clone()))
     }
 
+    fn project_repo(&self) -> Box<dyn ProjectRepo> {
+        Box::new(TransactionalSqliteProjectRepo::new(self.tx.clone()))
+    }
+
     async fn commit(self: Box<Self>) -> Result<(), AppError> {
         let mutex = match Arc::try_unwrap(self.tx) {
             Ok(mutex) => mutex,
@@ -69,6 +110,8 @@ impl UnitOfWork for SqliteUnitOfWork {
             }
         };
 
-        mutex.into_inner().commit().await.map_err(AppError::from)
+        mutex.into_inner().commit().await.map_err(AppError::from)?;
+        self.chat_search_index.flush();
+        Ok(())
     }
 }
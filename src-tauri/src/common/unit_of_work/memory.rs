@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::{
+    agent::repo::{memory::MemoryAgentRepo, AgentRepo},
+    chat::repo::{memory::MemoryChatRepo, ChatRepo},
+    common::{
+        error::AppError,
+        unit_of_work::{UnitOfWork, UnitOfWorkFactory},
+    },
+    database::memory::{MemoryDatabase, MemoryTables},
+    project::repo::{memory::MemoryProjectRepo, ProjectRepo},
+};
+
+/// Holds the database lock from creation until it is committed or dropped, so units
+/// of work run one at a time and nothing else sees their writes before the commit.
+pub struct MemoryUnitOfWork {
+    committed: OwnedMutexGuard<MemoryTables>,
+    tables: Arc<Mutex<MemoryTables>>,
+}
+
+pub struct MemoryUnitOfWorkFactory {
+    database: MemoryDatabase,
+}
+
+impl MemoryUnitOfWorkFactory {
+    pub fn new(database: MemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl UnitOfWorkFactory for MemoryUnitOfWorkFactory {
+    async fn create(&self) -> Result<Box<dyn UnitOfWork>, AppError> {
+        let committed = self.database.clone().lock_owned().await;
+        let tables = Arc::new(Mutex::new(committed.clone()));
+        Ok(Box::new(MemoryUnitOfWork { committed, tables }))
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for MemoryUnitOfWork {
+    fn chat_repo(&self) -> Box<dyn ChatRepo> {
+        Box::new(MemoryChatRepo::new(self.tables.clone()))
+    }
+
+    fn agent_repo(&self) -> Box<dyn AgentRepo> {
+        Box::new(MemoryAgentRepo::new(self.tables.clone()))
+    }
+
+    fn project_repo(&self) -> Box<dyn ProjectRepo> {
+        Box::new(MemoryProjectRepo::new(self.tables.clone()))
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), AppError> {
+        let Self {
+            mut committed,
+            tables,
+        } = *self;
+        let mutex = match Arc::try_unwrap(tables) {
+            Ok(mutex) => mutex,
+            Err(_) => {
+                return Err(AppError::TransactionInUse);
+            }
+        };
+
+        *committed = mutex.into_inner();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{chat::repo::CreateChat, database::memory::create_database};
+
+    fn create_chat(id: Uuid) -> CreateChat {
+        CreateChat {
+            id,
+            title: "test".into(),
+            created_at: None,
+            project_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn commit_publishes_writes() {
+        let database = create_database();
+        let factory = MemoryUnitOfWorkFactory::new(database.clone());
+        let chat_id = Uuid::new_v4();
+
+        let unit_of_work = factory.create().await.unwrap();
+        unit_of_work
+            .chat_repo()
+            .create_chat(create_chat(chat_id))
+            .await
+            .unwrap();
+        assert!(unit_of_work
+            .chat_repo()
+            .get_chat(chat_id)
+            .await
+            .unwrap()
+            .is_some());
+        unit_of_work.commit().await.unwrap();
+
+        let chat_repo = MemoryChatRepo::new(database);
+        assert!(chat_repo.get_chat(chat_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn drop_rolls_back_writes() {
+        let database = create_database();
+        let factory = MemoryUnitOfWorkFactory::new(database.clone());
+        let chat_id = Uuid::new_v4();
+
+        let unit_of_work = factory.create().await.unwrap();
+        unit_of_work
+            .chat_repo()
+            .create_chat(create_chat(chat_id))
+            .await
+            .unwrap();
+        drop(unit_of_work);
+
+        let chat_repo = MemoryChatRepo::new(database);
+        assert!(chat_repo.get_chat(chat_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn commit_fails_while_repo_is_held() {
+        let factory = MemoryUnitOfWorkFactory::new(create_database());
+
+        let unit_of_work = factory.create().await.unwrap();
+        let chat_repo = unit_of_work.chat_repo();
+        let result = unit_of_work.commit().await;
+        assert!(matches!(result, Err(AppError::TransactionInUse)));
+        drop(chat_repo);
+
+        // The failed commit released the lock along with the unit of work.
+        factory.create().await.unwrap().commit().await.unwrap();
+    }
+}
@@ -0,0 +1,23 @@
+/// Embeddings are stored as little-endian `f32` blobs.
+pub fn encode_embedding(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|a| a.to_le_bytes()).collect()
+}
+
+pub fn decode_embedding(data: &[u8]) -> Vec<f32> {
+    data.chunks_exact(4)
+        .map(|a| f32::from_le_bytes([a[0], a[1], a[2], a[3]]))
+        .collect()
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let norm =
+        a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return 0.0;
+    }
+    dot / norm
+}
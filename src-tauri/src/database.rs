@@ -0,0 +1,149 @@
+pub mod backup;
+pub mod cmds;
+#[cfg(test)]
+pub mod memory;
+
+use std::{collections::BTreeMap, path::Path, sync::Mutex, time::Duration};
+
+use serde::Serialize;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
+    Pool, Sqlite,
+};
+
+use crate::common::error::AppError;
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// WAL lets the UI read while a response is being streamed into the database, and the
+/// busy timeout makes concurrent writers wait for each other instead of failing.
+pub fn connect_options(path: &Path) -> SqliteConnectOptions {
+    SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .foreign_keys(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(BUSY_TIMEOUT)
+        .pragma("recursive_triggers", "off")
+}
+
+pub async fn connect(path: &Path) -> Result<Pool<Sqlite>, AppError> {
+    SqlitePoolOptions::new()
+        .connect_with(connect_options(path))
+        .await
+        .map_err(AppError::from)
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseOrphanCleanup {
+    pub table: String,
+    pub parent: String,
+    pub removed: u64,
+}
+
+#[derive(Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseHealthReport {
+    /// Problems reported by `pragma integrity_check`.
+    pub integrity_errors: Vec<String>,
+    /// Rows whose parent was missing, deleted at startup.
+    pub orphans: Vec<DatabaseOrphanCleanup>,
+    /// Checks that could not be run at all.
+    pub errors: Vec<String>,
+}
+
+impl DatabaseHealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.integrity_errors.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// The report of the last health check, kept so the UI can show problems found at
+/// startup.
+pub struct DatabaseHealth {
+    report: Mutex<DatabaseHealthReport>,
+}
+
+impl DatabaseHealth {
+    pub fn new(report: DatabaseHealthReport) -> Self {
+        Self {
+            report: Mutex::new(report),
+        }
+    }
+
+    pub fn report(&self) -> DatabaseHealthReport {
+        self.report.lock().unwrap().clone()
+    }
+
+    pub fn set_report(&self, report: DatabaseHealthReport) {
+        *self.report.lock().unwrap() = report;
+    }
+}
+
+/// Runs an integrity check and deletes rows that violate a foreign key, which older
+/// versions could leave behind while foreign keys were not enforced.
+pub async fn check_health(db_pool: &Pool<Sqlite>) -> DatabaseHealthReport {
+    let mut report = DatabaseHealthReport::default();
+    match sqlx::query_scalar::<_, String>("pragma integrity_check")
+        .fetch_all(db_pool)
+        .await
+    {
+        Ok(rows) => {
+            report.integrity_errors = rows.into_iter().filter(|a| a != "ok").collect();
+        }
+        Err(e) => report.errors.push(format!("integrity check failed: {}", e)),
+    }
+    match remove_orphans(db_pool).await {
+        Ok(orphans) => report.orphans = orphans,
+        Err(e) => report.errors.push(format!("orphan cleanup failed: {}", e)),
+    }
+    for problem in report.integrity_errors.iter().chain(&report.errors) {
+        log::error!("database health: {}", problem);
+    }
+    for orphan in &report.orphans {
+        log::warn!(
+            "removed {} rows from {} without a parent in {}",
+            orphan.removed,
+            orphan.table,
+            orphan.parent
+        );
+    }
+    report
+}
+
+async fn remove_orphans(db_pool: &Pool<Sqlite>) -> Result<Vec<DatabaseOrphanCleanup>, AppError> {
+    let mut tx = db_pool.begin().await.map_err(AppError::from)?;
+    let violations =
+        sqlx::query_as::<_, (String, Option<i64>, String, i64)>("pragma foreign_key_check")
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+
+    let mut removed = BTreeMap::<(String, String), u64>::new();
+    for (table, rowid, parent, _) in violations {
+        let rowid = match rowid {
+            Some(rowid) => rowid,
+            None => continue,
+        };
+        let result = sqlx::query(&format!(
+            "delete from \"{}\" where rowid = ?1",
+            table.replace('"', "\"\"")
+        ))
+        .bind(rowid)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+        *removed.entry((table, parent)).or_default() += result.rows_affected();
+    }
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(removed
+        .into_iter()
+        .map(|((table, parent), removed)| DatabaseOrphanCleanup {
+            table,
+            parent,
+            removed,
+        })
+        .collect())
+}
@@ -0,0 +1,247 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::Local;
+use serde::Serialize;
+use sqlx::{
+    sqlite::SqliteConnectOptions, ConnectOptions, Connection, Pool, Sqlite, SqliteConnection,
+};
+use tauri::AppHandle;
+
+use crate::common::error::AppError;
+
+const BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const AUTOMATIC_BACKUPS_KEPT: usize = 7;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseBackupKind {
+    Manual,
+    Automatic,
+    PreRestore,
+}
+
+impl DatabaseBackupKind {
+    fn prefix(&self) -> &'static str {
+        match self {
+            DatabaseBackupKind::Manual => "askkit-manual-",
+            DatabaseBackupKind::Automatic => "askkit-auto-",
+            DatabaseBackupKind::PreRestore => "askkit-pre-restore-",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            DatabaseBackupKind::Manual,
+            DatabaseBackupKind::Automatic,
+            DatabaseBackupKind::PreRestore,
+        ]
+        .into_iter()
+        .find(|a| name.starts_with(a.prefix()) && name.ends_with(".db"))
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseBackup {
+    pub name: String,
+    pub kind: DatabaseBackupKind,
+    pub size: u64,
+    pub created_at: i64,
+}
+
+/// Copies of the database kept in the `backups` directory next to it.
+pub struct DatabaseBackups {
+    db_pool: Arc<Pool<Sqlite>>,
+    db_path: PathBuf,
+    backups_dir: PathBuf,
+}
+
+impl DatabaseBackups {
+    pub fn new(db_pool: Arc<Pool<Sqlite>>, db_path: PathBuf) -> Self {
+        let backups_dir = db_path
+            .parent()
+            .map(|a| a.join("backups"))
+            .unwrap_or_else(|| PathBuf::from("backups"));
+        Self {
+            db_pool,
+            db_path,
+            backups_dir,
+        }
+    }
+
+    pub async fn get_backups(&self) -> Result<Vec<DatabaseBackup>, AppError> {
+        if !tokio::fs::try_exists(&self.backups_dir).await? {
+            return Ok(Vec::new());
+        }
+        let mut backups = Vec::new();
+        let mut read_dir = tokio::fs::read_dir(&self.backups_dir).await?;
+        while let Some(entry) = read_dir.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let kind = match DatabaseBackupKind::from_name(&name) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            let metadata = entry.metadata().await?;
+            backups.push(DatabaseBackup {
+                name,
+                kind,
+                size: metadata.len(),
+                created_at: metadata.modified()?.duration_since(UNIX_EPOCH)?.as_millis() as i64,
+            });
+        }
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(backups)
+    }
+
+    /// `VACUUM INTO` writes a consistent, compacted copy while the app keeps using the
+    /// database.
+    pub async fn create_backup(
+        &self,
+        kind: DatabaseBackupKind,
+    ) -> Result<DatabaseBackup, AppError> {
+        tokio::fs::create_dir_all(&self.backups_dir).await?;
+        let name = format!(
+            "{}{}.db",
+            kind.prefix(),
+            Local::now().format("%Y%m%d-%H%M%S-%3f")
+        );
+        let path = self.backups_dir.join(&name);
+        sqlx::query("vacuum into ?1")
+            .bind(path.to_string_lossy())
+            .execute(&*self.db_pool)
+            .await
+            .map_err(AppError::from)?;
+        let metadata = tokio::fs::metadata(&path).await?;
+        log::info!("created database backup {}", name);
+        Ok(DatabaseBackup {
+            name,
+            kind,
+            size: metadata.len(),
+            created_at: metadata.modified()?.duration_since(UNIX_EPOCH)?.as_millis() as i64,
+        })
+    }
+
+    /// Checks the backup, saves the current database as a pre-restore backup, then
+    /// closes the pool so nothing writes while the file is swapped, and restarts the
+    /// app on the restored database. Migrations bring older backups up to date.
+    pub async fn restore_backup(&self, app_handle: &AppHandle, name: &str) -> Result<(), AppError> {
+        if DatabaseBackupKind::from_name(name).is_none() || name.contains(['/', '\\']) {
+            return Err(AppError::InvalidBackup(format!("not a backup: {}", name)));
+        }
+        let path = self.backups_dir.join(name);
+        validate_backup(&path).await?;
+        self.create_backup(DatabaseBackupKind::PreRestore).await?;
+
+        let staged_path = self.db_path.with_extension("db-restore");
+        tokio::fs::copy(&path, &staged_path).await?;
+        self.db_pool.close().await;
+        // The pool can't be reopened, so the app restarts even if the swap failed and
+        // comes back up on whichever file is in place.
+        match swap_database(&staged_path, &self.db_path).await {
+            Ok(()) => log::info!("restored database backup {}, restarting", name),
+            Err(e) => log::error!("failed to restore database backup {}: {}", name, e),
+        }
+        app_handle.restart()
+    }
+
+    /// Creates an automatic backup once a day and keeps the newest few.
+    pub fn spawn_schedule(self: &Arc<Self>) {
+        let backups = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                if let Err(e) = backups.run_schedule().await {
+                    log::error!("failed to create automatic database backup: {}", e);
+                }
+                tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn run_schedule(&self) -> Result<(), AppError> {
+        let automatic = self
+            .get_backups()
+            .await?
+            .into_iter()
+            .filter(|a| a.kind == DatabaseBackupKind::Automatic)
+            .collect::<Vec<_>>();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        let due = automatic
+            .first()
+            .is_none_or(|a| now - a.created_at >= BACKUP_INTERVAL.as_millis() as i64);
+        if !due {
+            return Ok(());
+        }
+        self.create_backup(DatabaseBackupKind::Automatic).await?;
+        for backup in automatic.iter().skip(AUTOMATIC_BACKUPS_KEPT - 1) {
+            tokio::fs::remove_file(self.backups_dir.join(&backup.name)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Closing the last connection checkpoints the WAL, so its leftovers can go before the
+/// restored file takes the database's place.
+async fn swap_database(staged_path: &Path, db_path: &Path) -> Result<(), AppError> {
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = db_path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        match tokio::fs::remove_file(&sidecar).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    tokio::fs::rename(staged_path, db_path).await?;
+    Ok(())
+}
+
+/// A restorable backup passes an integrity check and was migrated by this version of
+/// the app or an older one.
+async fn validate_backup(path: &Path) -> Result<(), AppError> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(|e| AppError::InvalidBackup(e.to_string()))?;
+    let result = check_backup(&mut conn).await;
+    let _ = conn.close().await;
+    result
+}
+
+async fn check_backup(conn: &mut SqliteConnection) -> Result<(), AppError> {
+    let integrity = sqlx::query_scalar::<_, String>("pragma integrity_check")
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| AppError::InvalidBackup(e.to_string()))?;
+    if integrity != ["ok"] {
+        return Err(AppError::InvalidBackup(format!(
+            "integrity check failed: {}",
+            integrity.join("; ")
+        )));
+    }
+    let version = sqlx::query_scalar::<_, Option<i64>>(
+        "select max(version) from _sqlx_migrations where success = 1",
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .ok()
+    .flatten()
+    .ok_or_else(|| AppError::InvalidBackup("not an askkit database".into()))?;
+    let latest = sqlx::migrate!("./migrations")
+        .iter()
+        .map(|a| a.version)
+        .max()
+        .unwrap_or_default();
+    if version > latest {
+        return Err(AppError::InvalidBackup(format!(
+            "backup was made by a newer version of the app (migration {})",
+            version
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use sqlx::{Pool, Sqlite};
+use tauri::{AppHandle, State};
+
+use crate::{
+    common::error::AppError,
+    database::{
+        self,
+        backup::{DatabaseBackup, DatabaseBackupKind, DatabaseBackups},
+        DatabaseHealth, DatabaseHealthReport,
+    },
+};
+
+#[tauri::command]
+pub async fn get_database_health(
+    database_health: State<'_, Arc<DatabaseHealth>>,
+) -> Result<DatabaseHealthReport, AppError> {
+    Ok(database_health.report())
+}
+
+#[tauri::command]
+pub async fn check_database_health(
+    db_pool: State<'_, Arc<Pool<Sqlite>>>,
+    database_health: State<'_, Arc<DatabaseHealth>>,
+) -> Result<DatabaseHealthReport, AppError> {
+    let report = database::check_health(&db_pool).await;
+    database_health.set_report(report.clone());
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_backups(
+    database_backups: State<'_, Arc<DatabaseBackups>>,
+) -> Result<Vec<DatabaseBackup>, AppError> {
+    database_backups.get_backups().await
+}
+
+#[tauri::command]
+pub async fn create_backup(
+    database_backups: State<'_, Arc<DatabaseBackups>>,
+) -> Result<DatabaseBackup, AppError> {
+    database_backups
+        .create_backup(DatabaseBackupKind::Manual)
+        .await
+}
+
+/// Restarts the app on success.
+#[tauri::command]
+pub async fn restore_backup(
+    name: String,
+    app_handle: AppHandle,
+    database_backups: State<'_, Arc<DatabaseBackups>>,
+) -> Result<(), AppError> {
+    database_backups.restore_backup(&app_handle, &name).await
+}
@@ -0,0 +1,106 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::common::entity::{
+    agent::{AgentConfigRow, AgentProviderRow, AgentRow},
+    chat::{ChatMessageAttachmentRow, ChatMessageRow, ChatRow, TagRow},
+    project::{ProjectDocumentRow, ProjectRow},
+};
+
+#[derive(Clone)]
+pub struct ChatMessageEmbeddingRecord {
+    pub message_id: Uuid,
+    pub embedding_model: String,
+    pub content_revision: i64,
+    pub embedding: Vec<u8>,
+}
+
+#[derive(Clone)]
+pub struct ChatMessageSpeechRecord {
+    pub attachment_id: Uuid,
+    pub message_id: Uuid,
+    pub agent_id: Uuid,
+    pub voice: String,
+    pub content_revision: i64,
+}
+
+/// The tables behind the in-memory repos, kept in insertion order like sqlite's rowids
+/// so ties on `created_at` sort the same way.
+#[derive(Clone, Default)]
+pub struct MemoryTables {
+    pub chats: Vec<ChatRow>,
+    pub chat_messages: Vec<ChatMessageRow>,
+    pub chat_message_attachments: Vec<ChatMessageAttachmentRow>,
+    pub chat_message_embeddings: Vec<ChatMessageEmbeddingRecord>,
+    pub chat_message_speeches: Vec<ChatMessageSpeechRecord>,
+    pub chat_tags: Vec<(Uuid, Uuid)>,
+    pub chat_tool_approvals: Vec<(Uuid, String)>,
+    pub chat_encryption: bool,
+    pub tags: Vec<TagRow>,
+    pub agents: Vec<AgentRow>,
+    pub agent_providers: Vec<AgentProviderRow>,
+    pub agent_configs: Vec<AgentConfigRow>,
+    pub agent_disabled_tools: Vec<(Uuid, String)>,
+    pub current_agent_id: Option<Uuid>,
+    pub projects: Vec<ProjectRow>,
+    pub project_documents: Vec<ProjectDocumentRow>,
+}
+
+/// A database for tests. Units of work lock it for as long as they live and write to a
+/// copy of the tables, so a rollback is just dropping the copy.
+pub type MemoryDatabase = Arc<Mutex<MemoryTables>>;
+
+pub fn create_database() -> MemoryDatabase {
+    Arc::new(Mutex::new(MemoryTables::default()))
+}
+
+impl MemoryTables {
+    /// Deletes a chat message along with the rows sqlite would cascade to.
+    pub fn delete_chat_message(&mut self, id: Uuid) -> u64 {
+        let count = self.chat_messages.len();
+        self.chat_messages.retain(|a| a.id != id);
+        self.chat_message_attachments.retain(|a| a.message_id != id);
+        self.chat_message_embeddings.retain(|a| a.message_id != id);
+        self.delete_orphaned_speeches();
+        (count - self.chat_messages.len()) as u64
+    }
+
+    /// Deletes a chat along with the rows sqlite would cascade to.
+    pub fn delete_chat(&mut self, id: Uuid) -> u64 {
+        let count = self.chats.len();
+        self.chats.retain(|a| a.id != id);
+        let message_ids = self
+            .chat_messages
+            .iter()
+            .filter(|a| a.chat_id == id)
+            .map(|a| a.id)
+            .collect::<Vec<_>>();
+        for message_id in message_ids {
+            self.delete_chat_message(message_id);
+        }
+        self.chat_tags.retain(|a| a.0 != id);
+        self.chat_tool_approvals.retain(|a| a.0 != id);
+        (count - self.chats.len()) as u64
+    }
+
+    pub fn delete_orphaned_speeches(&mut self) {
+        let attachments = &self.chat_message_attachments;
+        let messages = &self.chat_messages;
+        self.chat_message_speeches.retain(|a| {
+            attachments.iter().any(|b| b.id == a.attachment_id)
+                && messages.iter().any(|b| b.id == a.message_id)
+        });
+    }
+}
+
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|a| a.as_millis() as i64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,138 @@
+pub mod emitter;
+pub mod logger;
+#[cfg(test)]
+pub mod memory;
+
+use std::sync::{Arc, RwLock};
+
+use uuid::Uuid;
+
+use crate::common::entity::chat::{
+    ChatMessageRow, ChatMessageStatus, ChatRow, ChatSchemaViolation, ChatToolCall,
+};
+
+/// Something that happened to a chat, published by the code that made it happen.
+/// Subscribers decide what it means for the UI, the logs or the indexes.
+#[derive(Clone, Debug)]
+pub enum DomainEvent {
+    ChatMessageCreated {
+        message: ChatMessageRow,
+    },
+    /// A message was rewritten as a whole, e.g. by a change merged from another device.
+    ChatMessageUpdated {
+        message: ChatMessageRow,
+    },
+    AttachmentTruncated {
+        chat_id: Uuid,
+        message_id: Uuid,
+        attachment_id: Uuid,
+        name: Option<String>,
+        included_chars: usize,
+        total_chars: usize,
+    },
+    /// A piece of a streamed reply: text, or an image saved as an attachment.
+    ChunkReceived {
+        chat_id: Uuid,
+        message_id: Uuid,
+        text: String,
+        attachment_id: Option<Uuid>,
+    },
+    ToolCallsRequested {
+        chat_id: Uuid,
+        message_id: Uuid,
+        tool_calls: Vec<ChatToolCall>,
+    },
+    ToolApprovalRequested {
+        chat_id: Uuid,
+        message_id: Uuid,
+        call: ChatToolCall,
+    },
+    ToolApprovalResolved {
+        chat_id: Uuid,
+        tool_call_id: String,
+        approved: bool,
+    },
+    SchemaValidationFailed {
+        chat_id: Uuid,
+        message_id: Uuid,
+        errors: Vec<ChatSchemaViolation>,
+    },
+    MessageStatusChanged {
+        chat_id: Uuid,
+        message_id: Uuid,
+        status: ChatMessageStatus,
+    },
+    /// The agent could not produce a reply. Followed by the message's change to
+    /// `Failed` once it is saved.
+    ResponseFailed {
+        chat_id: Uuid,
+        message_id: Uuid,
+        error: String,
+    },
+    /// A chat was created or changed by a change merged from another device.
+    ChatUpdated {
+        chat: ChatRow,
+    },
+    ChatArchived {
+        chat_id: Uuid,
+        archived: bool,
+    },
+    ChatDeleted {
+        chat_id: Uuid,
+    },
+    ChatMessageDeleted {
+        message_id: Uuid,
+    },
+}
+
+/// Reacts to published events. Called on the publishing task, so anything slow should
+/// be handed off rather than awaited.
+pub trait EventSubscriber: Send + Sync {
+    fn handle(&self, event: &DomainEvent);
+}
+
+/// Delivers each published event to every subscriber, in the order they subscribed.
+pub struct EventBus {
+    subscribers: RwLock<Vec<Arc<dyn EventSubscriber>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            subscribers: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn subscribe(&self, subscriber: Arc<dyn EventSubscriber>) {
+        self.subscribers.write().unwrap().push(subscriber);
+    }
+
+    pub fn publish(&self, event: DomainEvent) {
+        for subscriber in self.subscribers.read().unwrap().iter() {
+            subscriber.handle(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{memory::RecordedEvents, *};
+
+    #[test]
+    fn delivers_events_to_every_subscriber() {
+        let event_bus = EventBus::new();
+        let first = Arc::new(RecordedEvents::default());
+        let second = Arc::new(RecordedEvents::default());
+        event_bus.subscribe(first.clone());
+        event_bus.subscribe(second.clone());
+
+        let chat_id = Uuid::new_v4();
+        event_bus.publish(DomainEvent::ChatDeleted { chat_id });
+
+        for recorded in [first, second] {
+            let events = recorded.events();
+            assert_eq!(events.len(), 1);
+            assert!(matches!(events[0], DomainEvent::ChatDeleted { chat_id: a } if a == chat_id));
+        }
+    }
+}
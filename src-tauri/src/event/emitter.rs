@@ -0,0 +1,251 @@
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    common::entity::chat::{ChatMessageStatus, ChatSchemaViolation, ChatToolCall},
+    event::{DomainEvent, EventSubscriber},
+};
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageResponseChunkPayload {
+    pub chat_id: Uuid,
+    pub id: Uuid,
+    pub text: String,
+    /// Set when the chunk is a generated image, saved as an attachment of the message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachment_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageAttachmentTruncatedPayload {
+    pub chat_id: Uuid,
+    pub message_id: Uuid,
+    pub attachment_id: Uuid,
+    pub name: Option<String>,
+    pub included_chars: usize,
+    pub total_chars: usize,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageSchemaValidationFailedPayload {
+    pub chat_id: Uuid,
+    pub message_id: Uuid,
+    pub errors: Vec<ChatSchemaViolation>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageStatusChangedPayload {
+    pub chat_id: Uuid,
+    pub message_id: Uuid,
+    pub status: ChatMessageStatus,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageToolCallsPayload {
+    pub chat_id: Uuid,
+    pub message_id: Uuid,
+    pub tool_calls: Vec<ChatToolCall>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageResponseFailedPayload {
+    pub chat_id: Uuid,
+    pub message_id: Uuid,
+    pub error: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolApprovalRequestedPayload {
+    pub chat_id: Uuid,
+    pub message_id: Uuid,
+    pub tool_call_id: String,
+    pub tool_name: String,
+    pub arguments: Value,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolApprovalResolvedPayload {
+    pub chat_id: Uuid,
+    pub tool_call_id: String,
+    pub approved: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatArchivedPayload {
+    pub chat_id: Uuid,
+    pub archived: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatDeletedPayload {
+    pub chat_id: Uuid,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessageDeletedPayload {
+    pub message_id: Uuid,
+}
+
+/// Forwards events to the frontend under the names and payloads it listens for.
+pub struct TauriEventEmitter<R: Runtime> {
+    app_handle: AppHandle<R>,
+}
+
+impl<R: Runtime> TauriEventEmitter<R> {
+    pub fn new(app_handle: AppHandle<R>) -> Self {
+        Self { app_handle }
+    }
+
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        let _ = self.app_handle.emit(event, payload).inspect_err(|e| {
+            log::error!("failed to emit {event}: {e}");
+        });
+    }
+}
+
+impl<R: Runtime> EventSubscriber for TauriEventEmitter<R> {
+    fn handle(&self, event: &DomainEvent) {
+        match event.clone() {
+            DomainEvent::ChatMessageCreated { message } => {
+                self.emit("chat_message_created", message);
+            }
+            DomainEvent::ChatMessageUpdated { message } => {
+                self.emit("chat_message_updated", message);
+            }
+            DomainEvent::AttachmentTruncated {
+                chat_id,
+                message_id,
+                attachment_id,
+                name,
+                included_chars,
+                total_chars,
+            } => self.emit(
+                "chat_message_attachment_truncated",
+                ChatMessageAttachmentTruncatedPayload {
+                    chat_id,
+                    message_id,
+                    attachment_id,
+                    name,
+                    included_chars,
+                    total_chars,
+                },
+            ),
+            DomainEvent::ChunkReceived {
+                chat_id,
+                message_id,
+                text,
+                attachment_id,
+            } => self.emit(
+                "chat_message_response_chunk",
+                ChatMessageResponseChunkPayload {
+                    chat_id,
+                    id: message_id,
+                    text,
+                    attachment_id,
+                },
+            ),
+            DomainEvent::ToolCallsRequested {
+                chat_id,
+                message_id,
+                tool_calls,
+            } => self.emit(
+                "chat_message_tool_calls",
+                ChatMessageToolCallsPayload {
+                    chat_id,
+                    message_id,
+                    tool_calls,
+                },
+            ),
+            DomainEvent::ToolApprovalRequested {
+                chat_id,
+                message_id,
+                call,
+            } => self.emit(
+                "tool_approval_requested",
+                ToolApprovalRequestedPayload {
+                    chat_id,
+                    message_id,
+                    tool_call_id: call.id,
+                    tool_name: call.name,
+                    arguments: call.arguments,
+                },
+            ),
+            DomainEvent::ToolApprovalResolved {
+                chat_id,
+                tool_call_id,
+                approved,
+            } => self.emit(
+                "tool_approval_resolved",
+                ToolApprovalResolvedPayload {
+                    chat_id,
+                    tool_call_id,
+                    approved,
+                },
+            ),
+            DomainEvent::SchemaValidationFailed {
+                chat_id,
+                message_id,
+                errors,
+            } => self.emit(
+                "chat_message_schema_validation_failed",
+                ChatMessageSchemaValidationFailedPayload {
+                    chat_id,
+                    message_id,
+                    errors,
+                },
+            ),
+            DomainEvent::MessageStatusChanged {
+                chat_id,
+                message_id,
+                status,
+            } => self.emit(
+                "chat_message_status_changed",
+                ChatMessageStatusChangedPayload {
+                    chat_id,
+                    message_id,
+                    status,
+                },
+            ),
+            DomainEvent::ResponseFailed {
+                chat_id,
+                message_id,
+                error,
+            } => self.emit(
+                "chat_message_response_failed",
+                ChatMessageResponseFailedPayload {
+                    chat_id,
+                    message_id,
+                    error,
+                },
+            ),
+            DomainEvent::ChatUpdated { chat } => {
+                self.emit("chat_updated", chat);
+            }
+            DomainEvent::ChatArchived { chat_id, archived } => {
+                self.emit("chat_archived", ChatArchivedPayload { chat_id, archived });
+            }
+            DomainEvent::ChatDeleted { chat_id } => {
+                self.emit("chat_deleted", ChatDeletedPayload { chat_id });
+            }
+            DomainEvent::ChatMessageDeleted { message_id } => {
+                self.emit(
+                    "chat_message_deleted",
+                    ChatMessageDeletedPayload { message_id },
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{
+    common::entity::chat::ChatMessageStatus,
+    event::{DomainEvent, EventSubscriber},
+};
+
+/// Writes failures and deletions to the log, wherever in the backend they happen.
+pub struct EventLogger;
+
+impl EventSubscriber for EventLogger {
+    fn handle(&self, event: &DomainEvent) {
+        match event {
+            DomainEvent::ResponseFailed {
+                chat_id,
+                message_id,
+                error,
+            } => {
+                log::error!("reply {message_id} in chat {chat_id} failed: {error}");
+            }
+            DomainEvent::SchemaValidationFailed {
+                chat_id,
+                message_id,
+                errors,
+            } => {
+                log::warn!(
+                    "reply {message_id} in chat {chat_id} broke the response schema in {} places",
+                    errors.len()
+                );
+            }
+            DomainEvent::MessageStatusChanged {
+                chat_id,
+                message_id,
+                status: ChatMessageStatus::Failed,
+            } => {
+                log::warn!("marked message {message_id} in chat {chat_id} as failed");
+            }
+            DomainEvent::ChatDeleted { chat_id } => {
+                log::info!("deleted chat {chat_id}");
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::sync::Mutex;
+
+use crate::event::{DomainEvent, EventSubscriber};
+
+/// Keeps every event it is handed, for tests to inspect.
+#[derive(Default)]
+pub struct RecordedEvents {
+    events: Mutex<Vec<DomainEvent>>,
+}
+
+impl RecordedEvents {
+    pub fn events(&self) -> Vec<DomainEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl EventSubscriber for RecordedEvents {
+    fn handle(&self, event: &DomainEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
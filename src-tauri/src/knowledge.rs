@@ -0,0 +1,425 @@
+pub mod cmds;
+pub mod repo;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::{
+    agent::{Agent, AgentContext, AgentEmbeddingParams, AgentEmbeddingTask},
+    chat::document::{extract_text, guess_mime_type},
+    common::{
+        entity::{chat::ChatCitation, knowledge::KnowledgeFolderRow},
+        error::AppError,
+        vector::{cosine_similarity, decode_embedding, encode_embedding},
+    },
+    knowledge::repo::{
+        CreateKnowledgeChunk, CreateKnowledgeFile, KnowledgeRepo, UpdateKnowledgeFolder,
+    },
+};
+
+pub const EMBEDDING_MODEL: &str = "gemini-embedding-001";
+pub const RETRIEVAL_LIMIT: usize = 6;
+
+const CHUNK_CHARS: usize = 1_200;
+const CHUNK_OVERLAP_CHARS: usize = 200;
+const FILE_SIZE_LIMIT_BYTES: u64 = 20 * 1024 * 1024;
+
+#[derive(Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeIndexReport {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeIndexProgressPayload {
+    pub folder_id: Uuid,
+    pub path: String,
+    pub processed: usize,
+    pub total: usize,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeIndexCompletedPayload {
+    pub folder_id: Uuid,
+    pub report: Option<KnowledgeIndexReport>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeMatch {
+    pub chunk_id: Uuid,
+    pub folder_id: Uuid,
+    pub path: String,
+    pub ordinal: i64,
+    pub content: String,
+    pub score: f32,
+}
+
+struct KnowledgeFileCandidate {
+    path: String,
+    size: i64,
+    modified_at: i64,
+}
+
+/// Keeps the chunks and embeddings of each knowledge folder in line with the files on
+/// disk. Only files whose size or modification time changed are embedded again.
+pub struct KnowledgeIndexer {
+    knowledge_repo: Arc<dyn KnowledgeRepo>,
+    agent_context: AgentContext,
+    indexing: Mutex<HashSet<Uuid>>,
+}
+
+impl KnowledgeIndexer {
+    pub fn new(knowledge_repo: Arc<dyn KnowledgeRepo>, agent_context: AgentContext) -> Self {
+        Self {
+            knowledge_repo,
+            agent_context,
+            indexing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn spawn_index(self: &Arc<Self>, app_handle: AppHandle, folder_id: Uuid) {
+        let indexer = self.clone();
+        tauri::async_runtime::spawn(async move {
+            if !indexer.indexing.lock().unwrap().insert(folder_id) {
+                log::info!("knowledge folder {} is already being indexed", folder_id);
+                return;
+            }
+            let result = indexer.index(&app_handle, folder_id).await;
+            indexer.indexing.lock().unwrap().remove(&folder_id);
+            if let Err(e) = &result {
+                log::error!("failed to index knowledge folder {}: {}", folder_id, e);
+            }
+            let _ = app_handle
+                .emit(
+                    "knowledge_index_completed",
+                    KnowledgeIndexCompletedPayload {
+                        folder_id,
+                        error: result.as_ref().err().map(|e| e.to_string()),
+                        report: result.ok(),
+                    },
+                )
+                .inspect_err(|e| {
+                    log::error!("failed to emit knowledge_index_completed: {e}");
+                });
+        });
+    }
+
+    async fn index(
+        &self,
+        app_handle: &AppHandle,
+        folder_id: Uuid,
+    ) -> Result<KnowledgeIndexReport, AppError> {
+        let folder = self
+            .knowledge_repo
+            .get_knowledge_folder(folder_id)
+            .await?
+            .ok_or_else(|| AppError::KnowledgeFolderNotFound(folder_id))?;
+        let agent = self.get_agent(&folder).await?;
+        let mut indexed = self
+            .knowledge_repo
+            .get_knowledge_files(folder.id)
+            .await?
+            .into_iter()
+            .map(|a| (a.path.clone(), a))
+            .collect::<HashMap<_, _>>();
+
+        let mut report = KnowledgeIndexReport::default();
+        let mut changed = Vec::new();
+        for candidate in collect_files(Path::new(&folder.path)).await? {
+            match indexed.remove(&candidate.path) {
+                Some(file)
+                    if file.size == candidate.size && file.modified_at == candidate.modified_at =>
+                {
+                    report.unchanged += 1
+                }
+                Some(_) => changed.push((candidate, false)),
+                None => changed.push((candidate, true)),
+            }
+        }
+        for file in indexed.into_values() {
+            self.knowledge_repo.delete_knowledge_file(file.id).await?;
+            report.removed += 1;
+        }
+
+        let total = changed.len();
+        for (processed, (candidate, added)) in changed.into_iter().enumerate() {
+            let _ = app_handle
+                .emit(
+                    "knowledge_index_progress",
+                    KnowledgeIndexProgressPayload {
+                        folder_id,
+                        path: candidate.path.clone(),
+                        processed,
+                        total,
+                    },
+                )
+                .inspect_err(|e| {
+                    log::error!("failed to emit knowledge_index_progress: {e}");
+                });
+            match self.index_file(&folder, agent.clone(), candidate).await {
+                Ok(()) if added => report.added += 1,
+                Ok(()) => report.updated += 1,
+                Err(e @ AppError::AgentConfigRequired) => return Err(e),
+                Err(e) => {
+                    log::warn!("failed to index knowledge file: {}", e);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        self.knowledge_repo
+            .update_knowledge_folder(
+                folder_id,
+                UpdateKnowledgeFolder {
+                    indexed_at: Some(now_millis()?),
+                },
+            )
+            .await?;
+        Ok(report)
+    }
+
+    async fn index_file(
+        &self,
+        folder: &KnowledgeFolderRow,
+        agent: Agent,
+        candidate: KnowledgeFileCandidate,
+    ) -> Result<(), AppError> {
+        let mime_type = guess_mime_type(&candidate.path)
+            .ok_or_else(|| AppError::UnsupportedAttachment(candidate.path.clone()))?;
+        let data = tokio::fs::read(&candidate.path).await?;
+        let text = tokio::task::spawn_blocking(move || extract_text(mime_type, &data))
+            .await
+            .map_err(|e| AppError::DocumentExtraction(e.to_string()))??;
+        let chunks = chunk_text(&text);
+        let label = Path::new(&candidate.path)
+            .strip_prefix(&folder.path)
+            .unwrap_or(Path::new(&candidate.path))
+            .to_string_lossy()
+            .into_owned();
+        let embeddings = match chunks.is_empty() {
+            true => Vec::new(),
+            false => {
+                agent
+                    .embed_texts(
+                        self.agent_context.clone(),
+                        AgentEmbeddingParams {
+                            model: folder.embedding_model.clone(),
+                            texts: chunks
+                                .iter()
+                                .map(|a| format!("{}\n\n{}", label, a))
+                                .collect(),
+                            task: AgentEmbeddingTask::Document,
+                        },
+                    )
+                    .await?
+            }
+        };
+        self.knowledge_repo
+            .replace_knowledge_file(
+                CreateKnowledgeFile {
+                    id: Uuid::new_v4(),
+                    folder_id: folder.id,
+                    path: candidate.path,
+                    size: candidate.size,
+                    modified_at: candidate.modified_at,
+                },
+                chunks
+                    .into_iter()
+                    .zip(embeddings)
+                    .enumerate()
+                    .map(|(ordinal, (content, embedding))| CreateKnowledgeChunk {
+                        id: Uuid::new_v4(),
+                        ordinal: ordinal as i64,
+                        content,
+                        embedding: encode_embedding(&embedding),
+                    })
+                    .collect(),
+            )
+            .await
+    }
+
+    /// Ranks the chunks of the given folders by cosine similarity to `query`. Folders
+    /// embedded with different agents or models are queried separately.
+    pub async fn search(
+        &self,
+        folder_ids: &[Uuid],
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeMatch>, AppError> {
+        let mut groups = HashMap::<(Uuid, String), (KnowledgeFolderRow, Vec<Uuid>)>::new();
+        for folder in self.knowledge_repo.get_knowledge_folders().await? {
+            if !folder_ids.contains(&folder.id) {
+                continue;
+            }
+            groups
+                .entry((folder.agent_id, folder.embedding_model.clone()))
+                .or_insert_with(|| (folder.clone(), Vec::new()))
+                .1
+                .push(folder.id);
+        }
+
+        let mut matches = Vec::new();
+        for (folder, ids) in groups.into_values() {
+            let query_embedding = self
+                .get_agent(&folder)
+                .await?
+                .embed_texts(
+                    self.agent_context.clone(),
+                    AgentEmbeddingParams {
+                        model: folder.embedding_model.clone(),
+                        texts: vec![query.to_string()],
+                        task: AgentEmbeddingTask::Query,
+                    },
+                )
+                .await?
+                .pop()
+                .unwrap_or_default();
+            for chunk in self.knowledge_repo.get_knowledge_chunks(&ids).await? {
+                let score =
+                    cosine_similarity(&query_embedding, &decode_embedding(&chunk.embedding));
+                matches.push(KnowledgeMatch {
+                    chunk_id: chunk.id,
+                    folder_id: chunk.folder_id,
+                    path: chunk.path,
+                    ordinal: chunk.ordinal,
+                    content: chunk.content,
+                    score,
+                });
+            }
+        }
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
+    async fn get_agent(&self, folder: &KnowledgeFolderRow) -> Result<Agent, AppError> {
+        self.agent_context
+            .agent_repo
+            .get_agent(folder.agent_id)
+            .await?
+            .map(Agent::from)
+            .ok_or_else(|| AppError::AgentRequired)
+    }
+}
+
+/// Renders retrieved chunks as extra instructions, numbered so the model can cite them.
+pub fn render_knowledge(matches: &[KnowledgeMatch]) -> String {
+    let excerpts = matches
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            format!(
+                "[{}] {} (part {})\n{}",
+                i + 1,
+                a.path,
+                a.ordinal + 1,
+                a.content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    format!(
+        "The following excerpts from the user's files may be relevant. Use them when they help \
+         answer and cite the ones you use as [n].\n\n{}",
+        excerpts
+    )
+}
+
+pub fn knowledge_citations(matches: &[KnowledgeMatch]) -> Vec<ChatCitation> {
+    matches
+        .iter()
+        .enumerate()
+        .map(|(i, a)| ChatCitation {
+            index: i + 1,
+            chunk_id: a.chunk_id,
+            path: a.path.clone(),
+            ordinal: a.ordinal,
+            score: a.score,
+        })
+        .collect()
+}
+
+/// Walks `root` for files with a supported format, skipping hidden entries and not
+/// following symlinks.
+async fn collect_files(root: &Path) -> Result<Vec<KnowledgeFileCandidate>, AppError> {
+    let mut files = Vec::new();
+    let mut directories = vec![PathBuf::from(root)];
+    while let Some(directory) = directories.pop() {
+        let mut read_dir = tokio::fs::read_dir(&directory).await?;
+        while let Some(entry) = read_dir.next_entry().await? {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                directories.push(entry.path());
+                continue;
+            }
+            let path = entry.path().to_string_lossy().into_owned();
+            if !file_type.is_file() || guess_mime_type(&path).is_none() {
+                continue;
+            }
+            let metadata = entry.metadata().await?;
+            if metadata.len() > FILE_SIZE_LIMIT_BYTES {
+                log::info!("skipping large knowledge file {}", path);
+                continue;
+            }
+            files.push(KnowledgeFileCandidate {
+                path,
+                size: metadata.len() as i64,
+                modified_at: metadata.modified()?.duration_since(UNIX_EPOCH)?.as_millis() as i64,
+            });
+        }
+    }
+    Ok(files)
+}
+
+/// Cuts text into overlapping windows, preferring to end a window at a line break or
+/// space in its second half.
+fn chunk_text(text: &str) -> Vec<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = (start + CHUNK_CHARS).min(chars.len());
+        if end < chars.len() {
+            let window = &chars[start + CHUNK_CHARS / 2..end];
+            if let Some(i) = window.iter().rposition(|a| *a == '\n') {
+                end = start + CHUNK_CHARS / 2 + i + 1;
+            } else if let Some(i) = window.iter().rposition(|a| a.is_whitespace()) {
+                end = start + CHUNK_CHARS / 2 + i + 1;
+            }
+        }
+        let chunk = chars[start..end].iter().collect::<String>();
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk.trim().to_string());
+        }
+        if end == chars.len() {
+            break;
+        }
+        start = end.saturating_sub(CHUNK_OVERLAP_CHARS).max(start + 1);
+    }
+    chunks
+}
+
+fn now_millis() -> Result<i64, AppError> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)?
+        .as_millis() as i64)
+}
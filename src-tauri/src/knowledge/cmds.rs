@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use crate::{
+    agent::{AgentContext, AgentProvider},
+    common::{entity::knowledge::KnowledgeFolderRow, error::AppError},
+    knowledge::{
+        repo::{CreateKnowledgeFolder, KnowledgeRepo},
+        KnowledgeIndexer, KnowledgeMatch, EMBEDDING_MODEL, RETRIEVAL_LIMIT,
+    },
+};
+
+#[tauri::command]
+pub async fn get_knowledge_folders(
+    knowledge_repo: State<'_, Arc<dyn KnowledgeRepo>>,
+) -> Result<Vec<KnowledgeFolderRow>, AppError> {
+    knowledge_repo.get_knowledge_folders().await
+}
+
+/// Embeddings are created with the given agent's credentials, so it must belong to a
+/// provider with an embedding endpoint.
+#[tauri::command]
+pub async fn create_knowledge_folder(
+    path: String,
+    agent_id: Uuid,
+    app_handle: AppHandle,
+    agent_context: State<'_, AgentContext>,
+    knowledge_repo: State<'_, Arc<dyn KnowledgeRepo>>,
+    knowledge_indexer: State<'_, Arc<KnowledgeIndexer>>,
+) -> Result<KnowledgeFolderRow, AppError> {
+    let path = tokio::fs::canonicalize(&path).await?;
+    if !tokio::fs::metadata(&path).await?.is_dir() {
+        return Err(AppError::InvalidKnowledgeFolder(format!(
+            "not a directory: {}",
+            path.display()
+        )));
+    }
+    let agent = agent_context
+        .agent_repo
+        .get_agent(agent_id)
+        .await?
+        .ok_or(AppError::AgentRequired)?;
+    if !matches!(agent.provider, AgentProvider::Google) {
+        return Err(AppError::AgentCapabilityUnsupported("embeddings".into()));
+    }
+    let folder = knowledge_repo
+        .create_knowledge_folder(CreateKnowledgeFolder {
+            id: Uuid::new_v4(),
+            path: path.to_string_lossy().into_owned(),
+            agent_id,
+            embedding_model: EMBEDDING_MODEL.into(),
+        })
+        .await?;
+    knowledge_indexer.spawn_index(app_handle, folder.id);
+    Ok(folder)
+}
+
+#[tauri::command]
+pub async fn delete_knowledge_folder(
+    id: Uuid,
+    knowledge_repo: State<'_, Arc<dyn KnowledgeRepo>>,
+) -> Result<u64, AppError> {
+    knowledge_repo.delete_knowledge_folder(id).await
+}
+
+#[tauri::command]
+pub async fn reindex_knowledge_folder(
+    id: Uuid,
+    app_handle: AppHandle,
+    knowledge_repo: State<'_, Arc<dyn KnowledgeRepo>>,
+    knowledge_indexer: State<'_, Arc<KnowledgeIndexer>>,
+) -> Result<(), AppError> {
+    knowledge_repo
+        .get_knowledge_folder(id)
+        .await?
+        .ok_or(AppError::KnowledgeFolderNotFound(id))?;
+    knowledge_indexer.spawn_index(app_handle, id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn search_knowledge(
+    folder_ids: Vec<Uuid>,
+    query: String,
+    limit: Option<usize>,
+    knowledge_indexer: State<'_, Arc<KnowledgeIndexer>>,
+) -> Result<Vec<KnowledgeMatch>, AppError> {
+    knowledge_indexer
+        .search(&folder_ids, &query, limit.unwrap_or(RETRIEVAL_LIMIT))
+        .await
+}
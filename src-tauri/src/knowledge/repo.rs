@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::common::{
+    entity::knowledge::{KnowledgeChunkRow, KnowledgeFileRow, KnowledgeFolderRow},
+    error::AppError,
+};
+
+pub mod sqlite;
+
+pub struct CreateKnowledgeFolder {
+    pub id: Uuid,
+    pub path: String,
+    pub agent_id: Uuid,
+    pub embedding_model: String,
+}
+
+#[derive(Default)]
+pub struct UpdateKnowledgeFolder {
+    pub indexed_at: Option<i64>,
+}
+
+pub struct CreateKnowledgeFile {
+    pub id: Uuid,
+    pub folder_id: Uuid,
+    pub path: String,
+    pub size: i64,
+    pub modified_at: i64,
+}
+
+pub struct CreateKnowledgeChunk {
+    pub id: Uuid,
+    pub ordinal: i64,
+    pub content: String,
+    pub embedding: Vec<u8>,
+}
+
+#[async_trait]
+pub trait KnowledgeRepo: Send + Sync {
+    async fn get_knowledge_folders(&self) -> Result<Vec<KnowledgeFolderRow>, AppError>;
+    async fn get_knowledge_folder(&self, id: Uuid) -> Result<Option<KnowledgeFolderRow>, AppError>;
+    async fn create_knowledge_folder(
+        &self,
+        create: CreateKnowledgeFolder,
+    ) -> Result<KnowledgeFolderRow, AppError>;
+    async fn update_knowledge_folder(
+        &self,
+        id: Uuid,
+        update: UpdateKnowledgeFolder,
+    ) -> Result<u64, AppError>;
+    async fn delete_knowledge_folder(&self, id: Uuid) -> Result<u64, AppError>;
+    async fn get_knowledge_files(&self, folder_id: Uuid)
+        -> Result<Vec<KnowledgeFileRow>, AppError>;
+    /// Replaces the file at `create.path` and all of its chunks in one transaction.
+    async fn replace_knowledge_file(
+        &self,
+        create: CreateKnowledgeFile,
+        chunks: Vec<CreateKnowledgeChunk>,
+    ) -> Result<(), AppError>;
+    async fn delete_knowledge_file(&self, id: Uuid) -> Result<u64, AppError>;
+    async fn get_knowledge_chunks(
+        &self,
+        folder_ids: &[Uuid],
+    ) -> Result<Vec<KnowledgeChunkRow>, AppError>;
+}
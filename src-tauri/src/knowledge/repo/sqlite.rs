@@ -0,0 +1,268 @@
+use async_trait::async_trait;
+use sqlx::{Executor, Pool, QueryBuilder, Sqlite};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    common::{
+        entity::knowledge::{KnowledgeChunkRow, KnowledgeFileRow, KnowledgeFolderRow},
+        error::AppError,
+    },
+    knowledge::repo::{
+        CreateKnowledgeChunk, CreateKnowledgeFile, CreateKnowledgeFolder, KnowledgeRepo,
+        UpdateKnowledgeFolder,
+    },
+};
+
+pub struct SqliteKnowledgeRepo {
+    db_pool: Arc<Pool<Sqlite>>,
+}
+
+impl SqliteKnowledgeRepo {
+    pub fn new(db_pool: Arc<Pool<Sqlite>>) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl KnowledgeRepo for SqliteKnowledgeRepo {
+    async fn get_knowledge_folders(&self) -> Result<Vec<KnowledgeFolderRow>, AppError> {
+        get_knowledge_folders(&*self.db_pool).await
+    }
+
+    async fn get_knowledge_folder(&self, id: Uuid) -> Result<Option<KnowledgeFolderRow>, AppError> {
+        get_knowledge_folder(&*self.db_pool, id).await
+    }
+
+    async fn create_knowledge_folder(
+        &self,
+        create: CreateKnowledgeFolder,
+    ) -> Result<KnowledgeFolderRow, AppError> {
+        create_knowledge_folder(&*self.db_pool, create).await
+    }
+
+    async fn update_knowledge_folder(
+        &self,
+        id: Uuid,
+        update: UpdateKnowledgeFolder,
+    ) -> Result<u64, AppError> {
+        update_knowledge_folder(&*self.db_pool, id, update).await
+    }
+
+    async fn delete_knowledge_folder(&self, id: Uuid) -> Result<u64, AppError> {
+        delete_knowledge_folder(&*self.db_pool, id).await
+    }
+
+    async fn get_knowledge_files(
+        &self,
+        folder_id: Uuid,
+    ) -> Result<Vec<KnowledgeFileRow>, AppError> {
+        get_knowledge_files(&*self.db_pool, folder_id).await
+    }
+
+    async fn replace_knowledge_file(
+        &self,
+        create: CreateKnowledgeFile,
+        chunks: Vec<CreateKnowledgeChunk>,
+    ) -> Result<(), AppError> {
+        let mut tx = self.db_pool.begin().await.map_err(AppError::from)?;
+        sqlx::query("delete from knowledge_files where folder_id = ?1 and path = ?2")
+            .bind(create.folder_id)
+            .bind(&create.path)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        let file_id = create.id;
+        create_knowledge_file(&mut *tx, create).await?;
+        for chunk in chunks {
+            create_knowledge_chunk(&mut *tx, file_id, chunk).await?;
+        }
+        tx.commit().await.map_err(AppError::from)
+    }
+
+    async fn delete_knowledge_file(&self, id: Uuid) -> Result<u64, AppError> {
+        delete_knowledge_file(&*self.db_pool, id).await
+    }
+
+    async fn get_knowledge_chunks(
+        &self,
+        folder_ids: &[Uuid],
+    ) -> Result<Vec<KnowledgeChunkRow>, AppError> {
+        get_knowledge_chunks(&*self.db_pool, folder_ids).await
+    }
+}
+
+async fn get_knowledge_folders<'a, E>(executor: E) -> Result<Vec<KnowledgeFolderRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, KnowledgeFolderRow>("select * from knowledge_folders order by path asc")
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn get_knowledge_folder<'a, E>(
+    executor: E,
+    id: Uuid,
+) -> Result<Option<KnowledgeFolderRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, KnowledgeFolderRow>("select * from knowledge_folders where id = ?1")
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn create_knowledge_folder<'a, E>(
+    executor: E,
+    create: CreateKnowledgeFolder,
+) -> Result<KnowledgeFolderRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let (created_at, updated_at): (i64, i64) = sqlx::query_as("insert into knowledge_folders (id, path, agent_id, embedding_model) values (?1, ?2, ?3, ?4) returning created_at, updated_at")
+        .bind(create.id)
+        .bind(&create.path)
+        .bind(create.agent_id)
+        .bind(&create.embedding_model)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(KnowledgeFolderRow {
+        created_at,
+        updated_at,
+        id: create.id,
+        path: create.path,
+        agent_id: create.agent_id,
+        embedding_model: create.embedding_model,
+        indexed_at: None,
+    })
+}
+
+async fn update_knowledge_folder<'a, E>(
+    executor: E,
+    id: Uuid,
+    update: UpdateKnowledgeFolder,
+) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    if update.indexed_at.is_none() {
+        return Ok(0);
+    }
+    let mut qb = QueryBuilder::new("update knowledge_folders set ");
+    let mut sep = qb.separated(", ");
+    if let Some(indexed_at) = update.indexed_at {
+        sep.push("indexed_at = ").push_bind_unseparated(indexed_at);
+    }
+    qb.push(" where id = ").push_bind(id);
+
+    let result = qb.build().execute(executor).await.map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn delete_knowledge_folder<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from knowledge_folders where id = ?1")
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn get_knowledge_files<'a, E>(
+    executor: E,
+    folder_id: Uuid,
+) -> Result<Vec<KnowledgeFileRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, KnowledgeFileRow>(
+        "select * from knowledge_files where folder_id = ?1 order by path asc",
+    )
+    .bind(folder_id)
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn create_knowledge_file<'a, E>(
+    executor: E,
+    create: CreateKnowledgeFile,
+) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("insert into knowledge_files (id, folder_id, path, size, modified_at) values (?1, ?2, ?3, ?4, ?5)")
+        .bind(create.id)
+        .bind(create.folder_id)
+        .bind(&create.path)
+        .bind(create.size)
+        .bind(create.modified_at)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn create_knowledge_chunk<'a, E>(
+    executor: E,
+    file_id: Uuid,
+    create: CreateKnowledgeChunk,
+) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("insert into knowledge_chunks (id, file_id, ordinal, content, embedding) values (?1, ?2, ?3, ?4, ?5)")
+        .bind(create.id)
+        .bind(file_id)
+        .bind(create.ordinal)
+        .bind(&create.content)
+        .bind(&create.embedding)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn delete_knowledge_file<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from knowledge_files where id = ?1")
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn get_knowledge_chunks<'a, E>(
+    executor: E,
+    folder_ids: &[Uuid],
+) -> Result<Vec<KnowledgeChunkRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    if folder_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut qb = QueryBuilder::new(
+        "select c.id, f.folder_id, f.path, c.ordinal, c.content, c.embedding from knowledge_chunks c inner join knowledge_files f on f.id = c.file_id where f.folder_id in (",
+    );
+    let mut sep = qb.separated(", ");
+    for folder_id in folder_ids {
+        sep.push_bind(folder_id);
+    }
+    qb.push(")");
+    qb.build_query_as::<KnowledgeChunkRow>()
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
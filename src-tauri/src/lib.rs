@@ -3,12 +3,20 @@ mod chat;
 mod cipher;
 mod codec;
 mod common;
+mod database;
+mod event;
+mod knowledge;
 mod launcher;
+mod mcp;
+mod profile;
+mod project;
+mod retention;
+mod sync;
+mod tool;
 
 use const_hex::ToHexExt;
 use keyring::Entry;
 use rand::{rngs::OsRng, TryRngCore};
-use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePoolOptions, Sqlite};
 use std::{panic, sync::Arc};
 use tauri::{
     generate_handler,
@@ -23,12 +31,47 @@ use crate::{
         repo::{sqlite::SqliteAgentRepo, AgentRepo},
         AgentContext,
     },
-    chat::repo::{sqlite::SqliteChatRepo, ChatRepo},
+    chat::{
+        embedding::ChatEmbeddingIndexer,
+        encryption::ChatEncryption,
+        recovery::{self, ChatTasks},
+        repo::{encrypted::EncryptedChatRepo, sqlite::SqliteChatRepo, ChatRepo},
+        search::ChatSearchIndex,
+    },
     cipher::{Cipher, KeyringAesGcmCipher},
     common::{
         http::HttpClientManager,
         unit_of_work::{SqliteUnitOfWorkFactory, UnitOfWorkFactory},
     },
+    database::{backup::DatabaseBackups, DatabaseHealth},
+    event::{emitter::TauriEventEmitter, logger::EventLogger, EventBus},
+    knowledge::{
+        repo::{sqlite::SqliteKnowledgeRepo, KnowledgeRepo},
+        KnowledgeIndexer,
+    },
+    mcp::{
+        repo::{sqlite::SqliteMcpServerRepo, McpServerRepo},
+        McpManager,
+    },
+    profile::{Profiles, KEYRING_SERVICE},
+    project::repo::{sqlite::SqliteProjectRepo, ProjectRepo},
+    retention::{
+        repo::{sqlite::SqliteRetentionRepo, RetentionRepo},
+        Retention,
+    },
+    sync::{
+        repo::{sqlite::SqliteSyncRepo, SyncRepo},
+        ChatSync,
+    },
+    tool::{
+        approval::ToolApprovals,
+        calculator::CalculatorTool,
+        clock::ClockTool,
+        code::CodeExecutionTool,
+        fs::{ListDirectoryTool, ReadFileTool},
+        repo::{sqlite::SqliteToolRepo, ToolRepo},
+        ToolRegistry,
+    },
 };
 
 // #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -43,13 +86,85 @@ pub fn run() {
             chat::cmds::send_chat_message,
             chat::cmds::get_chat,
             chat::cmds::get_chat_messages,
+            chat::cmds::get_chat_attachments,
+            chat::cmds::get_chats,
+            chat::cmds::pin_chat,
+            chat::cmds::star_chat,
+            chat::cmds::archive_chat,
+            chat::cmds::star_chat_message,
+            chat::cmds::get_tags,
+            chat::cmds::delete_tag,
+            chat::cmds::get_chat_tags,
+            chat::cmds::tag_chat,
+            chat::cmds::untag_chat,
+            chat::cmds::export_chat,
+            chat::cmds::export_all_chats,
+            chat::cmds::import_chats,
+            chat::cmds::semantic_search_chats,
+            chat::cmds::synthesize_message_audio,
+            chat::cmds::search_chats,
+            chat::cmds::get_chat_encryption,
+            chat::cmds::update_chat_encryption,
+            tool::cmds::get_tools,
+            tool::cmds::respond_tool_approval,
+            tool::cmds::get_chat_tool_approvals,
+            tool::cmds::revoke_chat_tool_approval,
+            tool::cmds::get_tool_directories,
+            tool::cmds::create_tool_directory,
+            tool::cmds::delete_tool_directory,
+            knowledge::cmds::get_knowledge_folders,
+            knowledge::cmds::create_knowledge_folder,
+            knowledge::cmds::delete_knowledge_folder,
+            knowledge::cmds::reindex_knowledge_folder,
+            knowledge::cmds::search_knowledge,
+            mcp::cmds::get_mcp_servers,
+            mcp::cmds::create_mcp_server,
+            mcp::cmds::update_mcp_server,
+            mcp::cmds::delete_mcp_server,
+            mcp::cmds::connect_mcp_server,
+            mcp::cmds::disconnect_mcp_server,
+            mcp::cmds::get_mcp_server_connections,
+            mcp::cmds::get_mcp_prompts,
+            mcp::cmds::get_mcp_prompt,
+            mcp::cmds::get_mcp_resources,
+            mcp::cmds::read_mcp_resource,
             launcher::cmds::destroy_launcher_window,
+            database::cmds::get_database_health,
+            database::cmds::check_database_health,
+            database::cmds::get_backups,
+            database::cmds::create_backup,
+            database::cmds::restore_backup,
             agent::cmds::get_agents,
+            agent::cmds::transcribe_audio,
             agent::cmds::get_current_agent,
             agent::cmds::update_current_agent,
             agent::cmds::get_agent_config,
             agent::cmds::upsert_agent_config,
             agent::cmds::decrypt_agent_ciphertext,
+            agent::cmds::get_agent_tools,
+            agent::cmds::update_agent_tool,
+            project::cmds::get_projects,
+            project::cmds::get_project,
+            project::cmds::create_project,
+            project::cmds::update_project,
+            project::cmds::delete_project,
+            project::cmds::get_project_documents,
+            project::cmds::add_project_document,
+            project::cmds::delete_project_document,
+            profile::cmds::get_profiles,
+            profile::cmds::create_profile,
+            profile::cmds::rename_profile,
+            profile::cmds::delete_profile,
+            profile::cmds::switch_profile,
+            retention::cmds::get_retention_policy,
+            retention::cmds::update_retention_policy,
+            retention::cmds::preview_retention,
+            retention::cmds::apply_retention,
+            sync::cmds::get_sync_state,
+            sync::cmds::update_sync_folder,
+            sync::cmds::export_sync_key,
+            sync::cmds::import_sync_key,
+            sync::cmds::run_sync,
         ])
         .build(tauri::generate_context!())
         .expect("error while running tauri application")
@@ -127,70 +242,179 @@ fn setup_global_shortcut(app: &mut App) -> Result<(), Box<dyn std::error::Error>
 }
 
 fn setup_dependencies(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
-    let app_local_data_dir = app
-        .path()
-        .app_local_data_dir()
-        .expect("failed to get app local data dir");
-
+    let app_local_data_dir = app.path().app_local_data_dir()?;
     if !app_local_data_dir.exists() {
-        std::fs::create_dir_all(&app_local_data_dir).expect("failed to create app local data dir");
+        std::fs::create_dir_all(&app_local_data_dir)?;
     }
 
-    let db_file_path = app_local_data_dir.join("askkit.db");
-    let db_file_path_str = db_file_path
-        .to_str()
-        .expect("failed to convert path to str");
-
     let http_client_manager = Arc::new(HttpClientManager::new());
 
-    tauri::async_runtime::block_on(async { Sqlite::create_database(db_file_path_str).await })
-        .expect("failed to create sqlite database");
-
-    let db_pool = Arc::new(tauri::async_runtime::block_on(async {
-        SqlitePoolOptions::new()
-            .after_connect(|conn, _meta| {
-                Box::pin(async move {
-                    sqlx::query("pragma recursive_triggers = off")
-                        .execute(conn)
-                        .await?;
-                    Ok(())
-                })
-            })
-            .connect(&format!(
-                "sqlite:{}",
-                app_local_data_dir
-                    .join("askkit.db")
-                    .to_str()
-                    .expect("failed to convert path to str"),
-            ))
-            .await
-            .expect("failed to connect to sqlite")
-    }));
-    let cipher: Arc<dyn Cipher> = Arc::new(KeyringAesGcmCipher::new());
-    let chat_repo: Arc<dyn ChatRepo> = Arc::new(SqliteChatRepo::new(db_pool.clone()));
+    let profiles = Arc::new(Profiles::load(&app_local_data_dir)?);
+    let profile = profiles.active().clone();
+    log::info!("starting with profile {} ({})", profile.name, profile.id);
+    setup_keyring(&profile.keyring_user)?;
+
+    let db_path = profiles.database_path(&profile);
+    if let Some(db_dir) = db_path.parent() {
+        std::fs::create_dir_all(db_dir)?;
+    }
+    let db_pool = Arc::new(tauri::async_runtime::block_on(database::connect(&db_path))?);
+    let cipher: Arc<dyn Cipher> = Arc::new(KeyringAesGcmCipher::new(profile.keyring_user.clone()));
     let agent_repo: Arc<dyn AgentRepo> = Arc::new(SqliteAgentRepo::new(db_pool.clone()));
-    let unit_of_work_factory: Arc<dyn UnitOfWorkFactory> =
-        Arc::new(SqliteUnitOfWorkFactory::new(db_pool.clone()));
+    let project_repo: Arc<dyn ProjectRepo> = Arc::new(SqliteProjectRepo::new(db_pool.clone()));
+    let mcp_server_repo: Arc<dyn McpServerRepo> =
+        Arc::new(SqliteMcpServerRepo::new(db_pool.clone()));
+    let tool_repo: Arc<dyn ToolRepo> = Arc::new(SqliteToolRepo::new(db_pool.clone()));
+    let knowledge_repo: Arc<dyn KnowledgeRepo> =
+        Arc::new(SqliteKnowledgeRepo::new(db_pool.clone()));
+    let retention_repo: Arc<dyn RetentionRepo> =
+        Arc::new(SqliteRetentionRepo::new(db_pool.clone()));
+    let sync_repo: Arc<dyn SyncRepo> = Arc::new(SqliteSyncRepo::new(db_pool.clone()));
+    let tool_registry = Arc::new(ToolRegistry::new());
+    tauri::async_runtime::block_on(async {
+        tool_registry.register(Arc::new(CodeExecutionTool)).await;
+        tool_registry
+            .register(Arc::new(ReadFileTool::new(tool_repo.clone())))
+            .await;
+        tool_registry
+            .register(Arc::new(ListDirectoryTool::new(tool_repo.clone())))
+            .await;
+        tool_registry.register(Arc::new(ClockTool)).await;
+        tool_registry.register(Arc::new(CalculatorTool)).await;
+    });
+    let tool_approvals = Arc::new(ToolApprovals::new());
+    let mcp_manager = Arc::new(McpManager::new(tool_registry.clone()));
 
-    tauri::async_runtime::block_on(async { sqlx::migrate!("./migrations").run(&*db_pool).await })
-        .expect("failed to run migrations");
+    tauri::async_runtime::block_on(async { sqlx::migrate!("./migrations").run(&*db_pool).await })?;
+    let database_health = Arc::new(DatabaseHealth::new(tauri::async_runtime::block_on(
+        database::check_health(&db_pool),
+    )));
 
-    app.manage(AgentContext::new(
+    let chat_encryption = Arc::new(tauri::async_runtime::block_on(ChatEncryption::load(
+        cipher.clone(),
+        Arc::new(SqliteChatRepo::new(db_pool.clone())),
+    ))?);
+    let chat_search_index = Arc::new(ChatSearchIndex::new());
+    let chat_repo: Arc<dyn ChatRepo> = Arc::new(EncryptedChatRepo::new(
+        Box::new(SqliteChatRepo::new(db_pool.clone())),
+        chat_encryption.clone(),
+        chat_search_index.clone(),
+    ));
+    let unit_of_work_factory: Arc<dyn UnitOfWorkFactory> = Arc::new(SqliteUnitOfWorkFactory::new(
+        db_pool.clone(),
+        chat_encryption.clone(),
+        chat_search_index.clone(),
+    ));
+    {
+        let chat_encryption = chat_encryption.clone();
+        let chat_search_index = chat_search_index.clone();
+        let chat_repo = chat_repo.clone();
+        tauri::async_runtime::spawn(async move {
+            match chat_encryption.reseal_all().await {
+                Ok(0) => {}
+                Ok(count) => log::info!("migrated {} chat values to the encryption setting", count),
+                Err(e) => log::error!("failed to migrate chat encryption: {}", e),
+            }
+            if let Err(e) = chat_search_index.rebuild(&*chat_repo).await {
+                log::error!("failed to build chat search index: {}", e);
+            }
+        });
+    }
+
+    let agent_context = AgentContext::new(
         http_client_manager.clone(),
         agent_repo.clone(),
         chat_repo.clone(),
+        project_repo.clone(),
+        tool_registry.clone(),
         cipher.clone(),
+    );
+    let knowledge_indexer = Arc::new(KnowledgeIndexer::new(
+        knowledge_repo.clone(),
+        agent_context.clone(),
     ));
+    let chat_embedding_indexer = Arc::new(ChatEmbeddingIndexer::new(agent_context.clone()));
+    app.manage(agent_context);
+    let event_bus = Arc::new(EventBus::new());
+    event_bus.subscribe(Arc::new(TauriEventEmitter::new(app.handle().clone())));
+    event_bus.subscribe(Arc::new(EventLogger));
+    event_bus.subscribe(chat_embedding_indexer.clone());
+    let chat_tasks = Arc::new(ChatTasks::new());
+    {
+        let event_bus = event_bus.clone();
+        let chat_repo = chat_repo.clone();
+        let chat_tasks = chat_tasks.clone();
+        tauri::async_runtime::spawn(async move {
+            match recovery::recover_pending_chat_messages(&event_bus, &chat_repo, &chat_tasks).await
+            {
+                Ok(0) => {}
+                Ok(count) => log::info!("marked {} interrupted chat messages as failed", count),
+                Err(e) => log::error!("failed to recover pending chat messages: {}", e),
+            }
+        });
+    }
+    let database_backups = Arc::new(DatabaseBackups::new(db_pool.clone(), db_path));
+    database_backups.spawn_schedule();
     app.manage(db_pool);
+    app.manage(profiles);
+    app.manage(database_health);
+    app.manage(database_backups);
+    app.manage(chat_tasks);
+    let retention = Arc::new(Retention::new(
+        retention_repo.clone(),
+        chat_repo.clone(),
+        unit_of_work_factory.clone(),
+        event_bus.clone(),
+    ));
+    retention.spawn_schedule();
+    app.manage(retention);
+    app.manage(retention_repo);
+    let sync = Arc::new(ChatSync::new(
+        sync_repo.clone(),
+        chat_repo.clone(),
+        agent_repo.clone(),
+        cipher.clone(),
+        profile.sync_keyring_user(),
+        event_bus.clone(),
+    ));
+    sync.spawn_schedule();
+    app.manage(sync);
+    app.manage(sync_repo);
+    app.manage(chat_encryption);
+    app.manage(chat_search_index);
     app.manage(cipher);
     app.manage(chat_repo);
     app.manage(agent_repo);
+    app.manage(project_repo);
     app.manage(unit_of_work_factory);
+    app.manage(tool_registry);
+    app.manage(tool_approvals);
+    app.manage(tool_repo);
+
+    let servers =
+        tauri::async_runtime::block_on(async { mcp_server_repo.get_mcp_servers().await })?;
+    for server in servers.into_iter().filter(|a| a.enabled) {
+        mcp::cmds::spawn_connect(mcp_manager.clone(), server);
+    }
+    app.manage(mcp_server_repo);
+    app.manage(mcp_manager);
+
+    let knowledge_folders =
+        tauri::async_runtime::block_on(async { knowledge_repo.get_knowledge_folders().await })?;
+    for folder in knowledge_folders {
+        knowledge_indexer.spawn_index(app.handle().clone(), folder.id);
+    }
+    app.manage(knowledge_repo);
+    app.manage(knowledge_indexer);
+
+    chat_embedding_indexer.spawn();
+    app.manage(chat_embedding_indexer);
+    app.manage(event_bus);
     Ok(())
 }
 
-fn setup_keyring() -> Result<(), Box<dyn std::error::Error>> {
-    let entry = Entry::new("askkit", "local")?;
+fn setup_keyring(keyring_user: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let entry = Entry::new(KEYRING_SERVICE, keyring_user)?;
     if let Err(e) = entry.get_secret() {
         if let keyring::Error::NoEntry = e {
             let mut key = [0u8; 32];
@@ -207,7 +431,6 @@ fn setup(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
     setup_dependencies(app)?;
     setup_tray_icon(app)?;
     setup_global_shortcut(app)?;
-    setup_keyring()?;
     Ok(())
 }
 
@@ -0,0 +1,227 @@
+pub mod client;
+pub mod cmds;
+pub mod repo;
+
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    common::{entity::mcp::McpServerRow, error::AppError},
+    mcp::client::{McpClient, McpToolInfo},
+    tool::{Tool, ToolDefinition, ToolRegistry},
+};
+
+const TOOL_NAME_MAX_LEN: usize = 64;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum McpConnectionStatus {
+    Disconnected,
+    Connecting,
+    Connected,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerConnection {
+    pub server_id: Uuid,
+    pub status: McpConnectionStatus,
+    pub error: Option<String>,
+    pub server_name: Option<String>,
+    pub server_version: Option<String>,
+    pub tools: Vec<String>,
+}
+
+struct McpServerState {
+    connection: McpServerConnection,
+    client: Option<Arc<McpClient>>,
+}
+
+/// Owns the running MCP server processes and keeps the tool registry in sync with the
+/// tools each connected server exposes.
+pub struct McpManager {
+    servers: RwLock<HashMap<Uuid, McpServerState>>,
+    tool_registry: Arc<ToolRegistry>,
+}
+
+impl McpManager {
+    pub fn new(tool_registry: Arc<ToolRegistry>) -> Self {
+        Self {
+            servers: RwLock::new(HashMap::new()),
+            tool_registry,
+        }
+    }
+
+    pub async fn connect(&self, server: &McpServerRow) -> McpServerConnection {
+        self.disconnect(server.id).await;
+        self.servers.write().await.insert(
+            server.id,
+            McpServerState {
+                connection: McpServerConnection {
+                    server_id: server.id,
+                    status: McpConnectionStatus::Connecting,
+                    error: None,
+                    server_name: None,
+                    server_version: None,
+                    tools: Vec::new(),
+                },
+                client: None,
+            },
+        );
+
+        let state = match self.start(server).await {
+            Ok(state) => state,
+            Err(e) => {
+                log::error!("failed to connect mcp server {}: {}", server.name, e);
+                McpServerState {
+                    connection: McpServerConnection {
+                        server_id: server.id,
+                        status: McpConnectionStatus::Failed,
+                        error: Some(e.to_string()),
+                        server_name: None,
+                        server_version: None,
+                        tools: Vec::new(),
+                    },
+                    client: None,
+                }
+            }
+        };
+        let connection = state.connection.clone();
+        self.servers.write().await.insert(server.id, state);
+        connection
+    }
+
+    pub async fn disconnect(&self, server_id: Uuid) {
+        let state = self.servers.write().await.remove(&server_id);
+        if let Some(state) = state {
+            for name in &state.connection.tools {
+                self.tool_registry.unregister(name).await;
+            }
+            if let Some(client) = state.client {
+                client.shutdown().await;
+            }
+        }
+    }
+
+    pub async fn connections(&self) -> Vec<McpServerConnection> {
+        self.refresh().await;
+        self.servers
+            .read()
+            .await
+            .values()
+            .map(|a| a.connection.clone())
+            .collect()
+    }
+
+    pub async fn client(&self, server_id: Uuid) -> Result<Arc<McpClient>, AppError> {
+        self.refresh().await;
+        self.servers
+            .read()
+            .await
+            .get(&server_id)
+            .and_then(|a| a.client.clone())
+            .ok_or_else(|| AppError::Mcp("server is not connected".into()))
+    }
+
+    async fn start(&self, server: &McpServerRow) -> Result<McpServerState, AppError> {
+        let (client, info) = McpClient::connect(&server.command, &server.args, &server.env).await?;
+        let client = Arc::new(client);
+        let tools = match info.capabilities.tools {
+            Some(_) => client.list_tools().await?,
+            None => Vec::new(),
+        };
+        let mut names = Vec::with_capacity(tools.len());
+        for tool in tools {
+            let name = mcp_tool_name(&server.name, &tool.name);
+            self.tool_registry
+                .register(Arc::new(McpTool {
+                    name: name.clone(),
+                    client: client.clone(),
+                    tool,
+                }))
+                .await;
+            names.push(name);
+        }
+        Ok(McpServerState {
+            connection: McpServerConnection {
+                server_id: server.id,
+                status: McpConnectionStatus::Connected,
+                error: None,
+                server_name: Some(info.server_info.name),
+                server_version: Some(info.server_info.version),
+                tools: names,
+            },
+            client: Some(client),
+        })
+    }
+
+    /// Marks servers whose process has exited as failed and withdraws their tools.
+    async fn refresh(&self) {
+        let mut servers = self.servers.write().await;
+        for state in servers.values_mut() {
+            let exited = state.client.as_ref().is_some_and(|a| a.is_closed());
+            if !exited {
+                continue;
+            }
+            for name in state.connection.tools.drain(..) {
+                self.tool_registry.unregister(&name).await;
+            }
+            state.client = None;
+            state.connection.status = McpConnectionStatus::Failed;
+            state.connection.error = Some("server process exited".into());
+        }
+    }
+}
+
+struct McpTool {
+    name: String,
+    client: Arc<McpClient>,
+    tool: McpToolInfo,
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.name.clone(),
+            description: self.tool.description.clone().unwrap_or_default(),
+            parameters: self.tool.input_schema.clone(),
+        }
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, AppError> {
+        let result = self.client.call_tool(&self.tool.name, arguments).await?;
+        let text = result
+            .content
+            .iter()
+            .map(|a| match a.get("text").and_then(Value::as_str) {
+                Some(text) => text.to_string(),
+                None => a.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if result.is_error {
+            return Err(AppError::Mcp(text));
+        }
+        Ok(result.structured_content.unwrap_or(Value::String(text)))
+    }
+}
+
+/// Prefixes tool names with their server so two servers can expose tools with the same
+/// name, and keeps them within the character set and length providers accept.
+fn mcp_tool_name(server_name: &str, tool_name: &str) -> String {
+    format!("{}__{}", server_name, tool_name)
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => c,
+            _ => '_',
+        })
+        .take(TOOL_NAME_MAX_LEN)
+        .collect()
+}
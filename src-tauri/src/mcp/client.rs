@@ -0,0 +1,442 @@
+use std::{
+    collections::HashMap,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStderr, ChildStdin, ChildStdout, Command},
+    sync::{oneshot, Mutex},
+};
+
+use crate::common::error::AppError;
+
+const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
+const MCP_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+type McpPendingRequests = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, AppError>>>>>;
+
+pub struct McpClient {
+    child: Mutex<Child>,
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: McpPendingRequests,
+    closed: Arc<AtomicBool>,
+    next_id: AtomicU64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct McpInitializeResult {
+    pub protocol_version: String,
+    #[serde(default)]
+    pub capabilities: McpServerCapabilities,
+    pub server_info: McpImplementation,
+    pub instructions: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct McpServerCapabilities {
+    pub tools: Option<Value>,
+    pub prompts: Option<Value>,
+    pub resources: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct McpImplementation {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolInfo {
+    pub name: String,
+    pub description: Option<String>,
+    pub input_schema: Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct McpCallToolResult {
+    #[serde(default)]
+    pub content: Vec<Value>,
+    pub structured_content: Option<Value>,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct McpPrompt {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct McpPromptArgument {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct McpGetPromptResult {
+    pub description: Option<String>,
+    pub messages: Vec<McpPromptMessage>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct McpPromptMessage {
+    pub role: String,
+    pub content: Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct McpReadResourceResult {
+    pub contents: Vec<McpResourceContents>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResourceContents {
+    pub uri: String,
+    pub mime_type: Option<String>,
+    pub text: Option<String>,
+    pub blob: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct McpJsonRpcError {
+    code: i64,
+    message: String,
+}
+
+impl McpClient {
+    /// Launches the server and performs the MCP initialize handshake. Messages are
+    /// newline-delimited JSON-RPC over the child's stdin and stdout; stderr is logged.
+    pub async fn connect(
+        command: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+    ) -> Result<(Self, McpInitializeResult), AppError> {
+        let mut child = Command::new(command)
+            .args(args)
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(AppError::from)?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| AppError::Mcp("server stdin is not available".into()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| AppError::Mcp("server stdout is not available".into()))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| AppError::Mcp("server stderr is not available".into()))?;
+
+        let client = Self {
+            child: Mutex::new(child),
+            stdin: Arc::new(Mutex::new(stdin)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            closed: Arc::new(AtomicBool::new(false)),
+            next_id: AtomicU64::new(1),
+        };
+        tokio::spawn(read_stdout(
+            stdout,
+            client.stdin.clone(),
+            client.pending.clone(),
+            client.closed.clone(),
+        ));
+        tokio::spawn(read_stderr(command.to_string(), stderr));
+
+        let result = client
+            .request::<McpInitializeResult>(
+                "initialize",
+                json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "askkit",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            )
+            .await?;
+        client.notify("notifications/initialized").await?;
+        Ok((client, result))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    pub async fn shutdown(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        let _ = self.child.lock().await.kill().await.inspect_err(|e| {
+            log::error!("failed to kill mcp server: {e}");
+        });
+    }
+
+    pub async fn list_tools(&self) -> Result<Vec<McpToolInfo>, AppError> {
+        self.list_all("tools/list", "tools").await
+    }
+
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> Result<McpCallToolResult, AppError> {
+        self.request(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        )
+        .await
+    }
+
+    pub async fn list_prompts(&self) -> Result<Vec<McpPrompt>, AppError> {
+        self.list_all("prompts/list", "prompts").await
+    }
+
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: HashMap<String, String>,
+    ) -> Result<McpGetPromptResult, AppError> {
+        self.request(
+            "prompts/get",
+            json!({ "name": name, "arguments": arguments }),
+        )
+        .await
+    }
+
+    pub async fn list_resources(&self) -> Result<Vec<McpResource>, AppError> {
+        self.list_all("resources/list", "resources").await
+    }
+
+    pub async fn read_resource(&self, uri: &str) -> Result<McpReadResourceResult, AppError> {
+        self.request("resources/read", json!({ "uri": uri })).await
+    }
+
+    async fn list_all<T>(&self, method: &str, key: &str) -> Result<Vec<T>, AppError>
+    where
+        T: DeserializeOwned,
+    {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let mut result = self.request::<Value>(method, params).await?;
+            let page = result.get_mut(key).map(Value::take).unwrap_or_default();
+            items.extend(serde_json::from_value::<Vec<T>>(page).map_err(AppError::from)?);
+            cursor = result
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(String::from);
+            if cursor.is_none() {
+                return Ok(items);
+            }
+        }
+    }
+
+    async fn request<T>(&self, method: &str, params: Value) -> Result<T, AppError>
+    where
+        T: DeserializeOwned,
+    {
+        if self.is_closed() {
+            return Err(AppError::Mcp("server is not running".into()));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(id, tx);
+        write_message(
+            &self.stdin,
+            &json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+        )
+        .await?;
+        let result = match tokio::time::timeout(MCP_REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result?,
+            Ok(Err(_)) => return Err(AppError::Mcp("server closed the connection".into())),
+            Err(_) => {
+                self.pending.lock().await.remove(&id);
+                return Err(AppError::Mcp(format!("{} timed out", method)));
+            }
+        };
+        serde_json::from_value(result).map_err(AppError::from)
+    }
+
+    async fn notify(&self, method: &str) -> Result<(), AppError> {
+        write_message(&self.stdin, &json!({ "jsonrpc": "2.0", "method": method })).await
+    }
+}
+
+async fn write_message(stdin: &Mutex<ChildStdin>, message: &Value) -> Result<(), AppError> {
+    let mut line = serde_json::to_vec(message).map_err(AppError::from)?;
+    line.push(b'\n');
+    let mut stdin = stdin.lock().await;
+    stdin.write_all(&line).await.map_err(AppError::from)?;
+    stdin.flush().await.map_err(AppError::from)
+}
+
+async fn read_stdout(
+    stdout: ChildStdout,
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: McpPendingRequests,
+    closed: Arc<AtomicBool>,
+) {
+    let mut lines = BufReader::new(stdout).lines();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                log::error!("failed to read from mcp server: {e}");
+                break;
+            }
+        };
+        let message = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => message,
+            Err(_) => {
+                log::debug!("ignoring non json-rpc output from mcp server: {line}");
+                continue;
+            }
+        };
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str);
+        match (id, method) {
+            (Some(id), Some(method)) => {
+                // Requests from the server. Only ping is supported since we advertise no
+                // client capabilities.
+                let response = match method {
+                    "ping" => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+                    _ => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32601, "message": "Method not found" },
+                    }),
+                };
+                let _ = write_message(&stdin, &response).await.inspect_err(|e| {
+                    log::error!("failed to respond to mcp server request: {e}");
+                });
+            }
+            (None, Some(method)) => {
+                log::debug!("mcp server notification: {method}");
+            }
+            (Some(id), None) => {
+                let sender = match id.as_u64() {
+                    Some(id) => pending.lock().await.remove(&id),
+                    None => None,
+                };
+                let Some(sender) = sender else {
+                    continue;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(
+                        match serde_json::from_value::<McpJsonRpcError>(error.clone()) {
+                            Ok(error) => {
+                                AppError::Mcp(format!("{} ({})", error.message, error.code))
+                            }
+                            Err(_) => AppError::Mcp(error.to_string()),
+                        },
+                    ),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(result);
+            }
+            (None, None) => {}
+        }
+    }
+    closed.store(true, Ordering::SeqCst);
+    pending.lock().await.clear();
+}
+
+async fn read_stderr(command: String, stderr: ChildStderr) {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        log::debug!("[{command}] {line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Vec<String> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("mcp_server.mjs");
+        vec![path.to_string_lossy().into_owned()]
+    }
+
+    #[tokio::test]
+    #[ignore = "needs node on PATH, run with --ignored"]
+    async fn talks_to_fixture_server() {
+        let (client, info) = McpClient::connect("node", &fixture(), &HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(info.server_info.name, "askkit-fixture");
+        assert!(info.capabilities.tools.is_some());
+
+        let tools = client.list_tools().await.unwrap();
+        let names = tools.iter().map(|a| a.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["echo", "add"]);
+
+        let result = client
+            .call_tool("add", json!({ "a": 2, "b": 3 }))
+            .await
+            .unwrap();
+        assert!(!result.is_error);
+        assert_eq!(result.content[0]["text"], "5");
+
+        let result = client.call_tool("missing", json!({})).await.unwrap();
+        assert!(result.is_error);
+
+        let prompts = client.list_prompts().await.unwrap();
+        assert_eq!(prompts[0].name, "greet");
+        let prompt = client
+            .get_prompt("greet", HashMap::from([("name".into(), "Ada".into())]))
+            .await
+            .unwrap();
+        assert_eq!(prompt.messages[0].content["text"], "Say hello to Ada.");
+
+        let resources = client.list_resources().await.unwrap();
+        let contents = client.read_resource(&resources[0].uri).await.unwrap();
+        assert_eq!(contents.contents[0].text.as_deref(), Some("fixture notes"));
+
+        assert!(client
+            .request::<Value>("unknown/method", json!({}))
+            .await
+            .is_err());
+
+        client.shutdown().await;
+        assert!(client.is_closed());
+    }
+}
@@ -0,0 +1,178 @@
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    common::{entity::mcp::McpServerRow, error::AppError},
+    mcp::{
+        client::{McpGetPromptResult, McpPrompt, McpReadResourceResult, McpResource},
+        repo::{CreateMcpServer, McpServerRepo, UpdateMcpServer},
+        McpManager, McpServerConnection,
+    },
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMcpServerCmd {
+    pub name: String,
+    pub command: String,
+    pub args: Option<Vec<String>>,
+    pub env: Option<HashMap<String, String>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateMcpServerCmd {
+    pub name: Option<String>,
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub env: Option<HashMap<String, String>>,
+    pub enabled: Option<bool>,
+}
+
+#[tauri::command]
+pub async fn get_mcp_servers(
+    mcp_server_repo: State<'_, Arc<dyn McpServerRepo>>,
+) -> Result<Vec<McpServerRow>, AppError> {
+    mcp_server_repo.get_mcp_servers().await
+}
+
+#[tauri::command]
+pub async fn create_mcp_server(
+    create: CreateMcpServerCmd,
+    mcp_server_repo: State<'_, Arc<dyn McpServerRepo>>,
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<McpServerRow, AppError> {
+    let server = mcp_server_repo
+        .create_mcp_server(CreateMcpServer {
+            id: Uuid::new_v4(),
+            name: create.name.trim().to_string(),
+            command: create.command.trim().to_string(),
+            args: create.args.unwrap_or_default(),
+            env: create.env.unwrap_or_default(),
+            enabled: create.enabled.unwrap_or(true),
+        })
+        .await?;
+    if server.enabled {
+        spawn_connect(mcp_manager.inner().clone(), server.clone());
+    }
+    Ok(server)
+}
+
+#[tauri::command]
+pub async fn update_mcp_server(
+    id: Uuid,
+    update: UpdateMcpServerCmd,
+    mcp_server_repo: State<'_, Arc<dyn McpServerRepo>>,
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<u64, AppError> {
+    let affected = mcp_server_repo
+        .update_mcp_server(
+            id,
+            UpdateMcpServer {
+                name: update.name.map(|a| a.trim().to_string()),
+                command: update.command.map(|a| a.trim().to_string()),
+                args: update.args,
+                env: update.env,
+                enabled: update.enabled,
+            },
+        )
+        .await?;
+    if affected > 0 {
+        mcp_manager.disconnect(id).await;
+        if let Some(server) = mcp_server_repo
+            .get_mcp_server(id)
+            .await?
+            .filter(|a| a.enabled)
+        {
+            spawn_connect(mcp_manager.inner().clone(), server);
+        }
+    }
+    Ok(affected)
+}
+
+#[tauri::command]
+pub async fn delete_mcp_server(
+    id: Uuid,
+    mcp_server_repo: State<'_, Arc<dyn McpServerRepo>>,
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<u64, AppError> {
+    mcp_manager.disconnect(id).await;
+    mcp_server_repo.delete_mcp_server(id).await
+}
+
+#[tauri::command]
+pub async fn connect_mcp_server(
+    id: Uuid,
+    mcp_server_repo: State<'_, Arc<dyn McpServerRepo>>,
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<McpServerConnection, AppError> {
+    let server = mcp_server_repo
+        .get_mcp_server(id)
+        .await?
+        .ok_or_else(|| AppError::Mcp(format!("server not found: {}", id)))?;
+    Ok(mcp_manager.connect(&server).await)
+}
+
+#[tauri::command]
+pub async fn disconnect_mcp_server(
+    id: Uuid,
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<(), AppError> {
+    mcp_manager.disconnect(id).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_mcp_server_connections(
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<Vec<McpServerConnection>, AppError> {
+    Ok(mcp_manager.connections().await)
+}
+
+#[tauri::command]
+pub async fn get_mcp_prompts(
+    id: Uuid,
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<Vec<McpPrompt>, AppError> {
+    mcp_manager.client(id).await?.list_prompts().await
+}
+
+#[tauri::command]
+pub async fn get_mcp_prompt(
+    id: Uuid,
+    name: String,
+    arguments: Option<HashMap<String, String>>,
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<McpGetPromptResult, AppError> {
+    mcp_manager
+        .client(id)
+        .await?
+        .get_prompt(&name, arguments.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+pub async fn get_mcp_resources(
+    id: Uuid,
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<Vec<McpResource>, AppError> {
+    mcp_manager.client(id).await?.list_resources().await
+}
+
+#[tauri::command]
+pub async fn read_mcp_resource(
+    id: Uuid,
+    uri: String,
+    mcp_manager: State<'_, Arc<McpManager>>,
+) -> Result<McpReadResourceResult, AppError> {
+    mcp_manager.client(id).await?.read_resource(&uri).await
+}
+
+pub fn spawn_connect(mcp_manager: Arc<McpManager>, server: McpServerRow) {
+    tauri::async_runtime::spawn(async move {
+        mcp_manager.connect(&server).await;
+    });
+}
@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::common::{entity::mcp::McpServerRow, error::AppError};
+
+pub mod sqlite;
+
+pub struct CreateMcpServer {
+    pub id: Uuid,
+    pub name: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub enabled: bool,
+}
+
+#[derive(Default)]
+pub struct UpdateMcpServer {
+    pub name: Option<String>,
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub env: Option<HashMap<String, String>>,
+    pub enabled: Option<bool>,
+}
+
+#[async_trait]
+pub trait McpServerRepo: Send + Sync {
+    async fn get_mcp_servers(&self) -> Result<Vec<McpServerRow>, AppError>;
+    async fn get_mcp_server(&self, id: Uuid) -> Result<Option<McpServerRow>, AppError>;
+    async fn create_mcp_server(&self, create: CreateMcpServer) -> Result<McpServerRow, AppError>;
+    async fn update_mcp_server(&self, id: Uuid, update: UpdateMcpServer) -> Result<u64, AppError>;
+    async fn delete_mcp_server(&self, id: Uuid) -> Result<u64, AppError>;
+}
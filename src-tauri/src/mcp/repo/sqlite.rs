@@ -0,0 +1,143 @@
+use async_trait::async_trait;
+use sqlx::{types::Json, Executor, Pool, QueryBuilder, Sqlite};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    common::{entity::mcp::McpServerRow, error::AppError},
+    mcp::repo::{CreateMcpServer, McpServerRepo, UpdateMcpServer},
+};
+
+pub struct SqliteMcpServerRepo {
+    db_pool: Arc<Pool<Sqlite>>,
+}
+
+impl SqliteMcpServerRepo {
+    pub fn new(db_pool: Arc<Pool<Sqlite>>) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl McpServerRepo for SqliteMcpServerRepo {
+    async fn get_mcp_servers(&self) -> Result<Vec<McpServerRow>, AppError> {
+        get_mcp_servers(&*self.db_pool).await
+    }
+
+    async fn get_mcp_server(&self, id: Uuid) -> Result<Option<McpServerRow>, AppError> {
+        get_mcp_server(&*self.db_pool, id).await
+    }
+
+    async fn create_mcp_server(&self, create: CreateMcpServer) -> Result<McpServerRow, AppError> {
+        create_mcp_server(&*self.db_pool, create).await
+    }
+
+    async fn update_mcp_server(&self, id: Uuid, update: UpdateMcpServer) -> Result<u64, AppError> {
+        update_mcp_server(&*self.db_pool, id, update).await
+    }
+
+    async fn delete_mcp_server(&self, id: Uuid) -> Result<u64, AppError> {
+        delete_mcp_server(&*self.db_pool, id).await
+    }
+}
+
+async fn get_mcp_servers<'a, E>(executor: E) -> Result<Vec<McpServerRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, McpServerRow>("select * from mcp_servers order by name asc")
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn get_mcp_server<'a, E>(executor: E, id: Uuid) -> Result<Option<McpServerRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, McpServerRow>("select * from mcp_servers where id = ?1")
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn create_mcp_server<'a, E>(
+    executor: E,
+    create: CreateMcpServer,
+) -> Result<McpServerRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let (created_at, updated_at): (i64, i64) = sqlx::query_as("insert into mcp_servers (id, name, command, args, env, enabled) values (?1, ?2, ?3, ?4, ?5, ?6) returning created_at, updated_at")
+        .bind(create.id)
+        .bind(&create.name)
+        .bind(&create.command)
+        .bind(Json(&create.args))
+        .bind(Json(&create.env))
+        .bind(create.enabled)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(McpServerRow {
+        created_at,
+        updated_at,
+        id: create.id,
+        name: create.name,
+        command: create.command,
+        args: Json(create.args),
+        env: Json(create.env),
+        enabled: create.enabled,
+    })
+}
+
+async fn update_mcp_server<'a, E>(
+    executor: E,
+    id: Uuid,
+    update: UpdateMcpServer,
+) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    if update.name.is_none()
+        && update.command.is_none()
+        && update.args.is_none()
+        && update.env.is_none()
+        && update.enabled.is_none()
+    {
+        return Ok(0);
+    }
+    let mut qb = QueryBuilder::new("update mcp_servers set ");
+    let mut sep = qb.separated(", ");
+    if let Some(name) = update.name {
+        sep.push("name = ").push_bind_unseparated(name);
+    }
+    if let Some(command) = update.command {
+        sep.push("command = ").push_bind_unseparated(command);
+    }
+    if let Some(args) = update.args {
+        sep.push("args = ").push_bind_unseparated(Json(args));
+    }
+    if let Some(env) = update.env {
+        sep.push("env = ").push_bind_unseparated(Json(env));
+    }
+    if let Some(enabled) = update.enabled {
+        sep.push("enabled = ").push_bind_unseparated(enabled);
+    }
+    qb.push(" where id = ").push_bind(id);
+
+    let result = qb.build().execute(executor).await.map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn delete_mcp_server<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from mcp_servers where id = ?1")
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
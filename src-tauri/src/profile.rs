@@ -0,0 +1,223 @@
+pub mod cmds;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::common::error::AppError;
+
+const PROFILES_FILE: &str = "profiles.json";
+const PROFILES_DIR: &str = "profiles";
+const DATABASE_FILE: &str = "askkit.db";
+pub const KEYRING_SERVICE: &str = "askkit";
+/// The keyring entry and database of installs from before profiles, kept by the
+/// profile created for them.
+const LEGACY_KEYRING_USER: &str = "local";
+
+/// An isolated set of chats, agents and settings. `database` is relative to the app's
+/// local data directory.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: Uuid,
+    pub name: String,
+    pub database: PathBuf,
+    pub keyring_user: String,
+    pub created_at: i64,
+}
+
+impl Profile {
+    /// The keyring entry holding the key that seals this profile's sync logs. It's
+    /// separate from the local key so it can be shared with other devices.
+    pub fn sync_keyring_user(&self) -> String {
+        format!("{}-sync", self.keyring_user)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileList {
+    pub active_profile_id: Uuid,
+    pub profiles: Vec<Profile>,
+}
+
+/// The profile list, kept in `profiles.json` outside any profile's database. The app
+/// runs one profile at a time; switching saves the choice and restarts, so every pool,
+/// repo and `AgentContext` is created anew for the other profile.
+pub struct Profiles {
+    app_dir: PathBuf,
+    active: Profile,
+    list: Mutex<ProfileList>,
+}
+
+impl Profiles {
+    pub fn load(app_dir: &Path) -> Result<Self, AppError> {
+        let path = app_dir.join(PROFILES_FILE);
+        let list = if path.exists() {
+            serde_json::from_str::<ProfileList>(&std::fs::read_to_string(&path)?)?
+        } else {
+            let profile = Profile {
+                id: Uuid::new_v4(),
+                name: "Default".into(),
+                database: PathBuf::from(DATABASE_FILE),
+                keyring_user: LEGACY_KEYRING_USER.into(),
+                created_at: now_millis()?,
+            };
+            let list = ProfileList {
+                active_profile_id: profile.id,
+                profiles: vec![profile],
+            };
+            save(app_dir, &list)?;
+            list
+        };
+        let active = list
+            .profiles
+            .iter()
+            .find(|a| a.id == list.active_profile_id)
+            .or(list.profiles.first())
+            .cloned()
+            .ok_or_else(|| AppError::InvalidProfile("no profiles".into()))?;
+        Ok(Self {
+            app_dir: app_dir.to_path_buf(),
+            active,
+            list: Mutex::new(list),
+        })
+    }
+
+    /// The profile the app is running with.
+    pub fn active(&self) -> &Profile {
+        &self.active
+    }
+
+    pub fn database_path(&self, profile: &Profile) -> PathBuf {
+        self.app_dir.join(&profile.database)
+    }
+
+    pub fn get_profiles(&self) -> Result<ProfileList, AppError> {
+        Ok(self.list.lock().unwrap().clone())
+    }
+
+    pub fn create_profile(&self, name: &str) -> Result<Profile, AppError> {
+        let name = validate_name(name)?;
+        let id = Uuid::new_v4();
+        let profile = Profile {
+            id,
+            name,
+            database: Path::new(PROFILES_DIR)
+                .join(id.to_string())
+                .join(DATABASE_FILE),
+            keyring_user: format!("profile-{}", id),
+            created_at: now_millis()?,
+        };
+        let mut list = self.list.lock().unwrap();
+        list.profiles.push(profile.clone());
+        save(&self.app_dir, &list)?;
+        Ok(profile)
+    }
+
+    pub fn rename_profile(&self, id: Uuid, name: &str) -> Result<Profile, AppError> {
+        let name = validate_name(name)?;
+        let mut list = self.list.lock().unwrap();
+        let profile = list
+            .profiles
+            .iter_mut()
+            .find(|a| a.id == id)
+            .ok_or(AppError::ProfileNotFound(id))?;
+        profile.name = name;
+        let profile = profile.clone();
+        save(&self.app_dir, &list)?;
+        Ok(profile)
+    }
+
+    /// Selects the profile to start with next time; the caller restarts the app.
+    pub fn set_active_profile(&self, id: Uuid) -> Result<(), AppError> {
+        let mut list = self.list.lock().unwrap();
+        if !list.profiles.iter().any(|a| a.id == id) {
+            return Err(AppError::ProfileNotFound(id));
+        }
+        list.active_profile_id = id;
+        save(&self.app_dir, &list)
+    }
+
+    /// Removes a profile that is not in use, along with its database, backups and keys.
+    pub fn delete_profile(&self, id: Uuid) -> Result<(), AppError> {
+        if id == self.active.id {
+            return Err(AppError::InvalidProfile(
+                "the active profile can't be deleted".into(),
+            ));
+        }
+        let mut list = self.list.lock().unwrap();
+        if list.active_profile_id == id {
+            return Err(AppError::InvalidProfile(
+                "the profile selected for the next start can't be deleted".into(),
+            ));
+        }
+        let i = list
+            .profiles
+            .iter()
+            .position(|a| a.id == id)
+            .ok_or(AppError::ProfileNotFound(id))?;
+        let profile = list.profiles.remove(i);
+        save(&self.app_dir, &list)?;
+        drop(list);
+
+        let database_path = self.database_path(&profile);
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = database_path.as_os_str().to_owned();
+            path.push(suffix);
+            remove_if_exists(Path::new(&path), false)?;
+        }
+        if let Some(dir) = database_path.parent() {
+            remove_if_exists(&dir.join("backups"), true)?;
+            if dir.starts_with(self.app_dir.join(PROFILES_DIR)) {
+                remove_if_exists(dir, true)?;
+            }
+        }
+        for keyring_user in [profile.keyring_user.clone(), profile.sync_keyring_user()] {
+            match Entry::new(KEYRING_SERVICE, &keyring_user)?.delete_credential() {
+                Ok(()) | Err(keyring::Error::NoEntry) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidProfile("name is required".into()));
+    }
+    Ok(name.to_string())
+}
+
+/// Writes through a temporary file so a crash can't leave a truncated list.
+fn save(app_dir: &Path, list: &ProfileList) -> Result<(), AppError> {
+    let path = app_dir.join(PROFILES_FILE);
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(list)?)?;
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path, dir: bool) -> Result<(), AppError> {
+    let result = if dir {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    match result {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn now_millis() -> Result<i64, AppError> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64)
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use sqlx::{Pool, Sqlite};
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use crate::{
+    chat::recovery::ChatTasks,
+    common::error::AppError,
+    profile::{Profile, ProfileList, Profiles},
+};
+
+#[tauri::command]
+pub async fn get_profiles(profiles: State<'_, Arc<Profiles>>) -> Result<ProfileList, AppError> {
+    profiles.get_profiles()
+}
+
+#[tauri::command]
+pub async fn create_profile(
+    name: String,
+    profiles: State<'_, Arc<Profiles>>,
+) -> Result<Profile, AppError> {
+    profiles.create_profile(&name)
+}
+
+#[tauri::command]
+pub async fn rename_profile(
+    id: Uuid,
+    name: String,
+    profiles: State<'_, Arc<Profiles>>,
+) -> Result<Profile, AppError> {
+    profiles.rename_profile(id, &name)
+}
+
+#[tauri::command]
+pub async fn delete_profile(id: Uuid, profiles: State<'_, Arc<Profiles>>) -> Result<(), AppError> {
+    profiles.delete_profile(id)
+}
+
+/// Restarts the app into the profile, which re-creates the database pool, repos and
+/// agent context from its own database and key. Refused while a response is running,
+/// since the restart would cut it off along with any tool approval it waits on.
+#[tauri::command]
+pub async fn switch_profile(
+    id: Uuid,
+    app_handle: AppHandle,
+    db_pool: State<'_, Arc<Pool<Sqlite>>>,
+    profiles: State<'_, Arc<Profiles>>,
+    chat_tasks: State<'_, Arc<ChatTasks>>,
+) -> Result<(), AppError> {
+    if id == profiles.active().id {
+        return Ok(());
+    }
+    if chat_tasks.has_live() {
+        return Err(AppError::ChatResponsesInProgress);
+    }
+    profiles.set_active_profile(id)?;
+    db_pool.close().await;
+    log::info!("switching to profile {}, restarting", id);
+    app_handle.restart()
+}
@@ -0,0 +1,27 @@
+pub mod cmds;
+pub mod repo;
+
+use crate::common::entity::project::{ProjectDocumentRow, ProjectRow};
+
+pub fn build_project_instructions(
+    project: &ProjectRow,
+    documents: &[ProjectDocumentRow],
+) -> Option<String> {
+    let mut sections = Vec::new();
+    let system_prompt = project.system_prompt.trim();
+    if !system_prompt.is_empty() {
+        sections.push(system_prompt.to_string());
+    }
+    for document in documents {
+        sections.push(format!(
+            "<document name=\"{}\">\n{}\n</document>",
+            document.name,
+            document.content.trim_end()
+        ));
+    }
+    if sections.is_empty() {
+        None
+    } else {
+        Some(sections.join("\n\n"))
+    }
+}
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Deserializer};
+use std::{path::PathBuf, sync::Arc};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    common::{
+        entity::project::{ProjectDocumentRow, ProjectRow},
+        error::AppError,
+    },
+    project::repo::{CreateProject, CreateProjectDocument, ProjectRepo, UpdateProject},
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateProjectCmd {
+    pub name: String,
+    pub system_prompt: Option<String>,
+    pub default_agent_id: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProjectCmd {
+    pub name: Option<String>,
+    pub system_prompt: Option<String>,
+    /// Missing leaves the default agent as is, `null` clears it.
+    #[serde(default, deserialize_with = "deserialize_double_option")]
+    pub default_agent_id: Option<Option<Uuid>>,
+}
+
+/// Keeps an explicit `null` apart from a missing field, which plain serde folds into
+/// the outer `None`. Needs `#[serde(default)]` so the missing case stays `None`.
+fn deserialize_double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[tauri::command]
+pub async fn get_projects(
+    project_repo: State<'_, Arc<dyn ProjectRepo>>,
+) -> Result<Vec<ProjectRow>, AppError> {
+    project_repo.get_projects().await
+}
+
+#[tauri::command]
+pub async fn get_project(
+    id: Uuid,
+    project_repo: State<'_, Arc<dyn ProjectRepo>>,
+) -> Result<Option<ProjectRow>, AppError> {
+    project_repo.get_project(id).await
+}
+
+#[tauri::command]
+pub async fn create_project(
+    create: CreateProjectCmd,
+    project_repo: State<'_, Arc<dyn ProjectRepo>>,
+) -> Result<ProjectRow, AppError> {
+    project_repo
+        .create_project(CreateProject {
+            id: Uuid::new_v4(),
+            name: create.name.trim().to_string(),
+            system_prompt: create.system_prompt.unwrap_or_default(),
+            default_agent_id: create.default_agent_id,
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn update_project(
+    id: Uuid,
+    update: UpdateProjectCmd,
+    project_repo: State<'_, Arc<dyn ProjectRepo>>,
+) -> Result<u64, AppError> {
+    project_repo
+        .update_project(
+            id,
+            UpdateProject {
+                name: update.name.map(|a| a.trim().to_string()),
+                system_prompt: update.system_prompt,
+                default_agent_id: update.default_agent_id,
+            },
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_project(
+    id: Uuid,
+    project_repo: State<'_, Arc<dyn ProjectRepo>>,
+) -> Result<u64, AppError> {
+    project_repo.delete_project(id).await
+}
+
+#[tauri::command]
+pub async fn get_project_documents(
+    id: Uuid,
+    project_repo: State<'_, Arc<dyn ProjectRepo>>,
+) -> Result<Vec<ProjectDocumentRow>, AppError> {
+    project_repo.get_project_documents(id).await
+}
+
+#[tauri::command]
+pub async fn add_project_document(
+    id: Uuid,
+    path: PathBuf,
+    project_repo: State<'_, Arc<dyn ProjectRepo>>,
+) -> Result<ProjectDocumentRow, AppError> {
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(AppError::from)?;
+    let name = path
+        .file_name()
+        .map(|a| a.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    project_repo
+        .create_project_document(CreateProjectDocument {
+            id: Uuid::new_v4(),
+            project_id: id,
+            name,
+            content,
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_project_document(
+    id: Uuid,
+    project_repo: State<'_, Arc<dyn ProjectRepo>>,
+) -> Result<u64, AppError> {
+    project_repo.delete_project_document(id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn tells_cleared_default_agent_from_missing() {
+        let agent_id = Uuid::new_v4();
+        let parse = |value| serde_json::from_value::<UpdateProjectCmd>(value).unwrap();
+
+        assert_eq!(parse(json!({})).default_agent_id, None);
+        assert_eq!(
+            parse(json!({ "defaultAgentId": null })).default_agent_id,
+            Some(None)
+        );
+        assert_eq!(
+            parse(json!({ "defaultAgentId": agent_id })).default_agent_id,
+            Some(Some(agent_id))
+        );
+    }
+}
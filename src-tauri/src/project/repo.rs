@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::common::{
+    entity::project::{ProjectDocumentRow, ProjectRow},
+    error::AppError,
+};
+
+#[cfg(test)]
+pub mod memory;
+pub mod sqlite;
+
+pub struct CreateProject {
+    pub id: Uuid,
+    pub name: String,
+    pub system_prompt: String,
+    pub default_agent_id: Option<Uuid>,
+}
+
+#[derive(Default)]
+pub struct UpdateProject {
+    pub name: Option<String>,
+    pub system_prompt: Option<String>,
+    pub default_agent_id: Option<Option<Uuid>>,
+}
+
+pub struct CreateProjectDocument {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub name: String,
+    pub content: String,
+}
+
+#[async_trait]
+pub trait ProjectRepo: Send + Sync {
+    async fn get_projects(&self) -> Result<Vec<ProjectRow>, AppError>;
+    async fn get_project(&self, id: Uuid) -> Result<Option<ProjectRow>, AppError>;
+    async fn create_project(&self, create: CreateProject) -> Result<ProjectRow, AppError>;
+    async fn update_project(&self, id: Uuid, update: UpdateProject) -> Result<u64, AppError>;
+    async fn delete_project(&self, id: Uuid) -> Result<u64, AppError>;
+    async fn get_project_documents(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<ProjectDocumentRow>, AppError>;
+    async fn create_project_document(
+        &self,
+        create: CreateProjectDocument,
+    ) -> Result<ProjectDocumentRow, AppError>;
+    async fn delete_project_document(&self, id: Uuid) -> Result<u64, AppError>;
+}
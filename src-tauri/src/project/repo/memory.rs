@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    common::{
+        entity::project::{ProjectDocumentRow, ProjectRow},
+        error::AppError,
+    },
+    database::memory::{now_millis, MemoryTables},
+    project::repo::{CreateProject, CreateProjectDocument, ProjectRepo, UpdateProject},
+};
+
+pub struct MemoryProjectRepo {
+    tables: Arc<Mutex<MemoryTables>>,
+}
+
+impl MemoryProjectRepo {
+    pub fn new(tables: Arc<Mutex<MemoryTables>>) -> Self {
+        Self { tables }
+    }
+}
+
+#[async_trait]
+impl ProjectRepo for MemoryProjectRepo {
+    async fn get_projects(&self) -> Result<Vec<ProjectRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut projects = tables.projects.clone();
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(projects)
+    }
+
+    async fn get_project(&self, id: Uuid) -> Result<Option<ProjectRow>, AppError> {
+        let tables = self.tables.lock().await;
+        Ok(tables.projects.iter().find(|a| a.id == id).cloned())
+    }
+
+    async fn create_project(&self, create: CreateProject) -> Result<ProjectRow, AppError> {
+        let mut tables = self.tables.lock().await;
+        let now = now_millis();
+        let row = ProjectRow {
+            created_at: now,
+            updated_at: now,
+            id: create.id,
+            name: create.name,
+            system_prompt: create.system_prompt,
+            default_agent_id: create.default_agent_id,
+        };
+        tables.projects.push(row.clone());
+        Ok(row)
+    }
+
+    async fn update_project(&self, id: Uuid, update: UpdateProject) -> Result<u64, AppError> {
+        if update.name.is_none()
+            && update.system_prompt.is_none()
+            && update.default_agent_id.is_none()
+        {
+            return Ok(0);
+        }
+        let mut tables = self.tables.lock().await;
+        let Some(project) = tables.projects.iter_mut().find(|a| a.id == id) else {
+            return Ok(0);
+        };
+        if let Some(name) = update.name {
+            project.name = name;
+        }
+        if let Some(system_prompt) = update.system_prompt {
+            project.system_prompt = system_prompt;
+        }
+        if let Some(default_agent_id) = update.default_agent_id {
+            project.default_agent_id = default_agent_id;
+        }
+        project.updated_at = now_millis();
+        Ok(1)
+    }
+
+    async fn delete_project(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        let count = tables.projects.len();
+        tables.projects.retain(|a| a.id != id);
+        tables.project_documents.retain(|a| a.project_id != id);
+        for chat in tables.chats.iter_mut().filter(|a| a.project_id == Some(id)) {
+            chat.project_id = None;
+        }
+        Ok((count - tables.projects.len()) as u64)
+    }
+
+    async fn get_project_documents(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<ProjectDocumentRow>, AppError> {
+        let tables = self.tables.lock().await;
+        let mut documents = tables
+            .project_documents
+            .iter()
+            .filter(|a| a.project_id == project_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        documents.sort_by_key(|a| a.created_at);
+        Ok(documents)
+    }
+
+    async fn create_project_document(
+        &self,
+        create: CreateProjectDocument,
+    ) -> Result<ProjectDocumentRow, AppError> {
+        let mut tables = self.tables.lock().await;
+        let row = ProjectDocumentRow {
+            created_at: now_millis(),
+            id: create.id,
+            project_id: create.project_id,
+            name: create.name,
+            content: create.content,
+        };
+        tables.project_documents.push(row.clone());
+        Ok(row)
+    }
+
+    async fn delete_project_document(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tables = self.tables.lock().await;
+        let count = tables.project_documents.len();
+        tables.project_documents.retain(|a| a.id != id);
+        Ok((count - tables.project_documents.len()) as u64)
+    }
+}
@@ -0,0 +1,261 @@
+use async_trait::async_trait;
+use sqlx::{Executor, Pool, QueryBuilder, Sqlite, SqliteTransaction};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    common::{
+        entity::project::{ProjectDocumentRow, ProjectRow},
+        error::AppError,
+    },
+    project::repo::{CreateProject, CreateProjectDocument, ProjectRepo, UpdateProject},
+};
+
+pub struct SqliteProjectRepo {
+    db_pool: Arc<Pool<Sqlite>>,
+}
+
+pub struct TransactionalSqliteProjectRepo<'a> {
+    tx: Arc<Mutex<SqliteTransaction<'a>>>,
+}
+
+impl SqliteProjectRepo {
+    pub fn new(db_pool: Arc<Pool<Sqlite>>) -> Self {
+        Self { db_pool }
+    }
+}
+
+impl<'a> TransactionalSqliteProjectRepo<'a> {
+    pub fn new(tx: Arc<Mutex<SqliteTransaction<'a>>>) -> Self {
+        Self { tx }
+    }
+}
+
+#[async_trait]
+impl ProjectRepo for SqliteProjectRepo {
+    async fn get_projects(&self) -> Result<Vec<ProjectRow>, AppError> {
+        get_projects(&*self.db_pool).await
+    }
+
+    async fn get_project(&self, id: Uuid) -> Result<Option<ProjectRow>, AppError> {
+        get_project(&*self.db_pool, id).await
+    }
+
+    async fn create_project(&self, create: CreateProject) -> Result<ProjectRow, AppError> {
+        create_project(&*self.db_pool, create).await
+    }
+
+    async fn update_project(&self, id: Uuid, update: UpdateProject) -> Result<u64, AppError> {
+        update_project(&*self.db_pool, id, update).await
+    }
+
+    async fn delete_project(&self, id: Uuid) -> Result<u64, AppError> {
+        delete_project(&*self.db_pool, id).await
+    }
+
+    async fn get_project_documents(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<ProjectDocumentRow>, AppError> {
+        get_project_documents(&*self.db_pool, project_id).await
+    }
+
+    async fn create_project_document(
+        &self,
+        create: CreateProjectDocument,
+    ) -> Result<ProjectDocumentRow, AppError> {
+        create_project_document(&*self.db_pool, create).await
+    }
+
+    async fn delete_project_document(&self, id: Uuid) -> Result<u64, AppError> {
+        delete_project_document(&*self.db_pool, id).await
+    }
+}
+
+#[async_trait]
+impl<'a> ProjectRepo for TransactionalSqliteProjectRepo<'a> {
+    async fn get_projects(&self) -> Result<Vec<ProjectRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_projects(&mut **tx).await
+    }
+
+    async fn get_project(&self, id: Uuid) -> Result<Option<ProjectRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_project(&mut **tx, id).await
+    }
+
+    async fn create_project(&self, create: CreateProject) -> Result<ProjectRow, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        create_project(&mut **tx, create).await
+    }
+
+    async fn update_project(&self, id: Uuid, update: UpdateProject) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        update_project(&mut **tx, id, update).await
+    }
+
+    async fn delete_project(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        delete_project(&mut **tx, id).await
+    }
+
+    async fn get_project_documents(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<ProjectDocumentRow>, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        get_project_documents(&mut **tx, project_id).await
+    }
+
+    async fn create_project_document(
+        &self,
+        create: CreateProjectDocument,
+    ) -> Result<ProjectDocumentRow, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        create_project_document(&mut **tx, create).await
+    }
+
+    async fn delete_project_document(&self, id: Uuid) -> Result<u64, AppError> {
+        let mut tx = self.tx.try_lock().map_err(AppError::from)?;
+        delete_project_document(&mut **tx, id).await
+    }
+}
+
+async fn get_projects<'a, E>(executor: E) -> Result<Vec<ProjectRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ProjectRow>("select * from projects order by name asc")
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn get_project<'a, E>(executor: E, id: Uuid) -> Result<Option<ProjectRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ProjectRow>("select * from projects where id = ?1")
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn create_project<'a, E>(executor: E, create: CreateProject) -> Result<ProjectRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let (created_at, updated_at): (i64, i64) = sqlx::query_as("insert into projects (id, name, system_prompt, default_agent_id) values (?1, ?2, ?3, ?4) returning created_at, updated_at")
+        .bind(create.id)
+        .bind(&create.name)
+        .bind(&create.system_prompt)
+        .bind(create.default_agent_id)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(ProjectRow {
+        created_at,
+        updated_at,
+        id: create.id,
+        name: create.name,
+        system_prompt: create.system_prompt,
+        default_agent_id: create.default_agent_id,
+    })
+}
+
+async fn update_project<'a, E>(
+    executor: E,
+    id: Uuid,
+    update: UpdateProject,
+) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    if update.name.is_none() && update.system_prompt.is_none() && update.default_agent_id.is_none()
+    {
+        return Ok(0);
+    }
+    let mut qb = QueryBuilder::new("update projects set ");
+    let mut sep = qb.separated(", ");
+    if let Some(name) = update.name {
+        sep.push("name = ").push_bind_unseparated(name);
+    }
+    if let Some(system_prompt) = update.system_prompt {
+        sep.push("system_prompt = ")
+            .push_bind_unseparated(system_prompt);
+    }
+    if let Some(default_agent_id) = update.default_agent_id {
+        sep.push("default_agent_id = ")
+            .push_bind_unseparated(default_agent_id);
+    }
+    qb.push(" where id = ").push_bind(id);
+
+    let result = qb.build().execute(executor).await.map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn delete_project<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from projects where id = ?1")
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+async fn get_project_documents<'a, E>(
+    executor: E,
+    project_id: Uuid,
+) -> Result<Vec<ProjectDocumentRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ProjectDocumentRow>(
+        "select * from project_documents where project_id = ?1 order by created_at asc",
+    )
+    .bind(project_id)
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn create_project_document<'a, E>(
+    executor: E,
+    create: CreateProjectDocument,
+) -> Result<ProjectDocumentRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let created_at = sqlx::query_scalar::<_, i64>("insert into project_documents (id, project_id, name, content) values (?1, ?2, ?3, ?4) returning created_at")
+        .bind(create.id)
+        .bind(create.project_id)
+        .bind(&create.name)
+        .bind(&create.content)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(ProjectDocumentRow {
+        created_at,
+        id: create.id,
+        project_id: create.project_id,
+        name: create.name,
+        content: create.content,
+    })
+}
+
+async fn delete_project_document<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from project_documents where id = ?1")
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,315 @@
+pub mod cmds;
+pub mod repo;
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    chat::repo::{ChatFilter, ChatRepo, UpdateChat},
+    common::{
+        entity::retention::{RetentionAction, RetentionPolicyRow},
+        error::AppError,
+        unit_of_work::UnitOfWorkFactory,
+    },
+    event::{DomainEvent, EventBus},
+    retention::repo::RetentionRepo,
+};
+
+const RUN_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionReason {
+    MaxAge,
+    MaxDatabaseSize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPlanItem {
+    pub chat_id: Uuid,
+    pub title: String,
+    pub last_active_at: i64,
+    pub action: RetentionAction,
+    pub reason: RetentionReason,
+    pub size: i64,
+}
+
+/// What a retention run removes. `database_bytes` is the space in use before the run,
+/// leaving out free pages a vacuum would drop, and `reclaimable_bytes` an estimate of
+/// what deleting the chats frees.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPlan {
+    pub items: Vec<RetentionPlanItem>,
+    pub database_bytes: i64,
+    pub reclaimable_bytes: i64,
+}
+
+/// Applies the retention policy: chats inactive for longer than `max_age_days` are
+/// archived or deleted, then the oldest chats are deleted until the database fits in
+/// `max_database_bytes`. Pinned chats are spared when `keep_pinned` is set.
+pub struct Retention {
+    retention_repo: Arc<dyn RetentionRepo>,
+    chat_repo: Arc<dyn ChatRepo>,
+    unit_of_work_factory: Arc<dyn UnitOfWorkFactory>,
+    event_bus: Arc<EventBus>,
+}
+
+impl Retention {
+    pub fn new(
+        retention_repo: Arc<dyn RetentionRepo>,
+        chat_repo: Arc<dyn ChatRepo>,
+        unit_of_work_factory: Arc<dyn UnitOfWorkFactory>,
+        event_bus: Arc<EventBus>,
+    ) -> Self {
+        Self {
+            retention_repo,
+            chat_repo,
+            unit_of_work_factory,
+            event_bus,
+        }
+    }
+
+    pub async fn plan(&self) -> Result<RetentionPlan, AppError> {
+        let policy = self.retention_repo.get_retention_policy().await?;
+        self.plan_with(&policy).await
+    }
+
+    async fn plan_with(&self, policy: &RetentionPolicyRow) -> Result<RetentionPlan, AppError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        let chats = self.retention_repo.get_chat_retention().await?;
+        let database_bytes = self.retention_repo.get_database_size().await?;
+        let mut titles = self
+            .chat_repo
+            .get_chats(ChatFilter::default())
+            .await?
+            .into_iter()
+            .map(|a| (a.id, a.title))
+            .collect::<HashMap<_, _>>();
+
+        let mut actions = HashMap::new();
+        if let Some(max_age_days) = policy.max_age_days {
+            let cutoff = now - max_age_days * DAY_MILLIS;
+            for chat in &chats {
+                if chat.last_active_at >= cutoff
+                    || (policy.keep_pinned && chat.pinned)
+                    || (policy.action == RetentionAction::Archive && chat.archived_at.is_some())
+                {
+                    continue;
+                }
+                actions.insert(chat.id, (policy.action, RetentionReason::MaxAge));
+            }
+        }
+        if let Some(max_database_bytes) = policy.max_database_bytes {
+            let mut excess = database_bytes - max_database_bytes;
+            for chat in &chats {
+                if let Some((RetentionAction::Delete, _)) = actions.get(&chat.id) {
+                    excess -= chat.size;
+                }
+            }
+            for chat in &chats {
+                if excess <= 0 {
+                    break;
+                }
+                if (policy.keep_pinned && chat.pinned)
+                    || matches!(actions.get(&chat.id), Some((RetentionAction::Delete, _)))
+                {
+                    continue;
+                }
+                actions.insert(
+                    chat.id,
+                    (RetentionAction::Delete, RetentionReason::MaxDatabaseSize),
+                );
+                excess -= chat.size;
+            }
+        }
+
+        let mut items = Vec::new();
+        let mut reclaimable_bytes = 0;
+        for chat in chats {
+            let Some((action, reason)) = actions.remove(&chat.id) else {
+                continue;
+            };
+            if action == RetentionAction::Delete {
+                reclaimable_bytes += chat.size;
+            }
+            items.push(RetentionPlanItem {
+                chat_id: chat.id,
+                title: titles.remove(&chat.id).unwrap_or_default(),
+                last_active_at: chat.last_active_at,
+                action,
+                reason,
+                size: chat.size,
+            });
+        }
+        Ok(RetentionPlan {
+            items,
+            database_bytes,
+            reclaimable_bytes,
+        })
+    }
+
+    /// Archives and deletes in one transaction, announces each change once it is
+    /// committed, then vacuums if anything was deleted.
+    pub async fn apply(&self) -> Result<RetentionPlan, AppError> {
+        let policy = self.retention_repo.get_retention_policy().await?;
+        let plan = self.plan_with(&policy).await?;
+        let unit_of_work = self.unit_of_work_factory.create().await?;
+        {
+            let chat_repo = unit_of_work.chat_repo();
+            for item in &plan.items {
+                match item.action {
+                    RetentionAction::Archive => {
+                        chat_repo
+                            .update_chat(
+                                item.chat_id,
+                                UpdateChat {
+                                    archived: Some(true),
+                                    ..Default::default()
+                                },
+                            )
+                            .await?;
+                    }
+                    RetentionAction::Delete => {
+                        chat_repo.delete_chat(item.chat_id).await?;
+                    }
+                }
+            }
+        }
+        unit_of_work.commit().await?;
+        for item in &plan.items {
+            self.event_bus.publish(match item.action {
+                RetentionAction::Archive => DomainEvent::ChatArchived {
+                    chat_id: item.chat_id,
+                    archived: true,
+                },
+                RetentionAction::Delete => DomainEvent::ChatDeleted {
+                    chat_id: item.chat_id,
+                },
+            });
+        }
+        if plan
+            .items
+            .iter()
+            .any(|a| a.action == RetentionAction::Delete)
+        {
+            self.retention_repo.vacuum().await?;
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        self.retention_repo.update_retention_last_run(now).await?;
+        Ok(plan)
+    }
+
+    /// Applies the policy once a day while it is enabled.
+    pub fn spawn_schedule(self: &Arc<Self>) {
+        let retention = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                if let Err(e) = retention.run_schedule().await {
+                    log::error!("failed to apply retention policy: {}", e);
+                }
+                tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn run_schedule(&self) -> Result<(), AppError> {
+        let policy = self.retention_repo.get_retention_policy().await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        let due = policy
+            .last_run_at
+            .is_none_or(|a| now - a >= RUN_INTERVAL.as_millis() as i64);
+        if !policy.enabled || !due {
+            return Ok(());
+        }
+        let plan = self.apply().await?;
+        if !plan.items.is_empty() {
+            log::info!(
+                "retention policy archived or deleted {} chats",
+                plan.items.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::{
+        chat::repo::{sqlite::SqliteChatRepo, CreateChat},
+        common::unit_of_work::memory::MemoryUnitOfWorkFactory,
+        database::memory::create_database,
+        retention::repo::sqlite::SqliteRetentionRepo,
+    };
+
+    #[tokio::test]
+    async fn ignores_free_pages_when_sizing_the_database() {
+        let db_pool = Arc::new(
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+                .await
+                .unwrap(),
+        );
+        sqlx::migrate!("./migrations").run(&*db_pool).await.unwrap();
+        let chat_repo = Arc::new(SqliteChatRepo::new(db_pool.clone()));
+        chat_repo
+            .create_chat(CreateChat {
+                id: Uuid::new_v4(),
+                title: "kept".into(),
+                created_at: None,
+                project_id: None,
+            })
+            .await
+            .unwrap();
+        let retention = Retention::new(
+            Arc::new(SqliteRetentionRepo::new(db_pool.clone())),
+            chat_repo,
+            Arc::new(MemoryUnitOfWorkFactory::new(create_database())),
+            Arc::new(EventBus::new()),
+        );
+        let in_use = retention.retention_repo.get_database_size().await.unwrap();
+
+        sqlx::query("create table scratch (data blob)")
+            .execute(&*db_pool)
+            .await
+            .unwrap();
+        sqlx::query("insert into scratch values (zeroblob(1000000))")
+            .execute(&*db_pool)
+            .await
+            .unwrap();
+        sqlx::query("drop table scratch")
+            .execute(&*db_pool)
+            .await
+            .unwrap();
+        let file_size = sqlx::query_scalar::<_, i64>(
+            "select page_count * page_size from pragma_page_count(), pragma_page_size()",
+        )
+        .fetch_one(&*db_pool)
+        .await
+        .unwrap();
+        assert!(file_size > in_use + 500_000);
+
+        let mut policy = retention
+            .retention_repo
+            .get_retention_policy()
+            .await
+            .unwrap();
+        policy.max_database_bytes = Some(in_use);
+        let plan = retention.plan_with(&policy).await.unwrap();
+        assert_eq!(plan.database_bytes, in_use);
+        assert!(plan.items.is_empty());
+    }
+}
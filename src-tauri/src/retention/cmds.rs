@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+use tauri::State;
+
+use crate::{
+    common::{
+        entity::retention::{RetentionAction, RetentionPolicyRow},
+        error::AppError,
+    },
+    retention::{
+        repo::{RetentionRepo, UpdateRetentionPolicy},
+        Retention, RetentionPlan,
+    },
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRetentionPolicyCmd {
+    pub enabled: bool,
+    pub max_age_days: Option<i64>,
+    pub action: RetentionAction,
+    pub keep_pinned: bool,
+    pub max_database_bytes: Option<i64>,
+}
+
+#[tauri::command]
+pub async fn get_retention_policy(
+    retention_repo: State<'_, Arc<dyn RetentionRepo>>,
+) -> Result<RetentionPolicyRow, AppError> {
+    retention_repo.get_retention_policy().await
+}
+
+#[tauri::command]
+pub async fn update_retention_policy(
+    update: UpdateRetentionPolicyCmd,
+    retention_repo: State<'_, Arc<dyn RetentionRepo>>,
+) -> Result<RetentionPolicyRow, AppError> {
+    if update.max_age_days.is_some_and(|a| a <= 0) {
+        return Err(AppError::InvalidRetentionPolicy(
+            "max age must be at least one day".into(),
+        ));
+    }
+    if update.max_database_bytes.is_some_and(|a| a <= 0) {
+        return Err(AppError::InvalidRetentionPolicy(
+            "max database size must be positive".into(),
+        ));
+    }
+    retention_repo
+        .update_retention_policy(UpdateRetentionPolicy {
+            enabled: update.enabled,
+            max_age_days: update.max_age_days,
+            action: update.action,
+            keep_pinned: update.keep_pinned,
+            max_database_bytes: update.max_database_bytes,
+        })
+        .await
+}
+
+/// Lists what applying the policy now would archive or delete, without changing
+/// anything.
+#[tauri::command]
+pub async fn preview_retention(
+    retention: State<'_, Arc<Retention>>,
+) -> Result<RetentionPlan, AppError> {
+    retention.plan().await
+}
+
+#[tauri::command]
+pub async fn apply_retention(
+    retention: State<'_, Arc<Retention>>,
+) -> Result<RetentionPlan, AppError> {
+    retention.apply().await
+}
@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+
+use crate::common::{
+    entity::retention::{ChatRetentionRow, RetentionAction, RetentionPolicyRow},
+    error::AppError,
+};
+
+pub mod sqlite;
+
+pub struct UpdateRetentionPolicy {
+    pub enabled: bool,
+    pub max_age_days: Option<i64>,
+    pub action: RetentionAction,
+    pub keep_pinned: bool,
+    pub max_database_bytes: Option<i64>,
+}
+
+#[async_trait]
+pub trait RetentionRepo: Send + Sync {
+    async fn get_retention_policy(&self) -> Result<RetentionPolicyRow, AppError>;
+    async fn update_retention_policy(
+        &self,
+        update: UpdateRetentionPolicy,
+    ) -> Result<RetentionPolicyRow, AppError>;
+    async fn update_retention_last_run(&self, last_run_at: i64) -> Result<(), AppError>;
+    /// Oldest activity first.
+    async fn get_chat_retention(&self) -> Result<Vec<ChatRetentionRow>, AppError>;
+    async fn get_database_size(&self) -> Result<i64, AppError>;
+    async fn vacuum(&self) -> Result<(), AppError>;
+}
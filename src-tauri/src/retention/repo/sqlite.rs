@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use sqlx::{Executor, Pool, Sqlite};
+use std::sync::Arc;
+
+use crate::{
+    common::{
+        entity::retention::{ChatRetentionRow, RetentionPolicyRow},
+        error::AppError,
+    },
+    retention::repo::{RetentionRepo, UpdateRetentionPolicy},
+};
+
+pub struct SqliteRetentionRepo {
+    db_pool: Arc<Pool<Sqlite>>,
+}
+
+impl SqliteRetentionRepo {
+    pub fn new(db_pool: Arc<Pool<Sqlite>>) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl RetentionRepo for SqliteRetentionRepo {
+    async fn get_retention_policy(&self) -> Result<RetentionPolicyRow, AppError> {
+        get_retention_policy(&*self.db_pool).await
+    }
+
+    async fn update_retention_policy(
+        &self,
+        update: UpdateRetentionPolicy,
+    ) -> Result<RetentionPolicyRow, AppError> {
+        update_retention_policy(&*self.db_pool, update).await
+    }
+
+    async fn update_retention_last_run(&self, last_run_at: i64) -> Result<(), AppError> {
+        update_retention_last_run(&*self.db_pool, last_run_at).await
+    }
+
+    async fn get_chat_retention(&self) -> Result<Vec<ChatRetentionRow>, AppError> {
+        get_chat_retention(&*self.db_pool).await
+    }
+
+    async fn get_database_size(&self) -> Result<i64, AppError> {
+        get_database_size(&*self.db_pool).await
+    }
+
+    async fn vacuum(&self) -> Result<(), AppError> {
+        vacuum(&*self.db_pool).await
+    }
+}
+
+async fn get_retention_policy<'a, E>(executor: E) -> Result<RetentionPolicyRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, RetentionPolicyRow>("select * from retention_policy where id = 1")
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn update_retention_policy<'a, E>(
+    executor: E,
+    update: UpdateRetentionPolicy,
+) -> Result<RetentionPolicyRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, RetentionPolicyRow>("update retention_policy set enabled = ?1, max_age_days = ?2, action = ?3, keep_pinned = ?4, max_database_bytes = ?5 where id = 1 returning *")
+        .bind(update.enabled)
+        .bind(update.max_age_days)
+        .bind(update.action)
+        .bind(update.keep_pinned)
+        .bind(update.max_database_bytes)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn update_retention_last_run<'a, E>(executor: E, last_run_at: i64) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("update retention_policy set last_run_at = ?1 where id = 1")
+        .bind(last_run_at)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn get_chat_retention<'a, E>(executor: E) -> Result<Vec<ChatRetentionRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ChatRetentionRow>(
+        "select c.id, c.pinned, c.archived_at, coalesce(m.last_active_at, c.created_at) as last_active_at, coalesce(m.size, 0) + coalesce(a.size, 0) + coalesce(e.size, 0) as size from chats c left join (select chat_id, max(created_at) as last_active_at, sum(length(cast(content as blob))) as size from chat_messages group by chat_id) m on m.chat_id = c.id left join (select m.chat_id, sum(coalesce(length(a.data), 0) + coalesce(length(cast(a.text as blob)), 0)) as size from chat_message_attachments a inner join chat_messages m on m.id = a.message_id group by m.chat_id) a on a.chat_id = c.id left join (select m.chat_id, sum(length(e.embedding)) as size from chat_message_embeddings e inner join chat_messages m on m.id = e.message_id group by m.chat_id) e on e.chat_id = c.id order by last_active_at asc",
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn get_database_size<'a, E>(executor: E) -> Result<i64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_scalar::<_, i64>(
+        "select (page_count - freelist_count) * page_size from pragma_page_count(), pragma_freelist_count(), pragma_page_size()",
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn vacuum<'a, E>(executor: E) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("vacuum")
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
@@ -0,0 +1,665 @@
+pub mod cmds;
+pub mod repo;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{io::AsyncWriteExt, sync::Mutex};
+use uuid::Uuid;
+
+use crate::{
+    agent::repo::{AgentRepo, UpsertAgentConfig},
+    chat::repo::{
+        ChatFilter, ChatRepo, CreateChat, CreateChatMessage, UpdateChat, UpdateChatMessage,
+    },
+    cipher::{Cipher, KeyringAesGcmCipher},
+    common::{
+        entity::{
+            chat::{
+                ChatCitation, ChatMessageKind, ChatMessageRow, ChatMessageStatus, ChatRow,
+                ChatSchemaViolation, ChatToolCall,
+            },
+            sync::{SyncCursorRow, SyncEntity, SyncEntityRow},
+        },
+        error::AppError,
+    },
+    event::{DomainEvent, EventBus},
+    sync::repo::SyncRepo,
+};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const LOG_EXTENSION: &str = "log";
+
+/// One line of a device's change log. `data` is the entity as of `changed_at`, or
+/// `None` if it was deleted.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SyncChange {
+    device_id: Uuid,
+    changed_at: i64,
+    entity: SyncEntity,
+    entity_id: Uuid,
+    data: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SyncChat {
+    title: String,
+    pinned: bool,
+    starred: bool,
+    archived: bool,
+    created_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SyncChatMessage {
+    chat_id: Uuid,
+    role: String,
+    content: String,
+    status: ChatMessageStatus,
+    starred: bool,
+    kind: ChatMessageKind,
+    tool_calls: Option<Vec<ChatToolCall>>,
+    tool_call_id: Option<String>,
+    tool_name: Option<String>,
+    schema_errors: Option<Vec<ChatSchemaViolation>>,
+    citations: Option<Vec<ChatCitation>>,
+    created_at: i64,
+}
+
+/// The API key travels decrypted inside the sealed log and is encrypted again with
+/// the receiving device's key.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SyncAgentConfig {
+    api_key: Option<String>,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub exported: usize,
+    pub merged: usize,
+    pub skipped: usize,
+    /// Changes waiting for an entity they depend on, retried on the next run.
+    pub deferred: usize,
+    pub errors: Vec<String>,
+}
+
+/// An entity as it is on this device, and when it last changed here.
+struct SyncLocal {
+    data: Value,
+    updated_at: i64,
+}
+
+/// What became of a change read from another device's log.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SyncOutcome {
+    Merged,
+    Skipped,
+    /// Depends on an entity this device hasn't seen yet, such as a message whose chat
+    /// is further down the log or in another device's log.
+    Deferred,
+}
+
+/// Keeps chats, messages and agent API keys in step with other devices through a
+/// shared folder. Each device appends the changes it finds to its own log,
+/// `<device id>.log`, and merges the other logs from where it left off. Concurrent
+/// changes to the same entity resolve to the one with the later `changed_at`, then
+/// the greater device id, so every device ends up with the same result.
+///
+/// Every line is sealed with the sync key, a keyring entry shared between devices
+/// by exporting it on one and importing it on the others; nothing is written to the
+/// folder in plaintext.
+pub struct ChatSync {
+    sync_repo: Arc<dyn SyncRepo>,
+    chat_repo: Arc<dyn ChatRepo>,
+    agent_repo: Arc<dyn AgentRepo>,
+    cipher: Arc<dyn Cipher>,
+    sync_keyring_user: String,
+    event_bus: Arc<EventBus>,
+    running: Mutex<()>,
+}
+
+impl ChatSync {
+    pub fn new(
+        sync_repo: Arc<dyn SyncRepo>,
+        chat_repo: Arc<dyn ChatRepo>,
+        agent_repo: Arc<dyn AgentRepo>,
+        cipher: Arc<dyn Cipher>,
+        sync_keyring_user: String,
+        event_bus: Arc<EventBus>,
+    ) -> Self {
+        Self {
+            sync_repo,
+            chat_repo,
+            agent_repo,
+            cipher,
+            sync_keyring_user,
+            event_bus,
+            running: Mutex::new(()),
+        }
+    }
+
+    pub fn sync_keyring_user(&self) -> &str {
+        &self.sync_keyring_user
+    }
+
+    /// Syncs on an interval while a folder is configured.
+    pub fn spawn_schedule(self: &Arc<Self>) {
+        let sync = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(SYNC_INTERVAL).await;
+                let configured = match sync.sync_repo.get_sync_state().await {
+                    Ok(state) => state.folder.is_some(),
+                    Err(e) => {
+                        log::error!("failed to read sync state: {}", e);
+                        false
+                    }
+                };
+                if !configured {
+                    continue;
+                }
+                match sync.run().await {
+                    Ok(report) => {
+                        for error in report.errors {
+                            log::warn!("sync: {}", error);
+                        }
+                    }
+                    Err(e) => log::error!("failed to sync: {}", e),
+                }
+            }
+        });
+    }
+
+    pub async fn run(&self) -> Result<SyncReport, AppError> {
+        let _running = self.running.lock().await;
+        let state = self.sync_repo.get_sync_state().await?;
+        let folder = PathBuf::from(state.folder.ok_or(AppError::SyncNotConfigured)?);
+        let sync_cipher = KeyringAesGcmCipher::new(self.sync_keyring_user.clone());
+        let mut report = SyncReport::default();
+
+        let mut versions = self
+            .sync_repo
+            .get_sync_entities()
+            .await?
+            .into_iter()
+            .map(|a| ((a.entity, a.entity_id), a))
+            .collect::<HashMap<_, _>>();
+        report.exported = self
+            .export(&folder, state.device_id, &sync_cipher, &mut versions)
+            .await?;
+        self.merge(
+            &folder,
+            state.device_id,
+            &sync_cipher,
+            &mut versions,
+            &mut report,
+        )
+        .await?;
+        self.sync_repo.update_sync_last_run(now_millis()?).await?;
+        Ok(report)
+    }
+
+    /// Appends a change for every entity whose fingerprint differs from the version
+    /// last written or merged, and a deletion for every one that is gone.
+    ///
+    /// A change is stamped with when the entity last changed, not when it is exported,
+    /// so an edit made offline doesn't beat a newer one just by syncing later. It is
+    /// never stamped earlier than the version it replaces, so a local edit always
+    /// supersedes what this device merged before it, even with the clocks apart.
+    async fn export(
+        &self,
+        folder: &Path,
+        device_id: Uuid,
+        sync_cipher: &dyn Cipher,
+        versions: &mut HashMap<(SyncEntity, Uuid), SyncEntityRow>,
+    ) -> Result<usize, AppError> {
+        let local = self.snapshot().await?;
+        let changed_after = |key: &(SyncEntity, Uuid), changed_at: i64| {
+            versions
+                .get(key)
+                .map_or(changed_at, |a| changed_at.max(a.changed_at + 1))
+        };
+        let mut changes = Vec::new();
+        for (key, local) in &local {
+            let Some(local) = local else {
+                continue;
+            };
+            let fingerprint = fingerprint(&local.data)?;
+            if versions
+                .get(key)
+                .is_some_and(|a| a.fingerprint.as_deref() == Some(fingerprint.as_str()))
+            {
+                continue;
+            }
+            changes.push((
+                key,
+                Some(local.data.clone()),
+                Some(fingerprint),
+                changed_after(key, local.updated_at),
+            ));
+        }
+        // Chats go before their messages so the log reads in a valid order.
+        changes.sort_by_key(|a| a.0 .0 != SyncEntity::Chat);
+        let mut deleted = versions
+            .values()
+            .filter(|a| a.fingerprint.is_some() && !local.contains_key(&(a.entity, a.entity_id)))
+            .map(|a| (a.entity, a.entity_id))
+            .collect::<Vec<_>>();
+        // Messages go before their chats so the log reads in a valid order.
+        deleted.sort_by_key(|a| a.0 != SyncEntity::ChatMessage);
+        // A deletion leaves no row to read the time from, so it is dated when found.
+        let now = now_millis()?;
+        for key in &deleted {
+            changes.push((key, None, None, changed_after(key, now)));
+        }
+        if changes.is_empty() {
+            return Ok(0);
+        }
+
+        let mut lines = String::new();
+        for (key, data, _, changed_at) in &changes {
+            let change = SyncChange {
+                device_id,
+                changed_at: *changed_at,
+                entity: key.0,
+                entity_id: key.1,
+                data: data.clone(),
+            };
+            lines.push_str(&sync_cipher.encrypt_str_base64(&serde_json::to_string(&change)?)?);
+            lines.push('\n');
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path(folder, device_id))
+            .await?;
+        file.write_all(lines.as_bytes()).await?;
+        file.sync_all().await?;
+
+        for (key, _, fingerprint, changed_at) in &changes {
+            let version = SyncEntityRow {
+                entity: key.0,
+                entity_id: key.1,
+                fingerprint: fingerprint.clone(),
+                changed_at: *changed_at,
+                device_id,
+            };
+            self.sync_repo.upsert_sync_entity(version.clone()).await?;
+            versions.insert(**key, version);
+        }
+        Ok(changes.len())
+    }
+
+    /// Entities that exist locally; `None` for ones that exist but aren't synced yet,
+    /// such as messages still being generated.
+    async fn snapshot(&self) -> Result<HashMap<(SyncEntity, Uuid), Option<SyncLocal>>, AppError> {
+        let mut local = HashMap::new();
+        for chat in self.chat_repo.get_chats(ChatFilter::default()).await? {
+            for message in self.chat_repo.get_chat_messages(chat.id).await? {
+                let data = match message.status {
+                    ChatMessageStatus::Pending => None,
+                    _ => Some(SyncLocal {
+                        data: serde_json::to_value(sync_chat_message(&message))?,
+                        updated_at: message.updated_at,
+                    }),
+                };
+                local.insert((SyncEntity::ChatMessage, message.id), data);
+            }
+            local.insert(
+                (SyncEntity::Chat, chat.id),
+                Some(SyncLocal {
+                    data: serde_json::to_value(sync_chat(&chat))?,
+                    updated_at: chat.updated_at,
+                }),
+            );
+        }
+        for agent in self.agent_repo.get_agents(None).await? {
+            let Some(config) = self.agent_repo.get_agent_config(agent.id).await? else {
+                continue;
+            };
+            let api_key = match config.api_key {
+                Some(api_key) if !api_key.is_empty() => {
+                    Some(self.cipher.decrypt_base64_str(&api_key)?)
+                }
+                api_key => api_key,
+            };
+            local.insert(
+                (SyncEntity::AgentConfig, agent.id),
+                Some(SyncLocal {
+                    data: serde_json::to_value(SyncAgentConfig { api_key })?,
+                    updated_at: config.updated_at,
+                }),
+            );
+        }
+        Ok(local)
+    }
+
+    async fn merge(
+        &self,
+        folder: &Path,
+        device_id: Uuid,
+        sync_cipher: &dyn Cipher,
+        versions: &mut HashMap<(SyncEntity, Uuid), SyncEntityRow>,
+        report: &mut SyncReport,
+    ) -> Result<(), AppError> {
+        let cursors = self
+            .sync_repo
+            .get_sync_cursors()
+            .await?
+            .into_iter()
+            .map(|a| (a.device_id, a.line))
+            .collect::<HashMap<_, _>>();
+        let mut read_dir = tokio::fs::read_dir(folder).await?;
+        let mut logs = Vec::new();
+        while let Some(entry) = read_dir.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|a| a != LOG_EXTENSION) {
+                continue;
+            }
+            let Some(log_device_id) = path
+                .file_stem()
+                .and_then(|a| Uuid::parse_str(&a.to_string_lossy()).ok())
+            else {
+                continue;
+            };
+            if log_device_id != device_id {
+                logs.push((log_device_id, path));
+            }
+        }
+        logs.sort();
+
+        for (log_device_id, path) in logs {
+            let content = tokio::fs::read_to_string(&path).await?;
+            let skip = cursors.get(&log_device_id).copied().unwrap_or(0) as usize;
+            let mut line_count = skip;
+            let mut deferred = Vec::new();
+            // A line without its newline may still be being written by the folder's
+            // sync tool, so it is left for the next run.
+            for line in content
+                .split_inclusive('\n')
+                .filter(|a| a.ends_with('\n'))
+                .skip(skip)
+            {
+                let change = sync_cipher
+                    .decrypt_base64_str(line.trim_end())
+                    .and_then(|a| serde_json::from_str::<SyncChange>(&a).map_err(AppError::from));
+                let change = match change {
+                    Ok(change) => change,
+                    Err(e) => {
+                        report.errors.push(format!(
+                            "can't read the log of device {}, is the sync key the same on both devices? ({})",
+                            log_device_id, e
+                        ));
+                        break;
+                    }
+                };
+                match self.merge_change(change.clone(), versions).await {
+                    Ok(SyncOutcome::Merged) => report.merged += 1,
+                    Ok(SyncOutcome::Skipped) => report.skipped += 1,
+                    Ok(SyncOutcome::Deferred) => deferred.push((line_count, change)),
+                    Err(e) => {
+                        report.errors.push(format!(
+                            "failed to merge a change from device {}: {}",
+                            log_device_id, e
+                        ));
+                        break;
+                    }
+                }
+                line_count += 1;
+            }
+            // Retried once the rest of the log is in, which brings in chats written after
+            // their messages. Whatever still waits holds the cursor, so it is read again
+            // next run; the changes after it are then skipped as already merged.
+            let mut held = None;
+            for (line, change) in deferred {
+                match self.merge_change(change, versions).await {
+                    Ok(SyncOutcome::Merged) => report.merged += 1,
+                    Ok(SyncOutcome::Skipped) => report.skipped += 1,
+                    Ok(SyncOutcome::Deferred) => {
+                        report.deferred += 1;
+                        held.get_or_insert(line);
+                    }
+                    Err(e) => {
+                        report.errors.push(format!(
+                            "failed to merge a change from device {}: {}",
+                            log_device_id, e
+                        ));
+                        held.get_or_insert(line);
+                    }
+                }
+            }
+            let line_count = held.unwrap_or(line_count);
+            if line_count != skip {
+                self.sync_repo
+                    .upsert_sync_cursor(SyncCursorRow {
+                        device_id: log_device_id,
+                        line: line_count as i64,
+                    })
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a change unless the local version is newer.
+    async fn merge_change(
+        &self,
+        change: SyncChange,
+        versions: &mut HashMap<(SyncEntity, Uuid), SyncEntityRow>,
+    ) -> Result<SyncOutcome, AppError> {
+        let key = (change.entity, change.entity_id);
+        if versions
+            .get(&key)
+            .is_some_and(|a| (a.changed_at, a.device_id) >= (change.changed_at, change.device_id))
+        {
+            return Ok(SyncOutcome::Skipped);
+        }
+        let fingerprint = match &change.data {
+            Some(data) => match self
+                .apply(change.entity, change.entity_id, data, versions)
+                .await?
+            {
+                SyncOutcome::Merged => Some(fingerprint(data)?),
+                outcome => return Ok(outcome),
+            },
+            None => {
+                match change.entity {
+                    SyncEntity::Chat => {
+                        if self.chat_repo.delete_chat(change.entity_id).await? > 0 {
+                            self.event_bus.publish(DomainEvent::ChatDeleted {
+                                chat_id: change.entity_id,
+                            });
+                        }
+                    }
+                    SyncEntity::ChatMessage => {
+                        if self.chat_repo.delete_chat_message(change.entity_id).await? > 0 {
+                            self.event_bus.publish(DomainEvent::ChatMessageDeleted {
+                                message_id: change.entity_id,
+                            });
+                        }
+                    }
+                    SyncEntity::AgentConfig => {}
+                }
+                None
+            }
+        };
+        let version = SyncEntityRow {
+            entity: change.entity,
+            entity_id: change.entity_id,
+            fingerprint,
+            changed_at: change.changed_at,
+            device_id: change.device_id,
+        };
+        self.sync_repo.upsert_sync_entity(version.clone()).await?;
+        versions.insert(key, version);
+        Ok(SyncOutcome::Merged)
+    }
+
+    /// Skips changes that can't apply here, like a message whose chat was deleted or a
+    /// config for an agent this device doesn't have, and defers a message whose chat
+    /// hasn't arrived yet. Merged chats and messages are announced like local writes.
+    async fn apply(
+        &self,
+        entity: SyncEntity,
+        id: Uuid,
+        data: &Value,
+        versions: &HashMap<(SyncEntity, Uuid), SyncEntityRow>,
+    ) -> Result<SyncOutcome, AppError> {
+        match entity {
+            SyncEntity::Chat => {
+                let chat = from_value::<SyncChat>(data)?;
+                if self.chat_repo.get_chat(id).await?.is_none() {
+                    self.chat_repo
+                        .create_chat(CreateChat {
+                            id,
+                            title: chat.title.clone(),
+                            created_at: Some(chat.created_at),
+                            project_id: None,
+                        })
+                        .await?;
+                }
+                self.chat_repo
+                    .update_chat(
+                        id,
+                        UpdateChat {
+                            title: Some(chat.title),
+                            pinned: Some(chat.pinned),
+                            starred: Some(chat.starred),
+                            archived: Some(chat.archived),
+                        },
+                    )
+                    .await?;
+                if let Some(chat) = self.chat_repo.get_chat(id).await? {
+                    self.event_bus.publish(DomainEvent::ChatUpdated { chat });
+                }
+            }
+            SyncEntity::ChatMessage => {
+                let message = from_value::<SyncChatMessage>(data)?;
+                let exists = self.chat_repo.get_chat_message(id).await?.is_some();
+                if !exists {
+                    if self.chat_repo.get_chat(message.chat_id).await?.is_none() {
+                        let deleted = versions
+                            .get(&(SyncEntity::Chat, message.chat_id))
+                            .is_some_and(|a| a.fingerprint.is_none());
+                        return Ok(match deleted {
+                            true => SyncOutcome::Skipped,
+                            false => SyncOutcome::Deferred,
+                        });
+                    }
+                    self.chat_repo
+                        .create_chat_message(CreateChatMessage {
+                            created_at: Some(message.created_at),
+                            id,
+                            chat_id: message.chat_id,
+                            role: message.role.clone(),
+                            content: message.content.clone(),
+                            status: message.status.clone(),
+                            kind: message.kind,
+                            tool_calls: message.tool_calls.clone(),
+                            tool_call_id: message.tool_call_id.clone(),
+                            tool_name: message.tool_name.clone(),
+                        })
+                        .await?;
+                }
+                self.chat_repo
+                    .update_chat_message(
+                        id,
+                        UpdateChatMessage {
+                            role: Some(message.role),
+                            content: Some(message.content),
+                            status: Some(message.status),
+                            starred: Some(message.starred),
+                            kind: Some(message.kind),
+                            tool_calls: message.tool_calls,
+                            schema_errors: message.schema_errors,
+                            citations: message.citations,
+                        },
+                    )
+                    .await?;
+                if let Some(message) = self.chat_repo.get_chat_message(id).await? {
+                    self.event_bus.publish(match exists {
+                        true => DomainEvent::ChatMessageUpdated { message },
+                        false => DomainEvent::ChatMessageCreated { message },
+                    });
+                }
+            }
+            SyncEntity::AgentConfig => {
+                let config = from_value::<SyncAgentConfig>(data)?;
+                if self.agent_repo.get_agent(id).await?.is_none() {
+                    return Ok(SyncOutcome::Skipped);
+                }
+                let api_key = match config.api_key {
+                    Some(api_key) if !api_key.is_empty() => {
+                        Some(self.cipher.encrypt_str_base64(&api_key)?)
+                    }
+                    api_key => api_key,
+                };
+                self.agent_repo
+                    .upsert_agent_config(id, UpsertAgentConfig { api_key })
+                    .await?;
+            }
+        }
+        Ok(SyncOutcome::Merged)
+    }
+}
+
+fn sync_chat(chat: &ChatRow) -> SyncChat {
+    SyncChat {
+        title: chat.title.clone(),
+        pinned: chat.pinned,
+        starred: chat.starred,
+        archived: chat.archived_at.is_some(),
+        created_at: chat.created_at,
+    }
+}
+
+fn sync_chat_message(message: &ChatMessageRow) -> SyncChatMessage {
+    SyncChatMessage {
+        chat_id: message.chat_id,
+        role: message.role.clone(),
+        content: message.content.clone(),
+        status: message.status.clone(),
+        starred: message.starred,
+        kind: message.kind,
+        tool_calls: message.tool_calls.as_ref().map(|a| a.0.clone()),
+        tool_call_id: message.tool_call_id.clone(),
+        tool_name: message.tool_name.clone(),
+        schema_errors: message.schema_errors.as_ref().map(|a| a.0.clone()),
+        citations: message.citations.as_ref().map(|a| a.0.clone()),
+        created_at: message.created_at,
+    }
+}
+
+fn from_value<T: DeserializeOwned>(data: &Value) -> Result<T, AppError> {
+    serde_json::from_value(data.clone()).map_err(AppError::from)
+}
+
+/// FNV-1a over the serialized entity; only compared on this device, but it has to
+/// stay stable across app versions.
+fn fingerprint(data: &Value) -> Result<String, AppError> {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in serde_json::to_vec(data)? {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    Ok(format!("{:016x}", hash))
+}
+
+fn log_path(folder: &Path, device_id: Uuid) -> PathBuf {
+    folder.join(format!("{}.{}", device_id, LOG_EXTENSION))
+}
+
+fn now_millis() -> Result<i64, AppError> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64)
+}
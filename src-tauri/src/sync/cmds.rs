@@ -0,0 +1,55 @@
+use std::{path::PathBuf, sync::Arc};
+
+use tauri::State;
+
+use crate::{
+    cipher,
+    common::{entity::sync::SyncStateRow, error::AppError},
+    sync::{repo::SyncRepo, ChatSync, SyncReport},
+};
+
+#[tauri::command]
+pub async fn get_sync_state(
+    sync_repo: State<'_, Arc<dyn SyncRepo>>,
+) -> Result<SyncStateRow, AppError> {
+    sync_repo.get_sync_state().await
+}
+
+/// Sets the shared folder this device syncs through, or turns sync off with `None`.
+#[tauri::command]
+pub async fn update_sync_folder(
+    folder: Option<PathBuf>,
+    sync_repo: State<'_, Arc<dyn SyncRepo>>,
+) -> Result<SyncStateRow, AppError> {
+    let folder = match folder {
+        Some(folder) => {
+            let folder = tokio::fs::canonicalize(&folder).await?;
+            if !tokio::fs::metadata(&folder).await?.is_dir() {
+                return Err(AppError::InvalidSyncFolder(format!(
+                    "not a directory: {}",
+                    folder.display()
+                )));
+            }
+            Some(folder.to_string_lossy().into_owned())
+        }
+        None => None,
+    };
+    sync_repo.update_sync_folder(folder).await?;
+    sync_repo.get_sync_state().await
+}
+
+/// The sync key, to be imported on the other devices syncing through the folder.
+#[tauri::command]
+pub async fn export_sync_key(sync: State<'_, Arc<ChatSync>>) -> Result<String, AppError> {
+    cipher::export_keyring_key(sync.sync_keyring_user())
+}
+
+#[tauri::command]
+pub async fn import_sync_key(key: String, sync: State<'_, Arc<ChatSync>>) -> Result<(), AppError> {
+    cipher::import_keyring_key(sync.sync_keyring_user(), &key)
+}
+
+#[tauri::command]
+pub async fn run_sync(sync: State<'_, Arc<ChatSync>>) -> Result<SyncReport, AppError> {
+    sync.run().await
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+
+use crate::common::{
+    entity::sync::{SyncCursorRow, SyncEntityRow, SyncStateRow},
+    error::AppError,
+};
+
+pub mod sqlite;
+
+#[async_trait]
+pub trait SyncRepo: Send + Sync {
+    async fn get_sync_state(&self) -> Result<SyncStateRow, AppError>;
+    async fn update_sync_folder(&self, folder: Option<String>) -> Result<(), AppError>;
+    async fn update_sync_last_run(&self, last_synced_at: i64) -> Result<(), AppError>;
+    async fn get_sync_entities(&self) -> Result<Vec<SyncEntityRow>, AppError>;
+    async fn upsert_sync_entity(&self, upsert: SyncEntityRow) -> Result<(), AppError>;
+    async fn get_sync_cursors(&self) -> Result<Vec<SyncCursorRow>, AppError>;
+    async fn upsert_sync_cursor(&self, upsert: SyncCursorRow) -> Result<(), AppError>;
+}
@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use sqlx::{Executor, Pool, Sqlite};
+use std::sync::Arc;
+
+use crate::{
+    common::{
+        entity::sync::{SyncCursorRow, SyncEntityRow, SyncStateRow},
+        error::AppError,
+    },
+    sync::repo::SyncRepo,
+};
+
+pub struct SqliteSyncRepo {
+    db_pool: Arc<Pool<Sqlite>>,
+}
+
+impl SqliteSyncRepo {
+    pub fn new(db_pool: Arc<Pool<Sqlite>>) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl SyncRepo for SqliteSyncRepo {
+    async fn get_sync_state(&self) -> Result<SyncStateRow, AppError> {
+        get_sync_state(&*self.db_pool).await
+    }
+
+    async fn update_sync_folder(&self, folder: Option<String>) -> Result<(), AppError> {
+        update_sync_folder(&*self.db_pool, folder).await
+    }
+
+    async fn update_sync_last_run(&self, last_synced_at: i64) -> Result<(), AppError> {
+        update_sync_last_run(&*self.db_pool, last_synced_at).await
+    }
+
+    async fn get_sync_entities(&self) -> Result<Vec<SyncEntityRow>, AppError> {
+        get_sync_entities(&*self.db_pool).await
+    }
+
+    async fn upsert_sync_entity(&self, upsert: SyncEntityRow) -> Result<(), AppError> {
+        upsert_sync_entity(&*self.db_pool, upsert).await
+    }
+
+    async fn get_sync_cursors(&self) -> Result<Vec<SyncCursorRow>, AppError> {
+        get_sync_cursors(&*self.db_pool).await
+    }
+
+    async fn upsert_sync_cursor(&self, upsert: SyncCursorRow) -> Result<(), AppError> {
+        upsert_sync_cursor(&*self.db_pool, upsert).await
+    }
+}
+
+async fn get_sync_state<'a, E>(executor: E) -> Result<SyncStateRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, SyncStateRow>("select * from sync_state where id = 1")
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn update_sync_folder<'a, E>(executor: E, folder: Option<String>) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("update sync_state set folder = ?1 where id = 1")
+        .bind(folder)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn update_sync_last_run<'a, E>(executor: E, last_synced_at: i64) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("update sync_state set last_synced_at = ?1 where id = 1")
+        .bind(last_synced_at)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn get_sync_entities<'a, E>(executor: E) -> Result<Vec<SyncEntityRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, SyncEntityRow>("select * from sync_entities")
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn upsert_sync_entity<'a, E>(executor: E, upsert: SyncEntityRow) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("insert into sync_entities (entity, entity_id, fingerprint, changed_at, device_id) values (?1, ?2, ?3, ?4, ?5) on conflict (entity, entity_id) do update set fingerprint = excluded.fingerprint, changed_at = excluded.changed_at, device_id = excluded.device_id")
+        .bind(upsert.entity)
+        .bind(upsert.entity_id)
+        .bind(&upsert.fingerprint)
+        .bind(upsert.changed_at)
+        .bind(upsert.device_id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn get_sync_cursors<'a, E>(executor: E) -> Result<Vec<SyncCursorRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, SyncCursorRow>("select * from sync_cursors")
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn upsert_sync_cursor<'a, E>(executor: E, upsert: SyncCursorRow) -> Result<(), AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query("insert into sync_cursors (device_id, line) values (?1, ?2) on conflict (device_id) do update set line = excluded.line")
+        .bind(upsert.device_id)
+        .bind(upsert.line)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+pub mod approval;
+pub mod calculator;
+pub mod clock;
+pub mod cmds;
+pub mod code;
+pub mod fs;
+pub mod repo;
+
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use crate::common::{entity::chat::ChatToolCall, error::AppError};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+}
+
+#[async_trait]
+pub trait Tool: Send + Sync {
+    fn definition(&self) -> ToolDefinition;
+    /// Tools with side effects on the user's machine must be approved by the user before
+    /// each call, or once for the whole chat.
+    fn requires_approval(&self) -> bool {
+        false
+    }
+    async fn call(&self, arguments: Value) -> Result<Value, AppError>;
+}
+
+pub struct ToolRegistry {
+    tools: RwLock<HashMap<String, Arc<dyn Tool>>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self {
+            tools: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn register(&self, tool: Arc<dyn Tool>) {
+        let name = tool.definition().name;
+        self.tools.write().await.insert(name, tool);
+    }
+
+    pub async fn unregister(&self, name: &str) {
+        self.tools.write().await.remove(name);
+    }
+
+    pub async fn definitions(&self) -> Vec<ToolDefinition> {
+        let mut definitions = self
+            .tools
+            .read()
+            .await
+            .values()
+            .map(|a| a.definition())
+            .collect::<Vec<_>>();
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        definitions
+    }
+
+    pub async fn requires_approval(&self, name: &str) -> bool {
+        self.tools
+            .read()
+            .await
+            .get(name)
+            .is_some_and(|a| a.requires_approval())
+    }
+
+    pub async fn call(&self, name: &str, arguments: Value) -> Result<Value, AppError> {
+        let tool = self
+            .tools
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| AppError::ToolNotFound(name.to_string()))?;
+        tool.call(arguments).await
+    }
+
+    /// Runs a tool call requested by the model and renders the outcome as the content of
+    /// a tool result message. Failures are reported back to the model rather than aborting
+    /// the turn, so it can correct its arguments or answer without the tool.
+    pub async fn run(&self, call: &ChatToolCall) -> String {
+        let result = match self.call(&call.name, call.arguments.clone()).await {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!("tool {} failed: {}", call.name, e);
+                json!({ "error": e.to_string() })
+            }
+        };
+        match result {
+            Value::String(text) => text,
+            value => value.to_string(),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use serde::Deserialize;
+use tokio::sync::{oneshot, Mutex};
+use uuid::Uuid;
+
+use crate::{
+    chat::repo::ChatRepo,
+    common::{entity::chat::ChatToolCall, error::AppError},
+    event::{DomainEvent, EventBus},
+};
+
+const TOOL_APPROVAL_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolApprovalDecision {
+    Deny,
+    AllowOnce,
+    AllowChat,
+}
+
+/// Tool calls waiting for the user to allow or deny them. A request is announced with a
+/// `ToolApprovalRequested` event and settled by the `respond_tool_approval` command.
+pub struct ToolApprovals {
+    pending: Mutex<HashMap<String, oneshot::Sender<ToolApprovalDecision>>>,
+}
+
+impl ToolApprovals {
+    pub fn new() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits for the user's decision on `call`, unless the tool was already allowed for
+    /// the whole chat. No answer within the timeout counts as a denial.
+    pub async fn request(
+        &self,
+        event_bus: &EventBus,
+        chat_repo: &Arc<dyn ChatRepo>,
+        chat_id: Uuid,
+        message_id: Uuid,
+        call: &ChatToolCall,
+    ) -> Result<bool, AppError> {
+        let approvals = chat_repo.get_chat_tool_approvals(chat_id).await?;
+        if approvals.contains(&call.name) {
+            return Ok(true);
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().await.insert(call.id.clone(), sender);
+        event_bus.publish(DomainEvent::ToolApprovalRequested {
+            chat_id,
+            message_id,
+            call: call.clone(),
+        });
+
+        let decision = match tokio::time::timeout(TOOL_APPROVAL_TIMEOUT, receiver).await {
+            Ok(Ok(decision)) => decision,
+            Ok(Err(_)) => ToolApprovalDecision::Deny,
+            Err(_) => {
+                log::warn!("tool call {} was not approved in time", call.id);
+                self.pending.lock().await.remove(&call.id);
+                ToolApprovalDecision::Deny
+            }
+        };
+        if decision == ToolApprovalDecision::AllowChat {
+            chat_repo
+                .create_chat_tool_approval(chat_id, &call.name)
+                .await?;
+        }
+
+        let approved = decision != ToolApprovalDecision::Deny;
+        event_bus.publish(DomainEvent::ToolApprovalResolved {
+            chat_id,
+            tool_call_id: call.id.clone(),
+            approved,
+        });
+        Ok(approved)
+    }
+
+    pub async fn respond(
+        &self,
+        tool_call_id: &str,
+        decision: ToolApprovalDecision,
+    ) -> Result<(), AppError> {
+        let sender = self
+            .pending
+            .lock()
+            .await
+            .remove(tool_call_id)
+            .ok_or_else(|| AppError::ToolApprovalNotFound(tool_call_id.to_string()))?;
+        sender
+            .send(decision)
+            .map_err(|_| AppError::ToolApprovalNotFound(tool_call_id.to_string()))
+    }
+}
@@ -0,0 +1,353 @@
+use std::{f64::consts, iter::Peekable, str::Chars};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    common::error::AppError,
+    tool::{Tool, ToolDefinition},
+};
+
+/// How deeply parentheses, signs and exponents may nest. The expression comes from the
+/// model, and the parser recurses once per level.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Deserialize)]
+struct CalculatorArgs {
+    expression: String,
+}
+
+pub struct CalculatorTool;
+
+#[async_trait]
+impl Tool for CalculatorTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "calculate".into(),
+            description: "Evaluates an arithmetic expression exactly as written. Supports + - \
+                          * / % ^, parentheses, the constants pi, e and tau, and the functions \
+                          sqrt, abs, sin, cos, tan, asin, acos, atan, ln, log, log2, exp, floor, \
+                          ceil, round, min, max and pow. Angles are in radians."
+                .into(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "expression": {
+                        "type": "string",
+                        "description": "The expression to evaluate, e.g. \"(2 + 3) * sqrt(16)\".",
+                    },
+                },
+                "required": ["expression"],
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, AppError> {
+        let args = serde_json::from_value::<CalculatorArgs>(arguments)?;
+        let result = evaluate(&args.expression)?;
+        Ok(json!({
+            "expression": args.expression,
+            "result": result,
+        }))
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn evaluate(expression: &str) -> Result<f64, AppError> {
+    let tokens = tokenize(expression)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(invalid(format!("unexpected {:?}", token)));
+    }
+    if !value.is_finite() {
+        return Err(invalid("result is not a finite number".into()));
+    }
+    Ok(value)
+}
+
+fn invalid(message: String) -> AppError {
+    AppError::ToolInvalidArguments(message)
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, AppError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' | '.' => tokens.push(Token::Number(number(&mut chars)?)),
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !c.is_alphanumeric() && c != '_' {
+                        break;
+                    }
+                    ident.push(c.to_ascii_lowercase());
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            '*' => {
+                chars.next();
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    tokens.push(Token::Op('^'));
+                } else {
+                    tokens.push(Token::Op('*'));
+                }
+            }
+            '+' | '-' | '/' | '%' | '^' => {
+                chars.next();
+                tokens.push(Token::Op(c));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            _ => return Err(invalid(format!("unexpected character: {}", c))),
+        }
+    }
+    Ok(tokens)
+}
+
+fn number(chars: &mut Peekable<Chars>) -> Result<f64, AppError> {
+    let mut text = String::new();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '0'..='9' | '.' => text.push(c),
+            'e' | 'E' => {
+                let mut lookahead = chars.clone();
+                lookahead.next();
+                let signed = matches!(lookahead.peek(), Some('+' | '-'));
+                if signed {
+                    lookahead.next();
+                }
+                if !lookahead.peek().is_some_and(|a| a.is_ascii_digit()) {
+                    break;
+                }
+                text.push(c);
+                chars.next();
+                if signed {
+                    text.push(chars.next().unwrap_or('+'));
+                }
+                continue;
+            }
+            _ => break,
+        }
+        chars.next();
+    }
+    text.parse::<f64>()
+        .map_err(|_| invalid(format!("invalid number: {}", text)))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), AppError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(invalid(format!("expected {:?}, got {:?}", expected, token))),
+            None => Err(invalid(format!("expected {:?}", expected))),
+        }
+    }
+
+    fn expr(&mut self) -> Result<f64, AppError> {
+        let mut value = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.next();
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, AppError> {
+        let mut value = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/' | '%'))) = self.peek().cloned() {
+            self.next();
+            let rhs = self.unary()?;
+            if op != '*' && rhs == 0.0 {
+                return Err(invalid("division by zero".into()));
+            }
+            value = match op {
+                '*' => value * rhs,
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    /// Every recursive path goes through here, so this is where nesting is limited.
+    fn unary(&mut self) -> Result<f64, AppError> {
+        if self.depth == MAX_DEPTH {
+            return Err(invalid(format!(
+                "expression is nested more than {} levels deep",
+                MAX_DEPTH
+            )));
+        }
+        self.depth += 1;
+        let value = self.signed();
+        self.depth -= 1;
+        value
+    }
+
+    fn signed(&mut self) -> Result<f64, AppError> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.next();
+                Ok(-self.unary()?)
+            }
+            Some(Token::Op('+')) => {
+                self.next();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// Binds tighter than unary minus, so `-2^2` is `-4`, and is right associative.
+    fn power(&mut self) -> Result<f64, AppError> {
+        let base = self.primary()?;
+        if let Some(Token::Op('^')) = self.peek() {
+            self.next();
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<f64, AppError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::LParen) => {
+                let value = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() != Some(&Token::LParen) {
+                    return constant(&name);
+                }
+                self.next();
+                let mut args = vec![self.expr()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.next();
+                    args.push(self.expr()?);
+                }
+                self.expect(Token::RParen)?;
+                function(&name, &args)
+            }
+            Some(token) => Err(invalid(format!("unexpected {:?}", token))),
+            None => Err(invalid("unexpected end of expression".into())),
+        }
+    }
+}
+
+fn constant(name: &str) -> Result<f64, AppError> {
+    match name {
+        "pi" => Ok(consts::PI),
+        "e" => Ok(consts::E),
+        "tau" => Ok(consts::TAU),
+        _ => Err(invalid(format!("unknown constant: {}", name))),
+    }
+}
+
+fn function(name: &str, args: &[f64]) -> Result<f64, AppError> {
+    let value = match (name, args) {
+        ("sqrt", [x]) => x.sqrt(),
+        ("abs", [x]) => x.abs(),
+        ("sin", [x]) => x.sin(),
+        ("cos", [x]) => x.cos(),
+        ("tan", [x]) => x.tan(),
+        ("asin", [x]) => x.asin(),
+        ("acos", [x]) => x.acos(),
+        ("atan", [x]) => x.atan(),
+        ("ln", [x]) => x.ln(),
+        ("log", [x]) => x.log10(),
+        ("log", [x, base]) => x.log(*base),
+        ("log2", [x]) => x.log2(),
+        ("exp", [x]) => x.exp(),
+        ("floor", [x]) => x.floor(),
+        ("ceil", [x]) => x.ceil(),
+        ("round", [x]) => x.round(),
+        ("pow", [x, y]) => x.powf(*y),
+        ("min", [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.min(*b)),
+        ("max", [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.max(*b)),
+        _ => {
+            return Err(invalid(format!(
+                "unknown function or wrong number of arguments: {}({} args)",
+                name,
+                args.len()
+            )))
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_with_precedence() {
+        assert_eq!(evaluate("(2 + 3) * sqrt(16)").unwrap(), 20.0);
+        assert_eq!(evaluate("-2^2").unwrap(), -4.0);
+        assert_eq!(evaluate("2^3^2").unwrap(), 512.0);
+    }
+
+    #[test]
+    fn rejects_deeply_nested_expressions() {
+        let depth = 100_000;
+        for expression in [
+            format!("{}1{}", "(".repeat(depth), ")".repeat(depth)),
+            format!("{}1", "-".repeat(depth)),
+            format!("2{}", "^2".repeat(depth)),
+        ] {
+            assert!(matches!(
+                evaluate(&expression),
+                Err(AppError::ToolInvalidArguments(_))
+            ));
+        }
+        assert!(evaluate(&format!("{}1{}", "(".repeat(32), ")".repeat(32))).is_ok());
+    }
+}
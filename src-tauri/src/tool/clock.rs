@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use chrono::{Local, Utc};
+use serde_json::{json, Value};
+
+use crate::{
+    common::error::AppError,
+    tool::{Tool, ToolDefinition},
+};
+
+pub struct ClockTool;
+
+#[async_trait]
+impl Tool for ClockTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "get_current_time".into(),
+            description: "Returns the current date and time in UTC and in the user's local \
+                          time zone."
+                .into(),
+            parameters: json!({
+                "type": "object",
+                "properties": {},
+            }),
+        }
+    }
+
+    async fn call(&self, _arguments: Value) -> Result<Value, AppError> {
+        let utc = Utc::now();
+        let local = utc.with_timezone(&Local);
+        let time_zone = iana_time_zone::get_timezone()
+            .inspect_err(|e| log::warn!("failed to get time zone: {}", e))
+            .ok();
+        Ok(json!({
+            "utc": utc.to_rfc3339(),
+            "local": local.to_rfc3339(),
+            "timeZone": time_zone,
+            "utcOffset": local.format("%:z").to_string(),
+            "weekday": local.format("%A").to_string(),
+            "unixMs": utc.timestamp_millis(),
+        }))
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    chat::repo::ChatRepo,
+    common::{entity::tool::ToolDirectoryRow, error::AppError},
+    tool::{
+        approval::{ToolApprovalDecision, ToolApprovals},
+        repo::{CreateToolDirectory, ToolRepo},
+        ToolDefinition, ToolRegistry,
+    },
+};
+
+#[tauri::command]
+pub async fn get_tools(
+    tool_registry: State<'_, Arc<ToolRegistry>>,
+) -> Result<Vec<ToolDefinition>, AppError> {
+    Ok(tool_registry.definitions().await)
+}
+
+#[tauri::command]
+pub async fn respond_tool_approval(
+    tool_call_id: String,
+    decision: ToolApprovalDecision,
+    tool_approvals: State<'_, Arc<ToolApprovals>>,
+) -> Result<(), AppError> {
+    tool_approvals.respond(&tool_call_id, decision).await
+}
+
+#[tauri::command]
+pub async fn get_chat_tool_approvals(
+    chat_id: Uuid,
+    chat_repo: State<'_, Arc<dyn ChatRepo>>,
+) -> Result<Vec<String>, AppError> {
+    chat_repo.get_chat_tool_approvals(chat_id).await
+}
+
+#[tauri::command]
+pub async fn revoke_chat_tool_approval(
+    chat_id: Uuid,
+    tool_name: String,
+    chat_repo: State<'_, Arc<dyn ChatRepo>>,
+) -> Result<u64, AppError> {
+    chat_repo
+        .delete_chat_tool_approval(chat_id, &tool_name)
+        .await
+}
+
+#[tauri::command]
+pub async fn get_tool_directories(
+    tool_repo: State<'_, Arc<dyn ToolRepo>>,
+) -> Result<Vec<ToolDirectoryRow>, AppError> {
+    tool_repo.get_tool_directories().await
+}
+
+#[tauri::command]
+pub async fn create_tool_directory(
+    path: String,
+    tool_repo: State<'_, Arc<dyn ToolRepo>>,
+) -> Result<ToolDirectoryRow, AppError> {
+    let path = tokio::fs::canonicalize(&path).await?;
+    if !tokio::fs::metadata(&path).await?.is_dir() {
+        return Err(AppError::ToolInvalidArguments(format!(
+            "not a directory: {}",
+            path.display()
+        )));
+    }
+    tool_repo
+        .create_tool_directory(CreateToolDirectory {
+            id: Uuid::new_v4(),
+            path: path.to_string_lossy().into_owned(),
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_tool_directory(
+    id: Uuid,
+    tool_repo: State<'_, Arc<dyn ToolRepo>>,
+) -> Result<u64, AppError> {
+    tool_repo.delete_tool_directory(id).await
+}
@@ -0,0 +1,247 @@
+use std::{path::Path, process::Stdio, time::Duration};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    process::Command,
+};
+use uuid::Uuid;
+
+use crate::{
+    common::error::AppError,
+    tool::{Tool, ToolDefinition},
+};
+
+const CPU_TIME_LIMIT_SECS: u64 = 10;
+const WALL_TIME_LIMIT: Duration = Duration::from_secs(30);
+const MEMORY_LIMIT_BYTES: u64 = 512 * 1024 * 1024;
+const FILE_SIZE_LIMIT_BYTES: u64 = 16 * 1024 * 1024;
+const OUTPUT_LIMIT_BYTES: usize = 64 * 1024;
+
+#[cfg(target_os = "macos")]
+const MACOS_SANDBOX_PROFILE: &str = "(version 1)(allow default)(deny network*)";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+enum CodeLanguage {
+    Python,
+    Javascript,
+}
+
+#[derive(Deserialize)]
+struct CodeExecutionArgs {
+    language: CodeLanguage,
+    code: String,
+}
+
+/// Runs a snippet in a fresh temporary directory with CPU time, memory, file size and
+/// output limits. Network access is cut off where the OS offers an unprivileged way to
+/// do so: a new network namespace on Linux and `sandbox-exec` on macOS.
+pub struct CodeExecutionTool;
+
+#[async_trait]
+impl Tool for CodeExecutionTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "execute_code".into(),
+            description: format!(
+                "Runs a Python or JavaScript (Node.js) snippet on the user's machine and returns \
+                 its exit code, stdout and stderr. The snippet runs in an empty temporary \
+                 directory without network access, is limited to {} seconds of CPU time and \
+                 its output is truncated after {} KB. Print the values you need.",
+                CPU_TIME_LIMIT_SECS,
+                OUTPUT_LIMIT_BYTES / 1024
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "language": {
+                        "type": "string",
+                        "enum": ["python", "javascript"],
+                    },
+                    "code": {
+                        "type": "string",
+                        "description": "The source code to run.",
+                    },
+                },
+                "required": ["language", "code"],
+            }),
+        }
+    }
+
+    fn requires_approval(&self) -> bool {
+        true
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, AppError> {
+        let args = serde_json::from_value::<CodeExecutionArgs>(arguments)?;
+        let dir = std::env::temp_dir().join(format!("askkit-exec-{}", Uuid::new_v4()));
+        tokio::fs::create_dir(&dir).await?;
+        let result = execute(&dir, &args).await;
+        let _ = tokio::fs::remove_dir_all(&dir).await.inspect_err(|e| {
+            log::warn!("failed to remove {}: {}", dir.display(), e);
+        });
+        result
+    }
+}
+
+async fn execute(dir: &Path, args: &CodeExecutionArgs) -> Result<Value, AppError> {
+    let (file_name, program, interpreter_args) = match args.language {
+        CodeLanguage::Python => (
+            "main.py",
+            if cfg!(windows) { "python" } else { "python3" },
+            vec!["-I".to_string()],
+        ),
+        CodeLanguage::Javascript => (
+            "main.mjs",
+            "node",
+            vec![format!(
+                "--max-old-space-size={}",
+                MEMORY_LIMIT_BYTES / 1024 / 1024
+            )],
+        ),
+    };
+    tokio::fs::write(dir.join(file_name), &args.code).await?;
+
+    let mut command = sandboxed_command(program);
+    command
+        .args(interpreter_args)
+        .arg(file_name)
+        .current_dir(dir)
+        .env_clear()
+        .env("HOME", dir)
+        .env("TMPDIR", dir)
+        .env("TEMP", dir)
+        .env("TMP", dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(path) = std::env::var_os("PATH") {
+        command.env("PATH", path);
+    }
+    #[cfg(unix)]
+    {
+        command.process_group(0);
+        apply_limits(&mut command, matches!(args.language, CodeLanguage::Python));
+    }
+
+    let mut child = command.spawn()?;
+    let pid = child.id();
+    let stdout = tokio::spawn(read_capped(child.stdout.take()));
+    let stderr = tokio::spawn(read_capped(child.stderr.take()));
+    let (status, timed_out) = match tokio::time::timeout(WALL_TIME_LIMIT, child.wait()).await {
+        Ok(status) => (Some(status?), false),
+        Err(_) => {
+            kill(&mut child, pid).await;
+            (None, true)
+        }
+    };
+    // Background processes started by the snippet would keep the pipes open.
+    kill(&mut child, pid).await;
+    let (stdout, stdout_truncated) = stdout.await.unwrap_or_default();
+    let (stderr, stderr_truncated) = stderr.await.unwrap_or_default();
+
+    #[cfg(unix)]
+    let signal = status.and_then(|a| std::os::unix::process::ExitStatusExt::signal(&a));
+    #[cfg(not(unix))]
+    let signal: Option<i32> = None;
+
+    Ok(json!({
+        "language": args.language,
+        "exitCode": status.and_then(|a| a.code()),
+        "signal": signal,
+        "stdout": String::from_utf8_lossy(&stdout),
+        "stderr": String::from_utf8_lossy(&stderr),
+        "timedOut": timed_out,
+        "truncated": stdout_truncated || stderr_truncated,
+    }))
+}
+
+#[cfg(target_os = "macos")]
+fn sandboxed_command(program: &str) -> Command {
+    let mut command = Command::new("sandbox-exec");
+    command.args(["-p", MACOS_SANDBOX_PROFILE, program]);
+    command
+}
+
+#[cfg(not(target_os = "macos"))]
+fn sandboxed_command(program: &str) -> Command {
+    Command::new(program)
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+#[cfg(unix)]
+fn apply_limits(command: &mut Command, limit_address_space: bool) {
+    // Safety: the closure runs between fork and exec and only makes async-signal-safe
+    // system calls.
+    unsafe {
+        command.pre_exec(move || {
+            set_limit(libc::RLIMIT_CPU, CPU_TIME_LIMIT_SECS)?;
+            set_limit(libc::RLIMIT_FSIZE, FILE_SIZE_LIMIT_BYTES)?;
+            set_limit(libc::RLIMIT_CORE, 0)?;
+            // V8 reserves far more address space than it uses, so node is capped through
+            // its heap size flag instead.
+            if limit_address_space {
+                set_limit(libc::RLIMIT_AS, MEMORY_LIMIT_BYTES)?;
+            }
+            // Fails where unprivileged user namespaces are disabled; the snippet then runs
+            // with the host network.
+            #[cfg(target_os = "linux")]
+            libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET);
+            Ok(())
+        });
+    }
+}
+
+#[cfg(unix)]
+fn set_limit(resource: RlimitResource, value: u64) -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(unix), allow(unused_variables))]
+async fn kill(child: &mut tokio::process::Child, pid: Option<u32>) {
+    #[cfg(unix)]
+    if let Some(pid) = pid {
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+    }
+    let _ = child.kill().await;
+}
+
+/// Keeps the first `OUTPUT_LIMIT_BYTES` and drains the rest so the process never blocks
+/// on a full pipe.
+async fn read_capped<R: AsyncRead + Unpin>(reader: Option<R>) -> (Vec<u8>, bool) {
+    let mut output = Vec::new();
+    let mut truncated = false;
+    let Some(mut reader) = reader else {
+        return (output, truncated);
+    };
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        let remaining = OUTPUT_LIMIT_BYTES - output.len();
+        if n > remaining {
+            truncated = true;
+        }
+        output.extend_from_slice(&buf[..n.min(remaining)]);
+    }
+    (output, truncated)
+}
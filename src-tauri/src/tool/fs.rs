@@ -0,0 +1,203 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    chat::document::{extract_text, guess_mime_type},
+    common::error::AppError,
+    tool::{repo::ToolRepo, Tool, ToolDefinition},
+};
+
+const FILE_SIZE_LIMIT_BYTES: u64 = 20 * 1024 * 1024;
+const FILE_CONTENT_CHARS: usize = 100_000;
+const DIRECTORY_ENTRIES_LIMIT: usize = 500;
+
+#[derive(Deserialize)]
+struct ReadFileArgs {
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct ListDirectoryArgs {
+    path: Option<String>,
+}
+
+/// Reads text files, PDFs and Word documents that live under one of the directories the
+/// user allowed.
+pub struct ReadFileTool {
+    tool_repo: Arc<dyn ToolRepo>,
+}
+
+impl ReadFileTool {
+    pub fn new(tool_repo: Arc<dyn ToolRepo>) -> Self {
+        Self { tool_repo }
+    }
+}
+
+#[async_trait]
+impl Tool for ReadFileTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "read_file".into(),
+            description: "Reads a file on the user's machine and returns its text. Only files \
+                          under the directories the user allowed can be read; call \
+                          list_directory without a path to see them."
+                .into(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute path of the file.",
+                    },
+                },
+                "required": ["path"],
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, AppError> {
+        let args = serde_json::from_value::<ReadFileArgs>(arguments)?;
+        let path = resolve_allowed_path(&self.tool_repo, &args.path).await?;
+        let metadata = tokio::fs::metadata(&path).await?;
+        if !metadata.is_file() {
+            return Err(AppError::ToolInvalidArguments(format!(
+                "not a file: {}",
+                path.display()
+            )));
+        }
+        if metadata.len() > FILE_SIZE_LIMIT_BYTES {
+            return Err(AppError::ToolInvalidArguments(format!(
+                "file is larger than {} MB: {}",
+                FILE_SIZE_LIMIT_BYTES / 1024 / 1024,
+                path.display()
+            )));
+        }
+
+        let data = tokio::fs::read(&path).await?;
+        let name = path.to_string_lossy();
+        let text = match guess_mime_type(&name) {
+            Some(mime_type) => extract_text(mime_type, &data)?,
+            None => String::from_utf8(data).map_err(|_| {
+                AppError::ToolInvalidArguments(format!("not a text file: {}", name))
+            })?,
+        };
+        let total_chars = text.chars().count();
+        Ok(json!({
+            "path": path,
+            "content": text.chars().take(FILE_CONTENT_CHARS).collect::<String>(),
+            "truncated": total_chars > FILE_CONTENT_CHARS,
+        }))
+    }
+}
+
+/// Lists the entries of an allowed directory, or the allowed directories themselves when
+/// no path is given.
+pub struct ListDirectoryTool {
+    tool_repo: Arc<dyn ToolRepo>,
+}
+
+impl ListDirectoryTool {
+    pub fn new(tool_repo: Arc<dyn ToolRepo>) -> Self {
+        Self { tool_repo }
+    }
+}
+
+#[async_trait]
+impl Tool for ListDirectoryTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_directory".into(),
+            description: "Lists the files and subdirectories of a directory on the user's \
+                          machine. Without a path, returns the directories the user allowed \
+                          access to."
+                .into(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute path of the directory.",
+                    },
+                },
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, AppError> {
+        let args = serde_json::from_value::<ListDirectoryArgs>(arguments)?;
+        let path = match args.path {
+            Some(path) => resolve_allowed_path(&self.tool_repo, &path).await?,
+            None => {
+                let directories = self
+                    .tool_repo
+                    .get_tool_directories()
+                    .await?
+                    .into_iter()
+                    .map(|a| a.path)
+                    .collect::<Vec<_>>();
+                return Ok(json!({ "directories": directories }));
+            }
+        };
+
+        let mut entries = Vec::new();
+        let mut read_dir = tokio::fs::read_dir(&path).await?;
+        while let Some(entry) = read_dir.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            let kind = if file_type.is_dir() {
+                "directory"
+            } else if file_type.is_symlink() {
+                "symlink"
+            } else {
+                "file"
+            };
+            let size = match file_type.is_file() {
+                true => entry.metadata().await.ok().map(|a| a.len()),
+                false => None,
+            };
+            entries.push(json!({
+                "name": entry.file_name().to_string_lossy(),
+                "kind": kind,
+                "size": size,
+            }));
+        }
+        entries.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        let total = entries.len();
+        entries.truncate(DIRECTORY_ENTRIES_LIMIT);
+        Ok(json!({
+            "path": path,
+            "entries": entries,
+            "truncated": total > DIRECTORY_ENTRIES_LIMIT,
+        }))
+    }
+}
+
+/// Resolves symlinks and `..` before comparing, so a path can't escape the allowed
+/// directories through either.
+async fn resolve_allowed_path(
+    tool_repo: &Arc<dyn ToolRepo>,
+    path: &str,
+) -> Result<PathBuf, AppError> {
+    if !Path::new(path).is_absolute() {
+        return Err(AppError::ToolInvalidArguments(format!(
+            "path must be absolute: {}",
+            path
+        )));
+    }
+    let resolved = tokio::fs::canonicalize(path).await?;
+    for directory in tool_repo.get_tool_directories().await? {
+        let directory = match tokio::fs::canonicalize(&directory.path).await {
+            Ok(directory) => directory,
+            Err(_) => continue,
+        };
+        if resolved.starts_with(&directory) {
+            return Ok(resolved);
+        }
+    }
+    Err(AppError::ToolPathNotAllowed(path.to_string()))
+}
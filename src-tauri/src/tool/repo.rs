@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::common::{entity::tool::ToolDirectoryRow, error::AppError};
+
+pub mod sqlite;
+
+pub struct CreateToolDirectory {
+    pub id: Uuid,
+    pub path: String,
+}
+
+#[async_trait]
+pub trait ToolRepo: Send + Sync {
+    async fn get_tool_directories(&self) -> Result<Vec<ToolDirectoryRow>, AppError>;
+    async fn create_tool_directory(
+        &self,
+        create: CreateToolDirectory,
+    ) -> Result<ToolDirectoryRow, AppError>;
+    async fn delete_tool_directory(&self, id: Uuid) -> Result<u64, AppError>;
+}
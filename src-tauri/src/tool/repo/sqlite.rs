@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use sqlx::{Executor, Pool, Sqlite};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    common::{entity::tool::ToolDirectoryRow, error::AppError},
+    tool::repo::{CreateToolDirectory, ToolRepo},
+};
+
+pub struct SqliteToolRepo {
+    db_pool: Arc<Pool<Sqlite>>,
+}
+
+impl SqliteToolRepo {
+    pub fn new(db_pool: Arc<Pool<Sqlite>>) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl ToolRepo for SqliteToolRepo {
+    async fn get_tool_directories(&self) -> Result<Vec<ToolDirectoryRow>, AppError> {
+        get_tool_directories(&*self.db_pool).await
+    }
+
+    async fn create_tool_directory(
+        &self,
+        create: CreateToolDirectory,
+    ) -> Result<ToolDirectoryRow, AppError> {
+        create_tool_directory(&*self.db_pool, create).await
+    }
+
+    async fn delete_tool_directory(&self, id: Uuid) -> Result<u64, AppError> {
+        delete_tool_directory(&*self.db_pool, id).await
+    }
+}
+
+async fn get_tool_directories<'a, E>(executor: E) -> Result<Vec<ToolDirectoryRow>, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ToolDirectoryRow>("select * from tool_directories order by path asc")
+        .fetch_all(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn create_tool_directory<'a, E>(
+    executor: E,
+    create: CreateToolDirectory,
+) -> Result<ToolDirectoryRow, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let created_at: i64 = sqlx::query_scalar(
+        "insert into tool_directories (id, path) values (?1, ?2) returning created_at",
+    )
+    .bind(create.id)
+    .bind(&create.path)
+    .fetch_one(executor)
+    .await
+    .map_err(AppError::from)?;
+    Ok(ToolDirectoryRow {
+        created_at,
+        id: create.id,
+        path: create.path,
+    })
+}
+
+async fn delete_tool_directory<'a, E>(executor: E, id: Uuid) -> Result<u64, AppError>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    let result = sqlx::query("delete from tool_directories where id = ?1")
+        .bind(id)
+        .execute(executor)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
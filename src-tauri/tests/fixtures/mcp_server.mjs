@@ -0,0 +1,76 @@
+// Minimal MCP server used to exercise the stdio client. Run with `node mcp_server.mjs`.
+import { createInterface } from "node:readline";
+
+const tools = [
+  {
+    name: "echo",
+    description: "Returns the given text.",
+    inputSchema: {
+      type: "object",
+      properties: { text: { type: "string" } },
+      required: ["text"],
+    },
+  },
+  {
+    name: "add",
+    description: "Adds two numbers.",
+    inputSchema: {
+      type: "object",
+      properties: { a: { type: "number" }, b: { type: "number" } },
+      required: ["a", "b"],
+    },
+  },
+];
+
+const handlers = {
+  initialize: () => ({
+    protocolVersion: "2025-06-18",
+    capabilities: { tools: {}, prompts: {}, resources: {} },
+    serverInfo: { name: "askkit-fixture", version: "1.0.0" },
+  }),
+  ping: () => ({}),
+  "tools/list": () => ({ tools }),
+  "tools/call": ({ name, arguments: args }) => {
+    switch (name) {
+      case "echo":
+        return { content: [{ type: "text", text: String(args.text) }] };
+      case "add":
+        return { content: [{ type: "text", text: String(args.a + args.b) }] };
+      default:
+        return { content: [{ type: "text", text: `unknown tool: ${name}` }], isError: true };
+    }
+  },
+  "prompts/list": () => ({
+    prompts: [
+      {
+        name: "greet",
+        description: "Greets someone.",
+        arguments: [{ name: "name", required: true }],
+      },
+    ],
+  }),
+  "prompts/get": ({ arguments: args }) => ({
+    messages: [{ role: "user", content: { type: "text", text: `Say hello to ${args.name}.` } }],
+  }),
+  "resources/list": () => ({
+    resources: [{ uri: "fixture://notes", name: "notes", mimeType: "text/plain" }],
+  }),
+  "resources/read": ({ uri }) => ({
+    contents: [{ uri, mimeType: "text/plain", text: "fixture notes" }],
+  }),
+};
+
+const send = (message) => process.stdout.write(JSON.stringify(message) + "\n");
+
+createInterface({ input: process.stdin }).on("line", (line) => {
+  const message = JSON.parse(line);
+  if (message.id === undefined) {
+    return;
+  }
+  const handler = handlers[message.method];
+  if (!handler) {
+    send({ jsonrpc: "2.0", id: message.id, error: { code: -32601, message: "Method not found" } });
+    return;
+  }
+  send({ jsonrpc: "2.0", id: message.id, result: handler(message.params ?? {}) });
+});